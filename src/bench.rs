@@ -0,0 +1,101 @@
+// diagnostics that time the slow parts of jolly, so that they can be
+// measured on a real config file
+
+use std::time::{Duration, Instant};
+
+use crate::{icon, settings::Settings, store::Store};
+
+// how long it took to load the icon of one entry
+#[derive(Debug)]
+pub struct IconTiming {
+    pub name: String,
+    pub kind: &'static str,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+// load the icon of every entry in the store, one at a time, on the
+// current thread
+pub fn icons(store: &Store, settings: &Settings) -> Vec<IconTiming> {
+    store
+        .entries()
+        .filter(|entry| entry.has_icon())
+        .map(|entry| {
+            let start = Instant::now();
+            let result = icon::try_load_icon(&settings.ui.icon, entry.icontype().clone());
+            IconTiming {
+                name: entry.name().to_string(),
+                kind: entry.icontype().kind(),
+                elapsed: start.elapsed(),
+                error: result.err(),
+            }
+        })
+        .collect()
+}
+
+// summarize the timings, with the slowest icons first
+pub fn report(timings: &[IconTiming]) -> String {
+    let mut sorted: Vec<_> = timings.iter().collect();
+    sorted.sort_by(|a, b| b.elapsed.cmp(&a.elapsed));
+
+    let mut lines: Vec<_> = sorted
+        .iter()
+        .map(|t| {
+            let status = match &t.error {
+                Some(e) => format!("FAILED: {e}"),
+                None => "ok".to_string(),
+            };
+            format!(
+                "{:>10.3} ms  {:<8} {}  ({status})",
+                t.elapsed.as_secs_f64() * 1000.0,
+                t.kind,
+                t.name
+            )
+        })
+        .collect();
+
+    let total: Duration = timings.iter().map(|t| t.elapsed).sum();
+    let failed = timings.iter().filter(|t| t.error.is_some()).count();
+    lines.push(format!(
+        "loaded {} icons in {:.3} ms, {failed} failed",
+        timings.len(),
+        total.as_secs_f64() * 1000.0
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store;
+
+    #[test]
+    fn bench_fixture_store() {
+        let toml = r#"['jolly']
+                      location = 'Cargo.toml'
+
+                      ['missing']
+                      location = 'nonexistent_file.txt'
+
+                      ['custom']
+                      location = 'Cargo.toml'
+                      icon = 'nonexistent_icon.bmp'"#;
+        let store = store::tests::parse_store(toml).unwrap();
+
+        let timings = icons(&store, &Settings::default());
+        assert_eq!(timings.len(), 3);
+        assert_eq!(timings[0].name, "jolly");
+        assert_eq!(timings[0].kind, "file");
+        assert!(timings[1].error.is_some());
+        assert_eq!(timings[2].kind, "custom");
+
+        let report = report(&timings);
+        assert_eq!(report.lines().count(), 4);
+        assert!(report.contains("missing  (FAILED: "));
+        assert!(report
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("loaded 3 icons in "));
+    }
+}
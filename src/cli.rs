@@ -1,4 +1,5 @@
 // command line parsing for jolly
+use crate::export;
 use std::process::ExitCode;
 
 fn help() {
@@ -13,6 +14,11 @@ Usage: {exe} [OPTIONS] [CONFIG FILE]
 Options:
 -V, --version	Print version info and exit
 -h, --help	Print this help and exit
+--export json	Print the parsed entries as JSON and exit
+--fields LIST	Comma separated fields to export, such as name,location
+--bench-icons	Time loading the icon of every entry and exit
+--explain QUERY NAME	Show how the entry NAME is scored for QUERY and exit
+--dump-theme	Print the theme as a [config.ui.theme] table and exit
 
 Use the optional parameter [CONFIG FILE] to use a non-default config file
 
@@ -34,15 +40,29 @@ fn err_help() {
     eprintln!("Try '{name} --help' for more information");
 }
 
+// formats that the store can be exported as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+}
+
 #[derive(Default)]
 pub struct ParsedArgs {
     pub config: Option<String>,
+    pub export: Option<ExportFormat>,
+    // which fields are exported, if not all of them
+    pub fields: Option<Vec<export::Field>>,
+    pub bench_icons: bool,
+    // query and entry name to explain the score of
+    pub explain: Option<(String, String)>,
+    pub dump_theme: bool,
 }
 
 pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<ParsedArgs, ExitCode> {
     let mut parsed_args = ParsedArgs::default();
 
-    for arg in args.skip(1) {
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
         if arg == "-V" || arg == "-v" || arg == "--version" {
             version();
             return Err(ExitCode::SUCCESS);
@@ -53,6 +73,62 @@ pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<ParsedArgs, Exi
             return Err(ExitCode::SUCCESS);
         }
 
+        if arg == "--export" {
+            match args.next().as_deref() {
+                Some("json") => parsed_args.export = Some(ExportFormat::Json),
+                Some(format) => {
+                    eprintln!("Unsupported export format '{format}'");
+                    err_help();
+                    return Err(ExitCode::FAILURE);
+                }
+                None => {
+                    eprintln!("Missing export format");
+                    err_help();
+                    return Err(ExitCode::FAILURE);
+                }
+            }
+            continue;
+        }
+
+        if arg == "--fields" {
+            match args.next().as_deref().map(export::parse_fields) {
+                Some(Ok(fields)) => parsed_args.fields = Some(fields),
+                Some(Err(e)) => {
+                    eprintln!("{e}");
+                    err_help();
+                    return Err(ExitCode::FAILURE);
+                }
+                None => {
+                    eprintln!("Missing list of fields");
+                    err_help();
+                    return Err(ExitCode::FAILURE);
+                }
+            }
+            continue;
+        }
+
+        if arg == "--explain" {
+            match (args.next(), args.next()) {
+                (Some(query), Some(name)) => parsed_args.explain = Some((query, name)),
+                _ => {
+                    eprintln!("--explain needs a query and the name of an entry");
+                    err_help();
+                    return Err(ExitCode::FAILURE);
+                }
+            }
+            continue;
+        }
+
+        if arg == "--bench-icons" {
+            parsed_args.bench_icons = true;
+            continue;
+        }
+
+        if arg == "--dump-theme" {
+            parsed_args.dump_theme = true;
+            continue;
+        }
+
         if arg.starts_with("-") {
             eprintln!("Invalid option '{arg}'");
             err_help();
@@ -67,5 +143,11 @@ pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<ParsedArgs, Exi
             return Err(ExitCode::FAILURE);
         }
     }
+
+    if parsed_args.fields.is_some() && parsed_args.export.is_none() {
+        eprintln!("--fields can only be used with --export");
+        err_help();
+        return Err(ExitCode::FAILURE);
+    }
     Ok(parsed_args)
 }
@@ -3,13 +3,27 @@
 // settings are parameters for the program
 // store represents the links that are stored in jolly
 
-use crate::{error::Error, settings::Settings, store::Store};
+use crate::{error::Error, platform, settings::Settings, store::Store};
+use iced::futures::channel::mpsc;
+use iced::futures::{executor, stream, SinkExt, Stream, StreamExt};
 use serde::Deserialize;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
 use std::{fs, path};
 use toml;
 
 pub const LOGFILE_NAME: &str = "jolly.toml";
 
+// directory next to the config file with extra files of entries
+pub const FRAGMENT_DIR: &str = "jolly.d";
+
+// how often the config file is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+// how long to wait for a remote config file before falling back to
+// the cached copy
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(5);
+
 // helper enum to allow decoding a scalar into a single vec
 // original hint from here:
 // https://github.com/Mingun/ksc-rs/blob/8532f701e660b07b6d2c74963fdc0490be4fae4b/src/parser.rs#L18-L42
@@ -46,6 +60,10 @@ pub fn one_or_many<'de, T: Deserialize<'de>, D: serde::Deserializer<'de>>(
 pub struct Config {
     pub settings: Settings,
     pub store: Result<Store, Error>,
+    // problems with the config that did not stop it from loading
+    pub warnings: Vec<String>,
+    // the config file that was loaded, if any
+    pub path: Option<path::PathBuf>,
 }
 
 impl Default for Config {
@@ -53,31 +71,54 @@ impl Default for Config {
         Self {
             settings: Settings::default(),
             store: Err(Error::CustomError("".to_string())),
+            warnings: Vec::new(),
+            path: None,
         }
     }
 }
 
 impl Config {
     pub fn custom_load(path: String) -> Self {
-        let config = load_path(path);
+        let config = if is_remote(&path) {
+            load_remote(&path)
+        } else {
+            load_path(path)
+        };
         match config {
             Err(e) => Self {
                 settings: Default::default(),
                 store: Err(e),
+                warnings: Vec::new(),
+                path: None,
             },
             Ok(c) => c,
         }
     }
 
+    // exit code for jolly once its window is closed. If the store
+    // could not be loaded, the error is still shown in the window,
+    // but jolly exits with a failure so that scripts can detect it
+    pub fn exit_code(&self) -> ExitCode {
+        if self.store.is_ok() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    }
+
     pub fn load() -> Self {
         match get_logfile().map(load_path) {
             Ok(config) => config.unwrap_or_else(|e| Self {
                 settings: Settings::default(),
                 store: Err(e),
+                warnings: Vec::new(),
+                path: None,
             }),
             Err(e) => Self {
                 settings: Settings::default(),
                 store: Err(e),
+                warnings: Vec::new(),
+                path: None,
             },
         }
     }
@@ -101,13 +142,151 @@ fn get_logfile() -> Result<path::PathBuf, Error> {
 }
 
 pub fn load_path<P: AsRef<path::Path>>(path: P) -> Result<Config, Error> {
-    let txt = fs::read_to_string(&path)
-        .map_err(|e| Error::IoError(Some(path.as_ref().display().to_string()), e))?;
-    load_txt(&txt)
-        .map_err(|e| Error::ContextParseError(path.as_ref().display().to_string(), e.to_string()))
+    let txt = read_config(path.as_ref())?;
+
+    let config_dir = path.as_ref().parent().unwrap_or(path::Path::new(""));
+    let (fragments, fragment_warnings) = load_fragments(&config_dir.join(FRAGMENT_DIR))?;
+
+    // relative paths in entries are relative to the config file, not
+    // to where jolly was started from
+    let base_dir = config_dir.canonicalize().ok();
+    let mut config = load_txt_with_fragments(
+        &txt,
+        platform::hostname().as_deref(),
+        fragments,
+        base_dir.as_deref(),
+        false,
+    )
+    .map_err(|e| Error::ContextParseError(path.as_ref().display().to_string(), e.to_string()))?;
+    config.warnings.extend(fragment_warnings);
+
+    let path = path
+        .as_ref()
+        .canonicalize()
+        .unwrap_or(path.as_ref().to_path_buf());
+
+    // let the user open this config file from inside jolly
+    if let (Ok(store), Some(trigger)) = (&mut config.store, config.settings.config_trigger()) {
+        store.add_config_entry(trigger, &path);
+    }
+
+    config.path = Some(path);
+
+    Ok(config)
 }
 
-fn load_txt(txt: &str) -> Result<Config, Error> {
+// load only the settings of the config file at location, or of the
+// default config file. The entries are not built, so the settings
+// can be used even when the entries have errors
+pub fn load_settings(location: Option<&str>) -> Result<Settings, Error> {
+    let txt = match location {
+        Some(url) if is_remote(url) => fetch_remote(url)?.0,
+        Some(path) => read_config(path::Path::new(path))?,
+        None => read_config(&get_logfile()?)?,
+    };
+    let (settings, _) = parse_txt(&txt, platform::hostname().as_deref())?;
+    parse_settings(settings).map(|(settings, _)| settings)
+}
+
+fn read_config(path: &path::Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|e| Error::IoError(Some(path.display().to_string()), e))
+}
+
+// config files at http and https urls are fetched instead of read
+// from disk. Only https urls can actually be fetched
+pub fn is_remote(location: &str) -> bool {
+    url::Url::parse(location).map_or(false, |u| matches!(u.scheme(), "http" | "https"))
+}
+
+// load a config file from a url. Remote config files cannot have
+// fragment files or a remote store of their own, and are not watched
+// for changes
+pub fn load_remote(url: &str) -> Result<Config, Error> {
+    let (txt, warning) = fetch_remote(url)?;
+    let mut config = load_txt_with_fragments(
+        &txt,
+        platform::hostname().as_deref(),
+        toml::Table::new(),
+        None,
+        true,
+    )
+    .map_err(|e| Error::ContextParseError(url.to_string(), e.to_string()))?;
+    config.warnings.extend(warning);
+    Ok(config)
+}
+
+// fetch a remote config file over https, using the local cache of it
+// if the fetch fails. Plain http is refused, since anyone on the
+// network could then add entries that run commands
+fn fetch_remote(url: &str) -> Result<(String, Option<String>), Error> {
+    if url::Url::parse(url).map_or(true, |u| u.scheme() != "https") {
+        return Err(Error::CustomError(format!(
+            "Refusing to fetch {url}: remote config files must use https"
+        )));
+    }
+
+    let cache = dirs::cache_dir().map(|d| d.join("jolly").join(remote_cache_name(url)));
+    fetch_cached(url, cache.as_deref(), |url| {
+        ureq::AgentBuilder::new()
+            .timeout(REMOTE_TIMEOUT)
+            .build()
+            .get(url)
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())
+    })
+}
+
+// name of the file that a remote config file is cached in
+fn remote_cache_name(url: &str) -> String {
+    let name: String = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{name}.toml")
+}
+
+// get the text of a remote config file with fetch. A successful fetch
+// is saved to cache, and if the fetch fails, the text saved in cache
+// is used instead along with a warning
+fn fetch_cached<F>(
+    url: &str,
+    cache: Option<&path::Path>,
+    fetch: F,
+) -> Result<(String, Option<String>), Error>
+where
+    F: FnOnce(&str) -> Result<String, String>,
+{
+    let err = match fetch(url) {
+        Ok(txt) => {
+            if let Some(cache) = cache {
+                let saved = cache
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(cache, &txt));
+                if let Err(e) = saved {
+                    ::log::warn!("Could not cache {url} in {}: {e}", cache.display());
+                }
+            }
+            return Ok((txt, None));
+        }
+        Err(e) => e,
+    };
+
+    match cache.map(fs::read_to_string) {
+        Some(Ok(txt)) => Ok((
+            txt,
+            Some(format!("Could not fetch {url}, using a cached copy: {err}")),
+        )),
+        _ => Err(Error::CustomError(format!("Could not fetch {url}: {err}"))),
+    }
+}
+
+// split the text of a config file into its settings and the table
+// of its entries. If the file has a host section for host, it is
+// merged in first
+fn parse_txt(txt: &str, host: Option<&str>) -> Result<(Option<toml::Value>, toml::Table), Error> {
     let value: toml::Value =
         toml::from_str(txt).map_err(|e| Error::ParseError(e.message().to_string()))?;
 
@@ -116,9 +295,61 @@ fn load_txt(txt: &str) -> Result<Config, Error> {
         _ => return Err(Error::ParseError("entry is not a Table".to_string())),
     };
 
-    // if we have a settings entry use it, otherwise deserialize something empty and rely on serde defaults
+    if let Some(hosts) = parsed_config.remove("host") {
+        if let Some(section) = host_section(hosts, host)? {
+            for (key, value) in section {
+                merge_value(&mut parsed_config, key, value);
+            }
+        }
+    }
+
+    let settings = parsed_config.remove("config");
+    Ok((settings, parsed_config))
+}
+
+// find the [host.<hostname>] section for host. Hostnames are not case sensitive
+fn host_section(hosts: toml::Value, host: Option<&str>) -> Result<Option<toml::Table>, Error> {
+    let hosts = match hosts {
+        toml::Value::Table(t) => t,
+        _ => return Err(Error::ParseError("host is not a Table".to_string())),
+    };
+
+    let mut matched = None;
+    for (name, section) in hosts {
+        let section = match section {
+            toml::Value::Table(t) => t,
+            _ => {
+                return Err(Error::ParseError(format!(
+                    "host section '{name}' is not a Table"
+                )))
+            }
+        };
+        if host.map_or(false, |h| h.eq_ignore_ascii_case(&name)) {
+            matched = Some(section);
+        }
+    }
+    Ok(matched)
+}
+
+// add value to table, recursively merging tables so that host
+// sections can override single settings
+fn merge_value(table: &mut toml::Table, key: String, value: toml::Value) {
+    match (table.get_mut(&key), value) {
+        (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+            for (k, v) in overlay {
+                merge_value(base, k, v);
+            }
+        }
+        (_, value) => {
+            table.insert(key, value);
+        }
+    }
+}
 
-    let mut settings = match parsed_config.remove("config") {
+// returns the settings, along with any warnings about them
+fn parse_settings(value: Option<toml::Value>) -> Result<(Settings, Vec<String>), Error> {
+    // if we have a settings entry use it, otherwise deserialize something empty and rely on serde defaults
+    let mut settings = match value {
         Some(config) => {
             Settings::deserialize(config).map_err(|e| Error::ParseError(e.message().to_string()))?
         }
@@ -126,11 +357,226 @@ fn load_txt(txt: &str) -> Result<Config, Error> {
     };
 
     settings.ui.propagate();
+    settings.search.fuzzy = settings.ui.fuzzy;
+    let warnings = settings.validate();
+    settings.clamp();
+    Ok((settings, warnings))
+}
+
+#[cfg(test)]
+fn load_txt(txt: &str) -> Result<Config, Error> {
+    load_txt_for_host(txt, platform::hostname().as_deref())
+}
+
+// read the entries of every .toml file in dir, in filename
+// order. Entries in later files replace earlier entries with the same
+// name. A missing directory has no entries
+fn load_fragments(dir: &path::Path) -> Result<(toml::Table, Vec<String>), Error> {
+    let io_error = |e| Error::IoError(Some(dir.display().to_string()), e);
+
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((toml::Table::new(), Vec::new()))
+        }
+        Err(e) => return Err(io_error(e)),
+    };
+
+    let mut paths = Vec::new();
+    for dirent in read_dir {
+        let path = dirent.map_err(io_error)?.path();
+        if path.is_file() && path.extension().map_or(false, |e| e == "toml") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut entries = toml::Table::new();
+    let mut warnings = Vec::new();
+    for path in paths {
+        let txt = fs::read_to_string(&path)
+            .map_err(|e| Error::IoError(Some(path.display().to_string()), e))?;
+        let (settings, fragment) = parse_txt(&txt, platform::hostname().as_deref())
+            .map_err(|e| Error::ContextParseError(path.display().to_string(), e.to_string()))?;
+
+        if settings.is_some() {
+            warnings.push(format!(
+                "Settings in {} are ignored, they can only be set in {LOGFILE_NAME}",
+                path.display()
+            ));
+        }
+        entries.extend(fragment);
+    }
+    Ok((entries, warnings))
+}
+
+#[cfg(test)]
+fn load_txt_for_host(txt: &str, host: Option<&str>) -> Result<Config, Error> {
+    load_txt_with_fragments(txt, host, toml::Table::new(), None, false)
+}
+
+// load a config file, adding the entries from its fragment files. A
+// config file that was itself fetched cannot add a remote store
+fn load_txt_with_fragments(
+    txt: &str,
+    host: Option<&str>,
+    fragments: toml::Table,
+    base_dir: Option<&path::Path>,
+    fetched: bool,
+) -> Result<Config, Error> {
+    let (settings, mut parsed_config) = parse_txt(txt, host)?;
+    let (mut settings, mut warnings) = parse_settings(settings)?;
+
+    if fetched && settings.remote_store.take().is_some() {
+        warnings.push("remote_store is ignored in remote config files".to_string());
+    }
+
+    // entries from a shared remote file come first, so that local
+    // entries can replace them. If the remote file cannot be loaded,
+    // the local entries are still used
+    if let Some(url) = &settings.remote_store {
+        let remote = fetch_remote(url).and_then(|(remote, warning)| {
+            let (_, entries) = parse_txt(&remote, host)
+                .map_err(|e| Error::ContextParseError(url.clone(), e.to_string()))?;
+            Ok((entries, warning))
+        });
+        match remote {
+            Ok((mut entries, warning)) => {
+                warnings.extend(warning);
+                entries.extend(parsed_config);
+                parsed_config = entries;
+            }
+            Err(e) => warnings.push(format!("{e}, only local entries are loaded")),
+        }
+    }
+
+    parsed_config.extend(fragments);
 
     // get config as table of top level entries
-    let store = Store::build(parsed_config.into_iter()).map_err(Error::StoreError);
+    let store = Store::build(parsed_config.into_iter(), base_dir)
+        .map(|mut store| {
+            store.set_search_settings(settings.search.clone());
+            store
+        })
+        .map_err(Error::StoreError);
+
+    if let Ok(store) = &store {
+        warnings.extend(store.warnings());
+    }
+
+    Ok(Config {
+        settings,
+        store,
+        warnings,
+        path: None,
+    })
+}
+
+// how a config file changed between two versions of its text
+#[derive(Debug)]
+pub enum Change {
+    Unchanged,
+    // only the settings changed
+    Settings(Settings),
+    // the entries changed, so the store has to be rebuilt
+    Store,
+}
+
+pub fn classify_change(old: &str, new: &str) -> Result<Change, Error> {
+    let host = platform::hostname();
+    let (old_settings, old_entries) = parse_txt(old, host.as_deref())?;
+    let (new_settings, new_entries) = parse_txt(new, host.as_deref())?;
+
+    if old_settings == new_settings {
+        return Ok(if old_entries == new_entries {
+            Change::Unchanged
+        } else {
+            Change::Store
+        });
+    }
+
+    // invalid settings are an error, even if the entries changed too
+    let (settings, warnings) = parse_settings(new_settings)?;
+    if old_entries != new_entries {
+        return Ok(Change::Store);
+    }
+    for w in warnings {
+        ::log::warn!("{w}");
+    }
+    Ok(Change::Settings(settings))
+}
+
+// the settings in a config file, each time they change. The file
+// is polled on a thread of its own, which is only started once the
+// stream is first polled, and stops once the stream is dropped
+pub fn watch_settings(path: path::PathBuf) -> impl Stream<Item = Settings> {
+    stream::once(async move {
+        let (mut output, settings) = mpsc::channel(1);
+        std::thread::spawn(move || {
+            let mut watcher = Watcher::new(path);
+            while !output.is_closed() {
+                if let Some(settings) = watcher.poll() {
+                    if executor::block_on(output.send(settings)).is_err() {
+                        break;
+                    }
+                }
+                std::thread::sleep(WATCH_INTERVAL);
+            }
+        });
+        settings
+    })
+    .flatten()
+}
+
+// polls a config file for changes to its settings
+#[derive(Debug)]
+pub struct Watcher {
+    path: path::PathBuf,
+    modified: Option<SystemTime>,
+    // the last text that was loaded successfully
+    text: Option<String>,
+}
+
+impl Watcher {
+    // the file is not read until the first poll
+    pub fn new(path: path::PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+            text: None,
+        }
+    }
+
+    // check the config file once. Changes to the entries are only
+    // logged, since the store is not reloaded
+    fn poll(&mut self) -> Option<Settings> {
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if self.text.is_some() && modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        let text = fs::read_to_string(&self.path).ok()?;
+        // the first read is only used to compare against
+        let old = self.text.replace(text)?;
 
-    Ok(Config { settings, store })
+        match classify_change(&old, self.text.as_deref().unwrap_or_default()) {
+            Ok(Change::Settings(settings)) => Some(settings),
+            Ok(Change::Unchanged) => None,
+            Ok(Change::Store) => {
+                ::log::warn!(
+                    "Entries in {} changed, restart jolly to load them",
+                    self.path.display()
+                );
+                None
+            }
+            Err(e) => {
+                ::log::warn!("Could not reload settings: {e}");
+                self.text = Some(old);
+                None
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -179,10 +625,375 @@ mod tests {
         assert_eq!(config.settings, Settings::default());
     }
 
+    #[test]
+    fn config_entry_opens_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOGFILE_NAME);
+        fs::write(&path, "['foo']\nlocation = 'bar'").unwrap();
+
+        let store = load_path(&path).unwrap().store.unwrap();
+        assert_eq!(store.len(), 1);
+
+        let matches = store.find_matches(":config");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            store.get(matches[0]).format_selection(":config"),
+            path.canonicalize().unwrap().display().to_string()
+        );
+
+        // the config entry is hidden from normal searches
+        assert!(store.find_matches("config").is_empty());
+
+        // and can be disabled
+        fs::write(&path, "[config]\nconfig_trigger = ''").unwrap();
+        let store = load_path(&path).unwrap().store.unwrap();
+        assert!(store.find_matches(":config").is_empty());
+    }
+
+    #[test]
+    fn resolved_path_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOGFILE_NAME);
+        fs::write(&path, "['foo']\nlocation = 'bar'").unwrap();
+
+        let config = load_path(&path).unwrap();
+        assert_eq!(config.path, Some(path.canonicalize().unwrap()));
+
+        assert_eq!(load_txt("").unwrap().path, None);
+        assert_eq!(
+            Config::custom_load("nonexistentfile.toml".into()).path,
+            None
+        );
+    }
+
+    #[test]
+    fn warnings_are_collected() {
+        let toml = r#"[config.ui]
+                    max_results = 0
+
+                    ['foo']
+                    location = 'bar'
+                    icon = 'nonexistent_icon.png'
+
+                    ['baz']
+                    location = 'quux'"#;
+
+        let config = load_txt(toml).unwrap();
+        assert_eq!(config.warnings.len(), 2, "{:?}", config.warnings);
+        assert!(config.warnings[0].contains("max_results"));
+        assert!(config.warnings[1].contains("nonexistent_icon.png"));
+
+        // clamped settings are still usable
+        assert_eq!(config.settings.ui.max_results, 1);
+
+        assert!(load_txt("['foo']\nlocation = 'bar'")
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn all_settings_problems_reported() {
+        let toml = r#"[config]
+                    fallback_url = 'https://example.com/search'
+
+                    [config.ui]
+                    width = 0
+                    max_results = 0
+
+                    [config.ui.entry]
+                    description_size = 0
+
+                    [config.ui.icon]
+                    cache_size = 0
+
+                    [config.ui.theme]
+                    text_color = 'white'
+                    background_color = '#fff'"#;
+
+        let config = load_txt(toml).unwrap();
+        let expected = [
+            "width",
+            "max_results",
+            "description_size",
+            "text_color",
+            "cache_size",
+            "fallback_url",
+        ];
+        assert_eq!(
+            config.warnings.len(),
+            expected.len(),
+            "{:?}",
+            config.warnings
+        );
+        for (warning, setting) in config.warnings.iter().zip(expected) {
+            assert!(
+                warning.contains(setting),
+                "{warning} should be about {setting}"
+            );
+        }
+
+        assert!(Settings::default().validate().is_empty());
+    }
+
+    #[test]
+    fn settings_only_change() {
+        let old = "[config.ui]\nwidth = 42\n\n['foo']\nlocation = 'bar'";
+
+        // formatting and comments do not count as changes
+        let same = "# comment\n[config.ui]\nwidth   = 42\n['foo']\nlocation = 'bar'";
+        assert!(matches!(classify_change(old, same), Ok(Change::Unchanged)));
+
+        let new = "[config.ui]\nwidth = 43\n\n['foo']\nlocation = 'bar'";
+        match classify_change(old, new) {
+            Ok(Change::Settings(settings)) => assert_eq!(settings.ui.width, 43),
+            change => panic!("unexpected change {change:?}"),
+        }
+
+        let new = "[config.ui]\nwidth = 43\n\n['foo']\nlocation = 'baz'";
+        assert!(matches!(classify_change(old, new), Ok(Change::Store)));
+
+        assert!(classify_change(old, "[config.ui]\nwidth = 'a'").is_err());
+    }
+
+    #[test]
+    fn watcher_reports_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOGFILE_NAME);
+        fs::write(&path, "[config.ui]\nwidth = 42").unwrap();
+
+        let mut watcher = Watcher::new(path.clone());
+        assert_eq!(watcher.poll(), None);
+
+        fs::write(&path, "[config.ui]\nwidth = 43").unwrap();
+        // make sure the change is seen even on coarse filesystem timestamps
+        watcher.modified = None;
+        assert_eq!(watcher.poll().map(|s| s.ui.width), Some(43));
+        assert_eq!(watcher.poll(), None);
+
+        // invalid settings are skipped, and later compared against
+        // the last valid text
+        fs::write(&path, "[config.ui]\nwidth = 'a'").unwrap();
+        watcher.modified = None;
+        assert_eq!(watcher.poll(), None);
+        fs::write(&path, "[config.ui]\nwidth = 43").unwrap();
+        watcher.modified = None;
+        assert_eq!(watcher.poll(), None);
+    }
+
+    #[test]
+    fn host_sections() {
+        let toml = r#"[config.ui]
+                      width = 42
+                      max_results = 7
+
+                      ['foo']
+                      location = 'bar'
+
+                      [host.laptop.config.ui]
+                      width = 43
+
+                      [host.laptop.'baz']
+                      location = 'quux'
+
+                      [host.desktop.'foo']
+                      location = 'desktop'"#;
+
+        // a matching section adds entries and overrides single settings
+        let config = load_txt_for_host(toml, Some("Laptop")).unwrap();
+        assert_eq!(config.settings.ui.width, 43);
+        assert_eq!(config.settings.ui.max_results, 7);
+        let store = config.store.unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store
+                .get(store.find_matches("foo")[0])
+                .format_selection("foo"),
+            "bar"
+        );
+        assert_eq!(store.find_matches("baz").len(), 1);
+
+        // host sections can replace entries
+        let store = load_txt_for_host(toml, Some("desktop"))
+            .unwrap()
+            .store
+            .unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(
+            store
+                .get(store.find_matches("foo")[0])
+                .format_selection("foo"),
+            "desktop"
+        );
+
+        // other hosts are skipped
+        for host in [Some("server"), None] {
+            let config = load_txt_for_host(toml, host).unwrap();
+            assert_eq!(config.settings.ui.width, 42);
+            assert_eq!(config.store.unwrap().len(), 1);
+        }
+
+        assert!(load_txt_for_host("host = 1", None).is_err());
+        assert!(load_txt_for_host("[host]\nlaptop = 1", None).is_err());
+    }
+
+    #[test]
+    fn relative_icons_use_config_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOGFILE_NAME);
+        let absolute = dir.path().join("absolute.png");
+        let data = "data:image/png;base64,iVBORw0KGgo=";
+        fs::write(
+            &path,
+            format!(
+                "['relative']\nlocation = 'a'\nicon = 'icons/foo.png'\n\
+                 ['absolute']\nlocation = 'b'\nicon = '{}'\n\
+                 ['data']\nlocation = 'c'\nicon = '{data}'",
+                absolute.display()
+            ),
+        )
+        .unwrap();
+
+        let store = load_path(&path).unwrap().store.unwrap();
+        let icon = |name: &str| {
+            let id = store.get_by_name(name).unwrap();
+            store
+                .get(id)
+                .icontype()
+                .custom_path()
+                .unwrap()
+                .to_path_buf()
+        };
+
+        let config_dir = dir.path().canonicalize().unwrap();
+        assert_eq!(icon("relative"), config_dir.join("icons").join("foo.png"));
+        assert_eq!(icon("absolute"), absolute);
+        assert_eq!(icon("data"), path::PathBuf::from(data));
+
+        // without a config file, paths are left alone
+        let store = load_txt("['relative']\nlocation = 'a'\nicon = 'icons/foo.png'")
+            .unwrap()
+            .store
+            .unwrap();
+        let id = store.get_by_name("relative").unwrap();
+        assert_eq!(
+            store.get(id).icontype().custom_path(),
+            Some(path::Path::new("icons/foo.png"))
+        );
+    }
+
+    #[test]
+    fn fragment_entries_are_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOGFILE_NAME);
+        fs::write(&path, "['foo']\nlocation = 'main'").unwrap();
+
+        // no fragment directory is fine
+        assert_eq!(load_path(&path).unwrap().store.unwrap().len(), 1);
+
+        let fragments = dir.path().join(FRAGMENT_DIR);
+        fs::create_dir(&fragments).unwrap();
+        fs::write(
+            fragments.join("a.toml"),
+            "['bar']\nlocation = 'a'\n['foo']\nlocation = 'a'",
+        )
+        .unwrap();
+        fs::write(
+            fragments.join("b.toml"),
+            "['bar']\nlocation = 'b'\n['baz']\nlocation = 'b'",
+        )
+        .unwrap();
+        fs::write(fragments.join("notes.txt"), "not toml").unwrap();
+
+        let config = load_path(&path).unwrap();
+        assert!(config.warnings.is_empty(), "{:?}", config.warnings);
+        let store = config.store.unwrap();
+        assert_eq!(store.len(), 3);
+
+        let location = |name: &str| {
+            let matches = store.find_matches(name);
+            store.get(matches[0]).format_selection(name)
+        };
+        assert_eq!(location("foo"), "a");
+        assert_eq!(location("bar"), "b");
+        assert_eq!(location("baz"), "b");
+
+        // fragments cannot change settings
+        fs::write(fragments.join("c.toml"), "[config.ui]\nwidth = 42").unwrap();
+        let config = load_path(&path).unwrap();
+        assert_eq!(config.settings.ui.width, Settings::default().ui.width);
+        assert_eq!(config.warnings.len(), 1);
+
+        fs::write(fragments.join("d.toml"), "['broken'").unwrap();
+        assert!(matches!(
+            load_path(&path),
+            Err(Error::ContextParseError(p, _)) if p.ends_with("d.toml")
+        ));
+    }
+
+    #[test]
+    fn failed_store_exit_code() {
+        assert_eq!(
+            load_txt("['foo']\nlocation = 'bar'").unwrap().exit_code(),
+            ExitCode::SUCCESS
+        );
+        assert_eq!(load_txt("").unwrap().exit_code(), ExitCode::SUCCESS);
+
+        // the settings still load, but the store does not
+        assert_eq!(load_txt("a = 1").unwrap().exit_code(), ExitCode::FAILURE);
+        assert_eq!(
+            Config::custom_load("nonexistentfile.toml".into()).exit_code(),
+            ExitCode::FAILURE
+        );
+    }
+
     #[test]
     fn nonexistent_path() {
         let result = load_path("nonexistentfile.toml");
         assert!(matches!(result, Err(Error::IoError(_, _))));
+        let result = load_settings(Some("nonexistentfile.toml"));
+        assert!(matches!(result, Err(Error::IoError(_, _))));
+    }
+
+    #[test]
+    fn settings_load_without_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOGFILE_NAME);
+        let text = "[config.ui]\nwidth = 500\n\n['foo']\nlocation = 'a'\nsystem = 'b'";
+        fs::write(&path, text).unwrap();
+
+        // the broken entry stops the store from loading, but not the
+        // settings
+        assert!(load_path(&path).unwrap().store.is_err());
+        let settings = load_settings(path.to_str()).unwrap();
+        assert_eq!(settings.ui.width, 500);
+    }
+
+    #[test]
+    fn boundary_bonus_is_capped() {
+        let config = load_txt("[config.search]\nboundary_bonus = 100").unwrap();
+        assert_eq!(
+            config.settings.search.boundary_bonus,
+            crate::entry::MAX_BOUNDARY_BONUS
+        );
+        assert_eq!(config.warnings.len(), 1);
+        assert!(config.warnings[0].contains("boundary_bonus"));
+
+        let config = load_txt("[config.search]\nboundary_bonus = 0").unwrap();
+        assert_eq!(config.settings.search.boundary_bonus, 0);
+        assert!(config.warnings.is_empty());
+    }
+
+    #[test]
+    fn fuzzy_is_a_ui_setting() {
+        assert!(load_txt("").unwrap().settings.search.fuzzy);
+
+        let settings = load_txt("[config.ui]\nfuzzy = false").unwrap().settings;
+        assert!(!settings.search.fuzzy);
+
+        // the old place of the setting is not used
+        let settings = load_txt("[config.search]\nfuzzy = false").unwrap().settings;
+        assert!(settings.search.fuzzy);
     }
 
     #[test]
@@ -227,4 +1038,81 @@ mod tests {
             MapDeserializer::<_, serde::de::value::Error>::new(std::iter::once(("a", "b")));
         one_or_many::<Vec<()>, _>(map_de).unwrap_err();
     }
+
+    #[test]
+    fn remote_locations() {
+        assert!(is_remote("https://example.com/jolly.toml"));
+        assert!(is_remote("http://example.com/jolly.toml"));
+        assert!(!is_remote("jolly.toml"));
+        assert!(!is_remote("/home/user/jolly.toml"));
+        assert!(!is_remote(r"C:\Users\user\jolly.toml"));
+        assert!(!is_remote("file:///home/user/jolly.toml"));
+
+        assert_eq!(
+            remote_cache_name("https://example.com/a.toml"),
+            "https___example_com_a_toml.toml"
+        );
+    }
+
+    #[test]
+    fn remote_cache_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("remote").join("jolly.toml");
+        let url = "https://example.com/jolly.toml";
+        let offline = |_: &str| Err("network is down".to_string());
+
+        // without a cached copy, a failed fetch is an error
+        assert!(fetch_cached(url, Some(&cache), offline).is_err());
+        assert!(fetch_cached(url, None, offline).is_err());
+
+        // a successful fetch is cached
+        let (txt, warning) =
+            fetch_cached(url, Some(&cache), |_| Ok("[foo]\nlocation = 'a'".into())).unwrap();
+        assert_eq!(txt, "[foo]\nlocation = 'a'");
+        assert_eq!(warning, None);
+        assert_eq!(fs::read_to_string(&cache).unwrap(), txt);
+
+        // and used once the fetch fails
+        let (cached, warning) = fetch_cached(url, Some(&cache), offline).unwrap();
+        assert_eq!(cached, txt);
+        assert!(warning.unwrap().contains("network is down"));
+
+        // newer fetches replace the cached copy
+        fetch_cached(url, Some(&cache), |_| Ok("[bar]".into())).unwrap();
+        assert_eq!(fs::read_to_string(&cache).unwrap(), "[bar]");
+    }
+
+    #[test]
+    fn remote_store_problems() {
+        // plain http is refused before anything is fetched
+        let err = fetch_remote("http://example.com/jolly.toml").unwrap_err();
+        assert!(err.to_string().contains("must use https"), "{err}");
+        assert!(load_remote("http://example.com/jolly.toml").is_err());
+
+        // a remote store that cannot be loaded leaves the local entries
+        let config = load_txt(
+            "[config]\nremote_store = 'http://example.com/jolly.toml'\n['foo']\nlocation = 'a'",
+        )
+        .unwrap();
+        assert_eq!(config.store.unwrap().len(), 1);
+        assert!(
+            config
+                .warnings
+                .iter()
+                .any(|w| w.contains("only local entries")),
+            "{:?}",
+            config.warnings
+        );
+
+        // a fetched config file cannot add another remote store
+        let txt =
+            "[config]\nremote_store = 'https://example.com/other.toml'\n['foo']\nlocation = 'a'";
+        let config = load_txt_with_fragments(txt, None, toml::Table::new(), None, true).unwrap();
+        assert_eq!(config.settings.remote_store, None);
+        assert_eq!(config.store.unwrap().len(), 1);
+        assert_eq!(
+            config.warnings,
+            ["remote_store is ignored in remote config files"]
+        );
+    }
 }
@@ -7,7 +7,7 @@ use iced::advanced::widget::{tree, Operation, Tree};
 use iced::advanced::{self, overlay};
 use iced::advanced::{layout, renderer, Clipboard, Layout, Shell, Widget};
 use iced::mouse;
-use iced::{Element, Event, Length, Rectangle, Size};
+use iced::{Element, Event, Length, Padding, Point, Rectangle, Size};
 
 pub struct MeasuredContainer<'a, Message, Renderer, F>
 where
@@ -15,6 +15,7 @@ where
 {
     content: Element<'a, Message, Renderer>,
     msg_builder: F,
+    padding: Padding,
 }
 
 impl<'a, Message, Renderer, F> MeasuredContainer<'a, Message, Renderer, F>
@@ -26,8 +27,24 @@ where
         MeasuredContainer {
             content: content.into(),
             msg_builder: callback,
+            padding: Padding::ZERO,
         }
     }
+
+    /// Sets the [`Padding`] around the content, which is included in
+    /// the measured size.
+    pub fn padding<P: Into<Padding>>(mut self, padding: P) -> Self {
+        self.padding = padding.into();
+        self
+    }
+}
+
+// the layout of the content inside the padding
+fn content_layout(layout: Layout<'_>) -> Layout<'_> {
+    layout
+        .children()
+        .next()
+        .expect("measured container has one child")
 }
 
 #[derive(Default)]
@@ -65,7 +82,13 @@ where
     }
 
     fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
-        self.content.as_widget().layout(renderer, limits)
+        let mut content = self
+            .content
+            .as_widget()
+            .layout(renderer, &limits.pad(self.padding));
+        content.move_to(Point::new(self.padding.left, self.padding.top));
+        let size = content.size().pad(self.padding);
+        layout::Node::with_children(size, vec![content])
     }
 
     fn operate(
@@ -75,9 +98,12 @@ where
         renderer: &Renderer,
         operation: &mut dyn Operation<Message>,
     ) {
-        self.content
-            .as_widget()
-            .operate(&mut tree.children[0], layout, renderer, operation);
+        self.content.as_widget().operate(
+            &mut tree.children[0],
+            content_layout(layout),
+            renderer,
+            operation,
+        );
     }
 
     // when we receive events, we check to see if the minimum layout
@@ -114,7 +140,7 @@ where
         if let event::Status::Captured = self.content.as_widget_mut().on_event(
             &mut tree.children[0],
             event.clone(),
-            layout,
+            content_layout(layout),
             cursor_position,
             renderer,
             clipboard,
@@ -136,7 +162,7 @@ where
     ) -> mouse::Interaction {
         self.content.as_widget().mouse_interaction(
             &tree.children[0],
-            layout,
+            content_layout(layout),
             cursor_position,
             viewport,
             renderer,
@@ -158,7 +184,7 @@ where
             renderer,
             theme,
             renderer_style,
-            layout,
+            content_layout(layout),
             cursor_position,
             viewport,
         );
@@ -170,9 +196,11 @@ where
         layout: Layout<'_>,
         renderer: &Renderer,
     ) -> Option<overlay::Element<'b, Message, Renderer>> {
-        self.content
-            .as_widget_mut()
-            .overlay(&mut tree.children[0], layout, renderer)
+        self.content.as_widget_mut().overlay(
+            &mut tree.children[0],
+            content_layout(layout),
+            renderer,
+        )
     }
 }
 
@@ -187,3 +215,36 @@ where
         Element::new(area)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::advanced::renderer::Null;
+    use iced::widget::Space;
+
+    fn layout_of(container: MeasuredContainer<'_, (), Null, fn(f32, f32)>) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, Size::new(300.0, f32::INFINITY));
+        container.layout(&Null::new(), &limits)
+    }
+
+    #[test]
+    fn padding_is_applied() {
+        let content = || Space::new(Length::Fill, 20.0);
+        let callback: fn(f32, f32) = |_, _| ();
+
+        let node = layout_of(MeasuredContainer::new(content(), callback));
+        assert_eq!(node.size(), Size::new(300.0, 20.0));
+        let child = content_layout(Layout::new(&node)).bounds();
+        assert_eq!(child, Rectangle::new(Point::ORIGIN, Size::new(300.0, 20.0)));
+
+        // the content shrinks to make room for the padding, and the
+        // measured size includes it
+        let node = layout_of(MeasuredContainer::new(content(), callback).padding(10));
+        assert_eq!(node.size(), Size::new(300.0, 40.0));
+        let child = content_layout(Layout::new(&node)).bounds();
+        assert_eq!(
+            child,
+            Rectangle::new(Point::new(10.0, 10.0), Size::new(280.0, 20.0))
+        );
+    }
+}
@@ -5,3 +5,6 @@ pub use measured_container::MeasuredContainer;
 
 mod mouse_area;
 pub use mouse_area::MouseArea;
+
+mod slide_in;
+pub use slide_in::SlideIn;
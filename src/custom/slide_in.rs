@@ -0,0 +1,196 @@
+//! A container that slides and fades its content into place.
+//!
+//! Only drawing is affected: the content is laid out where it ends
+//! up, so the measured size of the window does not change while
+//! the content is moving.
+
+use iced::advanced::renderer::{self, Quad};
+use iced::advanced::widget::{Operation, Tree};
+use iced::advanced::{layout, mouse, overlay};
+use iced::advanced::{Clipboard, Layout, Shell, Widget};
+use iced::event::{self, Event};
+use iced::{Color, Element, Length, Rectangle, Vector};
+
+// how far to the right the content starts, in virtual units
+const SLIDE_DISTANCE: f32 = 24.0;
+
+/// Draws its content partly faded in and slid into place.
+#[allow(missing_debug_implementations)]
+pub struct SlideIn<'a, Message, Renderer> {
+    content: Element<'a, Message, Renderer>,
+    // 0.0 has the content hidden, 1.0 has it in place
+    progress: f32,
+    // color that the content fades in from
+    background: Color,
+}
+
+impl<'a, Message, Renderer> SlideIn<'a, Message, Renderer> {
+    /// Creates a [`SlideIn`] that has moved `progress` of the way in.
+    pub fn new(
+        content: impl Into<Element<'a, Message, Renderer>>,
+        progress: f32,
+        background: Color,
+    ) -> Self {
+        SlideIn {
+            content: content.into(),
+            progress: progress.clamp(0.0, 1.0),
+            background,
+        }
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for SlideIn<'a, Message, Renderer>
+where
+    Renderer: renderer::Renderer,
+{
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let content = self.content.as_widget();
+        if self.progress >= 1.0 {
+            content.draw(
+                &tree.children[0],
+                renderer,
+                theme,
+                renderer_style,
+                layout,
+                cursor,
+                viewport,
+            );
+            return;
+        }
+
+        // the layer keeps the sliding content from being drawn
+        // outside of its final bounds
+        let bounds = layout.bounds();
+        let offset = Vector::new((1.0 - self.progress) * SLIDE_DISTANCE, 0.0);
+        renderer.with_layer(bounds, |renderer| {
+            renderer.with_translation(offset, |renderer| {
+                content.draw(
+                    &tree.children[0],
+                    renderer,
+                    theme,
+                    renderer_style,
+                    layout,
+                    cursor,
+                    viewport,
+                );
+            });
+
+            // iced cannot draw with opacity, so the content is
+            // covered by the background as it fades in
+            renderer.fill_quad(
+                Quad {
+                    bounds,
+                    border_radius: 0.0.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                Color {
+                    a: 1.0 - self.progress,
+                    ..self.background
+                },
+            );
+        });
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message, Renderer> From<SlideIn<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: 'a + renderer::Renderer,
+{
+    fn from(slide: SlideIn<'a, Message, Renderer>) -> Element<'a, Message, Renderer> {
+        Element::new(slide)
+    }
+}
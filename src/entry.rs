@@ -1,26 +1,84 @@
 // contains logic for displaying entries
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::error;
 use std::fmt;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 
 use iced::advanced;
+use iced::keyboard;
 use serde::Deserialize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 use url::Url;
 
+use crate::config::one_or_many;
 use crate::icon::Icon;
+use crate::score_expr;
+use crate::search;
+use crate::settings;
 use crate::theme;
 use crate::ui;
 use crate::{icon, platform};
 
 // these are the weights for the different kind of matches.
 // we prefer each weight to be different so we can differentiate them in the test plan
-const FULL_KEYWORD_W: u32 = 100;
-const PARTIAL_NAME_W: u32 = 3;
-const FULL_NAME_W: u32 = 10;
-const PARTIAL_TAG_W: u32 = 2;
-const STARTSWITH_TAG_W: u32 = 4;
-const FULL_TAG_W: u32 = 6;
+pub const FULL_KEYWORD_W: u32 = 100;
+pub const PARTIAL_NAME_W: u32 = 3;
+pub const STARTSWITH_NAME_W: u32 = 5;
+pub const FULL_NAME_W: u32 = 10;
+pub const PARTIAL_TAG_W: u32 = 2;
+pub const STARTSWITH_TAG_W: u32 = 4;
+pub const FULL_TAG_W: u32 = 6;
+pub const PARTIAL_CATEGORY_W: u32 = 3;
+pub const STARTSWITH_CATEGORY_W: u32 = 5;
+pub const FULL_CATEGORY_W: u32 = 8;
+// tokens that are not part of the name can still match its
+// characters in order, like "frfx" for "firefox". These score
+// FUZZY_NAME_W, plus the configurable bonus for matching the starts
+// of words, like "gco" for "git checkout". The bonus is capped, so
+// that fuzzy matches always score less than PARTIAL_NAME_W
+const FUZZY_NAME_W: u32 = 1;
+pub const MAX_BOUNDARY_BONUS: u32 = 1;
+const _: () = assert!(FUZZY_NAME_W + MAX_BOUNDARY_BONUS < PARTIAL_NAME_W);
+// fuzzy matches with the same score are graded, to break ties
+// between them: up to FUZZY_START_W the closer the match starts to
+// the start of the name, plus up to FUZZY_RUN_W the more of its
+// characters are next to each other
+const FUZZY_START_W: u32 = 5;
+const FUZZY_RUN_W: u32 = 5;
+
+// weights of each kind of token match, in the same order as the match
+// signals of score expressions
+const MATCH_WEIGHTS: [u32; score_expr::MATCH_SIGNALS] = [
+    FULL_NAME_W,
+    PARTIAL_NAME_W,
+    STARTSWITH_NAME_W,
+    FULL_TAG_W,
+    PARTIAL_TAG_W,
+    STARTSWITH_TAG_W,
+    FULL_CATEGORY_W,
+    PARTIAL_CATEGORY_W,
+    STARTSWITH_CATEGORY_W,
+];
+
+// how much the location subtitle is dimmed compared to the entry text
+const LOCATION_ALPHA: f32 = 0.6;
+// approximate width of one column of text, relative to the text
+// size. Wide characters take up two columns
+pub const COLUMN_WIDTH: f32 = 0.5;
+// padding around the contents of an entry's button. The widgets of
+// an entry are sized with these, so that row_height can tell how
+// tall an entry is before it is laid out
+const BUTTON_PADDING: f32 = 5.0;
+// height of a line of the subtitle and description, relative to the
+// text size
+const LINE_HEIGHT: f32 = 1.3;
+// extra height of the row with the icon and name, so that the icon
+// is not clipped when it is as tall as the text
+const TITLE_PADDING: u16 = 4;
 
 pub type EntryId = usize;
 
@@ -28,6 +86,7 @@ pub type EntryId = usize;
 pub enum Error {
     ParseError(String),
     PlatformError(platform::Error),
+    DisallowedScheme(String),
 }
 
 impl fmt::Display for Error {
@@ -35,6 +94,9 @@ impl fmt::Display for Error {
         match self {
             Error::PlatformError(e) => e.fmt(f),
             Error::ParseError(s) => f.write_str(s),
+            Error::DisallowedScheme(s) => {
+                write!(f, "Refusing to open url: the scheme '{s}:' is not allowed")
+            }
         }
     }
 }
@@ -48,12 +110,55 @@ pub struct EntrySettings {
     #[serde(flatten)]
     common: ui::InheritedSettings,
     description_size: u16,
+    markdown: MarkdownMode,
+    // show where each entry points below its name
+    pub show_location: bool,
+    // draw a thin line between results
+    pub separators: bool,
+    // show the keyword of keyword entries next to their name
+    pub show_keywords: bool,
+}
+
+// determines how descriptions are shown when they use markdown that
+// jolly cannot render
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownMode {
+    // only show descriptions that could be fully parsed
+    Strict,
+    // show the description text as-is
+    #[default]
+    Raw,
+    // strip out markdown formatting and show the remaining text
+    Plain,
 }
 
 impl EntrySettings {
     pub fn propagate(&mut self, parent: &ui::InheritedSettings) {
         self.common.propagate(parent);
     }
+
+    // problems with the entry settings, once they are propagated
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.common.text_size() == 0 {
+            problems.push("config.ui.entry.text_size must be greater than 0".to_string());
+        }
+        if self.description_size == 0 {
+            problems.push("config.ui.entry.description_size must be greater than 0".to_string());
+        }
+        problems
+    }
+}
+
+impl EntrySettings {
+    pub fn text_size(&self) -> u16 {
+        self.common.text_size()
+    }
+
+    pub fn description_size(&self) -> u16 {
+        self.description_size
+    }
 }
 
 impl Default for EntrySettings {
@@ -62,22 +167,50 @@ impl Default for EntrySettings {
         let description_size = (inherited.text_size() as f32 * 0.8).round() as u16;
         Self {
             common: inherited,
-            description_size: description_size,
+            description_size,
+            markdown: MarkdownMode::default(),
+            show_location: false,
+            separators: false,
+            show_keywords: false,
         }
     }
 }
 
 #[derive(serde::Deserialize, Debug)]
 struct RawStoreEntry {
-    location: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    location: Vec<String>,
+    first_existing: Option<bool>,
     url: Option<String>,
     system: Option<String>,
     keyword: Option<String>,
+    default_arg: Option<String>,
     escape: Option<bool>,
     #[serde(alias = "desc")]
     description: Option<String>,
     tags: Option<Vec<String>>,
-    icon: Option<String>,
+    categories: Option<Vec<String>>,
+    icon: Option<RawIcon>,
+    icon_name: Option<String>,
+    env: Option<BTreeMap<String, String>>,
+    #[serde(rename = "await")]
+    await_exit: Option<bool>,
+    collapse_whitespace: Option<bool>,
+    priority: Option<u32>,
+    copy: Option<String>,
+    dir: Option<String>,
+    no_copy: Option<bool>,
+    runs: Option<Vec<String>>,
+    color: Option<ui::Color>,
+}
+
+// the icon field of an entry is either the path to an icon, or
+// false (or "none") to show the entry without an icon
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum RawIcon {
+    Enabled(bool),
+    Path(String),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -90,18 +223,119 @@ enum Keyword {
 #[derive(Debug, Clone, Hash)]
 pub struct StoreEntry {
     name: String,
+    // latin transliteration of the name, if it is not already ascii
+    transliterated: Option<String>,
+    // last component of the name, if the name is a path
+    basename: Option<String>,
     description: Option<String>,
     entry: EntryType,
     tags: Vec<String>,
+    categories: Vec<String>,
     keyword: Keyword,
+    // parameter used when a keyword is typed without one
+    default_arg: Option<String>,
     icon_type: icon::IconType,
     icon: Option<Icon>,
+    // if false, the entry is shown without an icon
+    show_icon: bool,
+    env: Vec<(String, String)>,
+    // wait for the system command to exit and report how it went
+    await_exit: bool,
+    priority: u32,
+    // names of the entries that are run when this entry is selected
+    runs: Vec<String>,
+    // the entry is always opened, even when copying is requested
+    no_copy: bool,
+    // more locations that are opened along with the first one
+    extra_locations: Vec<String>,
+    // only open the first of the locations that exists
+    first_existing: bool,
+    // highlight color when the entry is selected, as rgba
+    color: Option<[u8; 4]>,
+    // host and path segments of the entry's url, if it has one
+    url_terms: Vec<String>,
+    // how often and how recently the entry was opened, between 0 and
+    // history::MAX_WEIGHT
+    frecency: u32,
+    // when the entry's file was last modified, looked up when the
+    // store is loaded so that sorting does not touch the disk
+    mtime: Option<std::time::SystemTime>,
+}
+
+// how one token of the query matched an entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenScore {
+    pub token: String,
+    // the kinds of match the token had, with their weights
+    pub matches: Vec<(&'static str, u32)>,
+    // the best weight of the matches, or 0 if nothing matched
+    pub score: u32,
+}
+
+// the parts that make up the score of an entry for a query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    pub tokens: Vec<TokenScore>,
+    // the first token is the keyword of the entry
+    pub keyword: bool,
+    pub priority: u32,
+    pub score: u32,
+}
+
+// what happens to an entry when it is selected. Used both to
+// dispatch the selection and to label the selected entry in the UI
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    #[default]
+    Open,
+    Copy,
+    // open the entry and copy it to the clipboard
+    OpenAndCopy,
+    // show the entry's file in the file manager
+    Reveal,
+}
+
+impl Action {
+    // if the user is pressing the command key, we copy to the
+    // clipboard instead of opening the entry. If they also press the
+    // open_copy modifier, we do both
+    pub fn from_modifiers(
+        modifiers: keyboard::Modifiers,
+        open_copy: settings::OpenCopyModifier,
+    ) -> Self {
+        let open_copy = match open_copy {
+            settings::OpenCopyModifier::None => false,
+            settings::OpenCopyModifier::Shift => modifiers.shift(),
+            settings::OpenCopyModifier::Alt => modifiers.alt(),
+        };
+
+        match (modifiers.command(), open_copy) {
+            (true, true) => Action::OpenAndCopy,
+            (true, false) => Action::Copy,
+            _ => Action::Open,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Open => "Open",
+            Action::Copy => "Copy",
+            Action::OpenAndCopy => "Open & Copy",
+            Action::Reveal => "Reveal",
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum EntryType {
     FileEntry(String),
     SystemEntry(String),
+    // text that is only ever copied to the clipboard
+    CopyEntry(String),
+    // runs other entries. Holds the names of those entries, for display
+    RunsEntry(String),
+    // directory that is always opened in the file manager
+    DirEntry(String),
 }
 
 impl fmt::Display for EntryType {
@@ -109,13 +343,70 @@ impl fmt::Display for EntryType {
         match self {
             EntryType::FileEntry(_) => f.write_str("FileEntry"),
             EntryType::SystemEntry(_) => f.write_str("SystemEntry"),
+            EntryType::CopyEntry(_) => f.write_str("CopyEntry"),
+            EntryType::RunsEntry(_) => f.write_str("RunsEntry"),
+            EntryType::DirEntry(_) => f.write_str("DirEntry"),
         }
     }
 }
 
+// join the lines of a command into one line. Each line is trimmed,
+// and a backslash at the end of a line is dropped, since it only
+// continues the line in a shell. Commands that are already a single
+// line are left alone
+fn collapse_lines(cmd: &str) -> String {
+    if !cmd.contains('\n') {
+        return cmd.to_string();
+    }
+
+    cmd.lines()
+        .map(|l| {
+            let l = l.trim();
+            l.strip_suffix('\\').unwrap_or(l).trim_end()
+        })
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// the host and path segments of a url location, so that url entries
+// can be found by where they point. Locations that are not urls have
+// no terms. A leading www. is dropped from the host
+fn url_terms(loc: &str) -> Vec<String> {
+    let url = match Url::parse(&format_param(loc, "")) {
+        Ok(url) if !icon::is_drive_path(&url) => url,
+        _ => return Vec::new(),
+    };
+
+    let host = url.host_str().map(|h| h.strip_prefix("www.").unwrap_or(h));
+    host.into_iter()
+        .chain(url.path_segments().into_iter().flatten())
+        .filter(|t| !t.is_empty())
+        .map(|t| match urlencoding::decode(t) {
+            Ok(decoded) => decoded.into_owned(),
+            Err(_) => t.to_string(),
+        })
+        .collect()
+}
+
+// path of a custom icon. Relative paths are relative to base_dir,
+// while absolute paths and urls such as data uris are kept as is
+fn icon_path(path: String, base_dir: Option<&Path>) -> PathBuf {
+    let is_url = Url::parse(&path).map_or(false, |u| !icon::is_drive_path(&u));
+    match base_dir {
+        Some(dir) if Path::new(&path).is_relative() && !is_url => dir.join(path),
+        _ => path.into(),
+    }
+}
+
 impl StoreEntry {
-    // parse a toml value into a store entry
-    pub fn from_value(name: String, val: toml::Value) -> Result<Self, Error> {
+    // parse a toml value into a store entry. Relative icon paths are
+    // resolved against base_dir, the directory of the config file
+    pub fn from_value(
+        name: String,
+        val: toml::Value,
+        base_dir: Option<&Path>,
+    ) -> Result<Self, Error> {
         if !val.is_table() {
             return Err(Error::ParseError(format!(
                 "Invalid entry '{name}': Jolly entries can only be TOML tables"
@@ -125,6 +416,13 @@ impl StoreEntry {
         let raw_entry = RawStoreEntry::deserialize(val)
             .map_err(|e| Error::ParseError(format!("TOML Error: {}", e.message())))?;
 
+        if raw_entry.default_arg.is_some() && raw_entry.keyword.is_none() {
+            return Err(Error::ParseError(format!(
+                "Error with entry ['{}']: The default_arg key is only valid for keyword entries",
+                &name
+            )));
+        }
+
         let keyword = if let Some(keyword) = raw_entry.keyword {
             if raw_entry.url.is_some() || raw_entry.escape.unwrap_or(false) {
                 Keyword::EscapedKeyword(keyword)
@@ -135,25 +433,96 @@ impl StoreEntry {
             Keyword::None
         };
 
-        let is_system = raw_entry.system.is_some();
+        let runs = raw_entry.runs.unwrap_or_default();
+
+        let mut locations = raw_entry.location.into_iter();
+        let location = locations.next();
+        let extra_locations: Vec<_> = locations.collect();
 
-        let location = match (raw_entry.location, raw_entry.url, raw_entry.system) {
-            (Some(loc), None, None) => loc,
-            (None, Some(loc), None) => loc,
-            (None, None, Some(loc)) => loc,
-            (None, None, None) => name.to_string(),
+        let first_existing = raw_entry.first_existing.unwrap_or(false);
+        if raw_entry.first_existing.is_some() && extra_locations.is_empty() {
+            return Err(Error::ParseError(format!(
+                "Error with entry ['{}']: The first_existing key is only valid for entries with several locations",
+                &name
+            )));
+        }
+
+        let entry = match (
+            location,
+            raw_entry.url,
+            raw_entry.system,
+            raw_entry.copy,
+            raw_entry.dir,
+            runs.is_empty(),
+        ) {
+            (Some(loc), None, None, None, None, true) => EntryType::FileEntry(loc),
+            (None, Some(loc), None, None, None, true) => EntryType::FileEntry(loc),
+            (None, None, Some(loc), None, None, true) => EntryType::SystemEntry(loc),
+            (None, None, None, Some(text), None, true) => EntryType::CopyEntry(text),
+            (None, None, None, None, Some(dir), true) => EntryType::DirEntry(dir),
+            (None, None, None, None, None, false) => EntryType::RunsEntry(runs.join(", ")),
+            (None, None, None, None, None, true) => EntryType::FileEntry(name.to_string()),
             _ => {
                 return Err(Error::ParseError(format!(
-                    "Error with entry ['{}']: The entry should only specify one of location/url/system keys",
+                    "Error with entry ['{}']: The entry should only specify one of location/url/system/copy/dir/runs keys",
                     &name
                 )))
             }
         };
 
-        let entry = if is_system {
-            EntryType::SystemEntry(location)
-        } else {
-            EntryType::FileEntry(location)
+        if !runs.is_empty() && !matches!(keyword, Keyword::None) {
+            return Err(Error::ParseError(format!(
+                "Error with entry ['{}']: The keyword key is not valid for entries with runs",
+                &name
+            )));
+        }
+
+        let is_system = matches!(entry, EntryType::SystemEntry(_));
+
+        let color = raw_entry.color.map(|c| c.0.to_rgba8());
+        let no_copy = raw_entry.no_copy.unwrap_or(false);
+        if no_copy && matches!(entry, EntryType::CopyEntry(_)) {
+            return Err(Error::ParseError(format!(
+                "Error with entry ['{}']: The no_copy key is not valid for copy entries",
+                &name
+            )));
+        }
+
+        // extra environment variables only make sense for system commands
+        let env = match raw_entry.env {
+            Some(env) if is_system => env.into_iter().collect(),
+            Some(_) => {
+                return Err(Error::ParseError(format!(
+                    "Error with entry ['{}']: The env key is only valid for system entries",
+                    &name
+                )))
+            }
+            None => Vec::new(),
+        };
+
+        // only system commands have an exit status to wait for
+        let await_exit = match raw_entry.await_exit {
+            Some(await_exit) if is_system => await_exit,
+            Some(_) => {
+                return Err(Error::ParseError(format!(
+                    "Error with entry ['{}']: The await key is only valid for system entries",
+                    &name
+                )))
+            }
+            None => false,
+        };
+
+        // long commands can be written over several lines of a toml
+        // multi-line string, but are run as a single line
+        let entry = match (entry, raw_entry.collapse_whitespace) {
+            (EntryType::SystemEntry(cmd), Some(true)) => EntryType::SystemEntry(collapse_lines(&cmd)),
+            (_, Some(_)) if !is_system => {
+                return Err(Error::ParseError(format!(
+                    "Error with entry ['{}']: The collapse_whitespace key is only valid for system entries",
+                    &name
+                )))
+            }
+            (entry, _) => entry,
         };
 
         let tags = match raw_entry.tags {
@@ -161,11 +530,36 @@ impl StoreEntry {
             None => Vec::new(),
         };
 
-        let icon_type = if let Some(p) = raw_entry.icon {
-            icon::IconType::custom(p)
-        } else {
-            match &entry {
+        let categories = raw_entry.categories.unwrap_or_default();
+
+        let (icon, show_icon) = match raw_entry.icon {
+            Some(RawIcon::Enabled(false)) => (None, false),
+            Some(RawIcon::Path(p)) if p == "none" => (None, false),
+            Some(RawIcon::Enabled(true)) => {
+                return Err(Error::ParseError(format!(
+                    "Error with entry ['{}']: The icon key should be a path to an icon, or false",
+                    &name
+                )))
+            }
+            Some(RawIcon::Path(p)) => (Some(p), true),
+            None => (None, true),
+        };
+
+        let icon_type = match (icon, raw_entry.icon_name) {
+            (Some(_), Some(_)) => {
+                return Err(Error::ParseError(format!(
+                "Error with entry ['{}']: The entry should only specify one of icon/icon_name keys",
+                &name
+            )))
+            }
+            (Some(p), None) => icon::IconType::custom(icon_path(p, base_dir)),
+            (None, Some(n)) => icon::IconType::named(n),
+            (None, None) => match &entry {
                 EntryType::SystemEntry(loc) => icon::IconType::system(loc),
+                EntryType::CopyEntry(_) => icon::IconType::named("edit-copy"),
+                EntryType::RunsEntry(_) => icon::IconType::named("system-run"),
+                // the platform's icon for a directory is the folder icon
+                EntryType::DirEntry(dir) => icon::IconType::file(format_param(dir, "")),
                 EntryType::FileEntry(loc) => {
                     let parsed_loc = format_param(loc, "");
 
@@ -175,26 +569,79 @@ impl StoreEntry {
                         icon::IconType::file(parsed_loc)
                     }
                 }
-            }
+            },
+        };
+
+        let url_terms = match &entry {
+            EntryType::FileEntry(loc) => url_terms(loc),
+            _ => Vec::new(),
         };
 
         Ok(StoreEntry {
+            transliterated: transliterate(&name),
+            basename: basename(&name),
             name: name.to_string(),
             description: raw_entry.description,
             entry: entry,
             tags: tags,
+            categories,
             keyword: keyword,
+            default_arg: raw_entry.default_arg,
             icon_type,
             icon: None,
+            show_icon,
+            env,
+            await_exit,
+            priority: raw_entry.priority.unwrap_or(0),
+            runs,
+            no_copy,
+            extra_locations,
+            first_existing,
+            color,
+            url_terms,
+            frecency: 0,
+            mtime: None,
         })
     }
 
+    // build a synthetic entry that opens the jolly config file
+    pub fn config_entry(path: &std::path::Path) -> Self {
+        StoreEntry {
+            name: "Edit Jolly Configuration".to_string(),
+            transliterated: None,
+            basename: None,
+            description: Some(path.display().to_string()),
+            entry: EntryType::FileEntry(path.display().to_string()),
+            tags: Vec::new(),
+            categories: Vec::new(),
+            keyword: Keyword::None,
+            default_arg: None,
+            icon_type: icon::IconType::file(path),
+            icon: None,
+            show_icon: true,
+            env: Vec::new(),
+            await_exit: false,
+            priority: 0,
+            runs: Vec::new(),
+            no_copy: false,
+            extra_locations: Vec::new(),
+            first_existing: false,
+            color: None,
+            url_terms: Vec::new(),
+            frecency: 0,
+            mtime: None,
+        }
+    }
+
     // basic idea: search query consists of multiple filters that
     // are ANDED together. And Each query is run on the name and
     // each tag and ORed together
     //
     // score functions score(item, query)
     //
+    // categories are scored the same way as tags, but with higher
+    // weights.
+    //
     // for entry (name='foo', tags = ['abc', '123'])
     //
     // for query = "foo a"
@@ -211,6 +658,44 @@ impl StoreEntry {
     // seen above, so they show up in results for searchs. But
     // they OR together a special check for (1st search token) == keyword token
     pub fn score(&self, searchtext: &str) -> u32 {
+        self.score_with(searchtext, &Default::default())
+    }
+
+    // score the entry with search settings that change how the name
+    // is matched. If split_paths is set, each component of a name
+    // that is a path is also matched like a tag
+    pub fn score_with(&self, searchtext: &str, search: &search::SearchSettings) -> u32 {
+        self.score_inner(searchtext, search, None).0
+    }
+
+    // how well the tokens of the query that only matched fuzzily
+    // match, to order entries with the same score. Zero if no token
+    // matched fuzzily
+    pub fn fuzzy_grade(&self, searchtext: &str, search: &search::SearchSettings) -> u32 {
+        self.score_inner(searchtext, search, None).1
+    }
+
+    // score the entry, and report which kinds of match each token of
+    // the query had
+    pub fn explain_score(
+        &self,
+        searchtext: &str,
+        search: &search::SearchSettings,
+    ) -> ScoreBreakdown {
+        let mut breakdown = ScoreBreakdown::default();
+        breakdown.score = self.score_inner(searchtext, search, Some(&mut breakdown)).0;
+        breakdown
+    }
+
+    // scoring shared by score_with, fuzzy_grade and explain_score,
+    // which returns the score and the fuzzy grade. With a breakdown,
+    // every token is scored, even once the entry can no longer match
+    fn score_inner(
+        &self,
+        searchtext: &str,
+        search: &search::SearchSettings,
+        mut breakdown: Option<&mut ScoreBreakdown>,
+    ) -> (u32, u32) {
         // determine if we are doing case sensitive or case - insensitive match
         let change_case = if searchtext == searchtext.to_lowercase() {
             |s: &str| s.to_uppercase()
@@ -219,46 +704,254 @@ impl StoreEntry {
         };
 
         // build temporary strings with the right case
-        let name = change_case(&self.name);
-        let tags: Vec<_> = self
+        let name = change_case(search.searchable_name(&self.name));
+        // the transliterated name is matched as another spelling of
+        // the name, and the last component of a name that is a path
+        // can be matched on its own
+        let other_names: Vec<_> = self
+            .transliterated
+            .as_deref()
+            .filter(|_| search.transliterate)
+            .into_iter()
+            .chain(self.basename.as_deref().filter(|_| search.match_basename))
+            .collect();
+        let alt_names: Vec<_> = other_names
+            .iter()
+            .map(|n| change_case(search.searchable_name(n)))
+            .collect();
+        let names: Vec<_> = std::iter::once(&name).chain(&alt_names).collect();
+        // the starts of words are found before the case is changed,
+        // so that changes of case inside names are kept. They are
+        // only needed once a token falls back to fuzzy matching
+        let name_starts = std::cell::OnceCell::new();
+        let name_starts = || {
+            name_starts.get_or_init(|| {
+                std::iter::once(self.name.as_str())
+                    .chain(other_names.iter().copied())
+                    .map(|n| word_starts(search.searchable_name(n)))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let mut tags: Vec<_> = self
             .tags
             .iter()
             .map(String::deref)
             .map(change_case)
             .collect();
-        let query: Vec<_> = searchtext.split_whitespace().map(change_case).collect();
+        if search.search_url {
+            tags.extend(self.url_terms.iter().map(String::deref).map(change_case));
+        }
+        if search.split_paths {
+            let components: Vec<_> = name.split(['/', '\\']).filter(|c| !c.is_empty()).collect();
+            if components.len() > 1 {
+                tags.extend(components.into_iter().map(str::to_string));
+            }
+        }
+        let categories: Vec<_> = self
+            .categories
+            .iter()
+            .map(String::deref)
+            .map(change_case)
+            .collect();
+        // each token of the query matches if the token or any of its
+        // synonyms match
+        let expand = |token: &str| -> Vec<String> {
+            std::iter::once(token)
+                .chain(search.synonyms(token))
+                .map(change_case)
+                .collect()
+        };
+        let tokens: Vec<_> = if search.phrase {
+            // the whole query is matched as one string
+            Some(searchtext.trim())
+                .filter(|q| !q.is_empty())
+                .into_iter()
+                .collect()
+        } else {
+            search::tokens(searchtext)
+        };
+        let query: Vec<_> = tokens.iter().copied().map(expand).collect();
 
         // if vec is empty or first element is empty, no score
-        if query.len() == 0 || query[0].len() == 0 {
-            return 0;
+        if query.len() == 0 || query[0][0].len() == 0 {
+            return (0, 0);
         }
 
-        // check to see if we match a keyword
-        let full_keyword = FULL_KEYWORD_W
-            * match &self.keyword {
-                Keyword::None => false,
-                Keyword::RawKeyword(k) => change_case(k) == query[0],
-                Keyword::EscapedKeyword(k) => change_case(k) == query[0],
-            } as u32;
+        let full_keyword = FULL_KEYWORD_W * self.keyword_active(searchtext) as u32;
+        if let Some(breakdown) = breakdown.as_deref_mut() {
+            breakdown.keyword = full_keyword > 0;
+            breakdown.priority = self.priority;
+        }
 
+        // a token can only match strings that are at least as long
+        // as it is, so we can skip searching shorter ones. This keeps
+        // very long tokens from being compared against every tag
+        let fits = |s: &str, q: &str| s.len() >= q.len();
+        let tag_contains = search.tag_match == search::TagMatch::Contains;
+        let longest = tags
+            .iter()
+            .chain(categories.iter())
+            .chain(alt_names.iter())
+            .map(String::len)
+            .fold(name.len(), usize::max);
+
+        let mut signals = score_expr::Signals {
+            keyword: full_keyword > 0,
+            priority: self.priority,
+            tokens: query.len(),
+            frecency: self.frecency,
+            ..Default::default()
+        };
         let mut running_score = u32::MAX;
+        let mut fuzzy_grade = 0;
+
+        for (token, alternatives) in tokens.iter().zip(&query) {
+            // tokens are ANDed together, so once one token fails to
+            // match, the rest of the tokens cannot change the
+            // score. Score expressions need every token to be counted
+            if search.score_expr.is_none()
+                && breakdown.is_none()
+                && (running_score == 0 || alternatives.iter().all(|q| q.len() > longest))
+            {
+                running_score = 0;
+                break;
+            }
 
-        for ref q in query {
-            running_score = running_score.min(
-                // calculate measures of a match
-                [
-                    FULL_NAME_W * ((&name == q) as u32),
-                    PARTIAL_NAME_W * (name.contains(q) as u32),
-                    FULL_TAG_W * (tags.iter().any(|t| t == q) as u32),
-                    PARTIAL_TAG_W * (tags.iter().any(|t| t.contains(q)) as u32),
-                    STARTSWITH_TAG_W * (tags.iter().any(|t| t.starts_with(q)) as u32),
-                ]
+            // calculate measures of a match, in the order of
+            // MATCH_WEIGHTS. Synonyms of a token are ORed together
+            let mut hits = [false; score_expr::MATCH_SIGNALS];
+            for q in alternatives {
+                let alternative_hits = [
+                    names.iter().any(|n| *n == q),
+                    names.iter().any(|n| fits(n, q) && n.contains(q)),
+                    names.iter().any(|n| n.starts_with(q)),
+                    tags.iter().any(|t| t == q),
+                    tag_contains && tags.iter().any(|t| fits(t, q) && t.contains(q)),
+                    tags.iter().any(|t| t.starts_with(q)),
+                    categories.iter().any(|c| c == q),
+                    categories.iter().any(|c| fits(c, q) && c.contains(q)),
+                    categories.iter().any(|c| c.starts_with(q)),
+                ];
+                for (hit, alternative) in hits.iter_mut().zip(alternative_hits) {
+                    *hit |= alternative;
+                }
+            }
+
+            for (count, hit) in signals.matches.iter_mut().zip(hits) {
+                *count += hit as u32;
+            }
+
+            let mut token_score = hits
                 .into_iter()
-                .reduce(std::cmp::max)
-                .unwrap(),
-            );
+                .zip(MATCH_WEIGHTS)
+                .map(|(hit, weight)| weight * hit as u32)
+                .max()
+                .unwrap_or(0);
+
+            // fuzzy matching is only a fallback for tokens that did
+            // not match any other way. Phrases and quoted tokens ask
+            // for their words to appear as written, so they are never
+            // matched fuzzily
+            let (fuzzy, grade) = if search.fuzzy && !search.phrase && token_score == 0 {
+                let names = || names.iter().zip(name_starts());
+                alternatives
+                    .iter()
+                    .filter(|q| !q.contains(char::is_whitespace))
+                    .flat_map(|q| {
+                        names().map(move |(n, starts)| {
+                            fuzzy_score(n, q, starts, search.boundary_bonus)
+                        })
+                    })
+                    .max()
+                    .unwrap_or((0, 0))
+            } else {
+                (0, 0)
+            };
+            token_score = token_score.max(fuzzy);
+            fuzzy_grade += grade;
+            running_score = running_score.min(token_score);
+
+            if let Some(breakdown) = breakdown.as_deref_mut() {
+                let matches = hits
+                    .into_iter()
+                    .zip(score_expr::SIGNALS)
+                    .zip(MATCH_WEIGHTS)
+                    .filter(|((hit, _), _)| *hit)
+                    .map(|((_, signal), weight)| (signal, weight))
+                    .chain((fuzzy > 0).then_some(("fuzzy_name", fuzzy)))
+                    .collect();
+                breakdown.tokens.push(TokenScore {
+                    token: token.to_string(),
+                    matches,
+                    score: token_score,
+                });
+            }
+        }
+
+        if let Some(expr) = &search.score_expr {
+            return (expr.score(&signals), fuzzy_grade);
+        }
+        let score = match search.keyword_mode {
+            search::KeywordMode::Max => running_score.max(full_keyword),
+            search::KeywordMode::Add => running_score.saturating_add(full_keyword),
+        };
+
+        // priority is only a bonus for entries that already match
+        if score > 0 {
+            (score.saturating_add(self.priority), fuzzy_grade)
+        } else {
+            (0, 0)
         }
-        running_score.max(full_keyword)
+    }
+
+    // byte ranges of the name that are matched by the tokens of the
+    // query, sorted and without overlaps, for example to highlight
+    // them. Matching follows the same case rules as scoring, and the
+    // keyword of an active keyword query is not matched against the
+    // name. An empty vector means that no part of the name matched
+    pub fn match_ranges(&self, searchtext: &str) -> Vec<(usize, usize)> {
+        let ignore_case = searchtext == searchtext.to_lowercase();
+        let same = |a: char, b: char| {
+            if ignore_case {
+                a.to_lowercase().eq(b.to_lowercase())
+            } else {
+                a == b
+            }
+        };
+
+        let mut tokens = search::tokens(searchtext);
+        if self.keyword_active(searchtext) {
+            tokens.remove(0);
+        }
+
+        let name: Vec<(usize, char)> = self.name.char_indices().collect();
+        let mut ranges = Vec::new();
+        for token in tokens {
+            let token: Vec<char> = token.chars().collect();
+            let mut start = 0;
+            while start + token.len() <= name.len() {
+                let window = &name[start..start + token.len()];
+                if window.iter().zip(&token).all(|((_, a), b)| same(*a, *b)) {
+                    let end = start + token.len();
+                    let end_byte = name.get(end).map_or(self.name.len(), |(i, _)| *i);
+                    ranges.push((name[start].0, end_byte));
+                    start = end;
+                } else {
+                    start += 1;
+                }
+            }
+        }
+
+        // merge the matches of different tokens
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
     }
 
     // format example:
@@ -269,46 +962,209 @@ impl StoreEntry {
             return self.name.clone();
         }
 
-        let param = if let Some((_, back)) = searchtext.split_once(char::is_whitespace) {
-            back
+        // without a parameter, the name keeps its placeholder unless
+        // the entry has a default
+        let param = if let Some(param) = search::keyword_param(searchtext) {
+            param
         } else {
-            "%s"
+            self.default_arg.as_deref().unwrap_or("%s")
         };
 
         format_param(&self.name, param)
     }
 
-    pub fn format_selection(&self, searchtext: &str) -> String {
-        let param = if let Some((_, back)) = searchtext.split_once(char::is_whitespace) {
-            back
-        } else {
-            "%s"
+    // check to see if the query uses the keyword of this
+    // entry. Keywords are always the first word of the query, even in
+    // phrase mode, and are matched with the same case rules as the
+    // rest of the query
+    pub fn keyword_active(&self, searchtext: &str) -> bool {
+        let keyword = match &self.keyword {
+            Keyword::None => return false,
+            Keyword::RawKeyword(k) => k,
+            Keyword::EscapedKeyword(k) => k,
+        };
+        let first_word = match searchtext.split_whitespace().next() {
+            Some(w) => w,
+            None => return false,
         };
 
+        if searchtext == searchtext.to_lowercase() {
+            keyword.to_uppercase() == first_word.to_uppercase()
+        } else {
+            keyword == first_word
+        }
+    }
+
+    // true if this entry takes a keyword parameter, but the query
+    // does not give it one
+    pub fn missing_param(&self, searchtext: &str) -> bool {
+        self.keyword().is_some()
+            && search::keyword_param(searchtext).map_or(true, |p| p.trim().is_empty())
+    }
+
+    pub fn format_selection(&self, searchtext: &str) -> String {
         let s = match &self.entry {
             EntryType::FileEntry(s) => s,
             EntryType::SystemEntry(s) => s,
+            EntryType::CopyEntry(s) => s,
+            EntryType::RunsEntry(s) => s,
+            EntryType::DirEntry(s) => s,
+        };
+
+        self.fill_param(s, searchtext)
+    }
+
+    // fill the keyword parameter from the query into a target
+    fn fill_param(&self, target: &str, searchtext: &str) -> String {
+        let param = if let Some(param) = search::keyword_param(searchtext) {
+            param
+        } else {
+            self.default_arg.as_deref().unwrap_or("")
         };
 
         let escaped_param = match self.keyword {
             Keyword::EscapedKeyword(_) => urlencoding::encode(param).into_owned(),
-            Keyword::None => return s.clone(),
+            Keyword::None => return target.to_string(),
             _ => param.to_string(),
         };
 
-        format_param(s, escaped_param)
+        format_param(target, escaped_param)
     }
 
-    pub fn handle_selection(&self, searchtext: &str) -> Result<(), Error> {
-        let func = match &self.entry {
-            EntryType::FileEntry(_) => platform::open_file,
-            EntryType::SystemEntry(_) => platform::system,
+    // the locations that selecting a file entry opens. Every
+    // location is opened, unless the entry only opens the first
+    // location that exists
+    pub fn file_targets(&self, searchtext: &str) -> Result<Vec<String>, Error> {
+        let first = match &self.entry {
+            EntryType::FileEntry(loc) => loc,
+            _ => return Ok(Vec::new()),
         };
+
+        let mut targets = std::iter::once(first)
+            .chain(&self.extra_locations)
+            .map(|loc| self.fill_param(loc, searchtext));
+
+        if !self.first_existing {
+            return Ok(targets.collect());
+        }
+
+        match targets.find(|t| location_exists(t)) {
+            Some(target) => Ok(vec![target]),
+            None => Err(Error::PlatformError(platform::Error::IoError(
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("None of the locations of '{}' exist", self.name),
+                ),
+            ))),
+        }
+    }
+
+    pub fn handle_selection(
+        &self,
+        searchtext: &str,
+        settings: &settings::Settings,
+    ) -> Result<(), Error> {
         let selection = self.format_selection(searchtext);
 
         ::log::info!(r#"Selected Entry {}("{}")"#, &self.entry, selection);
 
-        func(&selection).map_err(Error::PlatformError)
+        match &self.entry {
+            EntryType::FileEntry(_) => {
+                // every location is checked before any of them is opened
+                let targets = self.file_targets(searchtext)?;
+                for target in &targets {
+                    check_scheme(target, settings)?;
+                }
+                targets.iter().try_for_each(|t| open_location(t, settings))
+            }
+            EntryType::SystemEntry(_) => {
+                platform::system(&selection, self.env.clone(), settings.spawn_timeout())
+                    .map_err(Error::PlatformError)
+            }
+            EntryType::DirEntry(_) => {
+                check_scheme(&selection, settings)?;
+                platform::open_dir(&selection).map_err(Error::PlatformError)
+            }
+            // copy entries have nothing to open, the caller copies
+            // them to the clipboard instead
+            EntryType::CopyEntry(_) => Ok(()),
+            // the caller runs the referenced entries instead
+            EntryType::RunsEntry(_) => Ok(()),
+        }
+    }
+
+    // run the command of a system entry and wait for it to exit
+    pub fn handle_awaited_selection(
+        &self,
+        searchtext: &str,
+    ) -> Result<platform::CommandStatus, Error> {
+        let selection = self.format_selection(searchtext);
+
+        ::log::info!(
+            r#"Selected Entry {}("{}"), waiting for it to exit"#,
+            &self.entry,
+            selection
+        );
+
+        platform::system_await(&selection, self.env.clone()).map_err(Error::PlatformError)
+    }
+
+    pub fn awaits_exit(&self) -> bool {
+        self.await_exit
+    }
+
+    // what actually happens when the user requests an action for this
+    // entry. Copy entries can only be copied, and entries that run
+    // other entries or are marked no_copy can only be opened. Only
+    // entries with a local file can be revealed
+    pub fn action(&self, requested: Action) -> Action {
+        match self.entry {
+            EntryType::CopyEntry(_) => Action::Copy,
+            EntryType::RunsEntry(_) => Action::Open,
+            _ if requested == Action::Reveal && self.reveal_path().is_none() => Action::Open,
+            _ if self.no_copy && requested != Action::Reveal => Action::Open,
+            _ => requested,
+        }
+    }
+
+    // the actions that can be taken for this entry, in the order
+    // that repeated presses of enter cycle through them
+    pub fn actions(&self, allow_copy: bool) -> Vec<Action> {
+        let mut actions = vec![self.action(Action::Open)];
+        for extra in [Action::Reveal, Action::Copy] {
+            if extra == Action::Copy && !allow_copy {
+                continue;
+            }
+            let action = self.action(extra);
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+
+    // show the entry's file in the file manager
+    pub fn reveal(&self) -> Result<(), Error> {
+        let path = self.reveal_path().ok_or_else(|| {
+            platform::Error::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("'{}' has no local file to reveal", self.name),
+            ))
+        });
+        path.and_then(platform::reveal)
+            .map_err(Error::PlatformError)
+    }
+
+    // style of the entry's button. A selected entry is highlighted
+    // with its own color, if it has one
+    pub fn button_style(&self, selected: bool) -> theme::ButtonStyle {
+        match self.color {
+            _ if !selected => theme::ButtonStyle::Transparent,
+            Some([r, g, b, a]) => {
+                theme::ButtonStyle::Highlight(iced::Color::from_rgba8(r, g, b, a as f32 / 255.0))
+            }
+            None => theme::ButtonStyle::Selected,
+        }
     }
 
     pub fn build_entry<'a, F, Message, Renderer>(
@@ -317,6 +1173,7 @@ impl StoreEntry {
         searchtext: &str,
         settings: &ui::UISettings,
         selected: bool,
+        action: Action,
         my_id: EntryId,
     ) -> iced::Element<'a, Message, Renderer>
     where
@@ -332,11 +1189,7 @@ impl StoreEntry {
             settings.theme.text_color.clone()
         };
 
-        let button_style = if selected {
-            theme::ButtonStyle::Selected
-        } else {
-            theme::ButtonStyle::Transparent
-        };
+        let button_style = self.button_style(selected);
 
         let text_color: iced::Color = text_color.into();
 
@@ -347,15 +1200,30 @@ impl StoreEntry {
             .vertical_alignment(iced::alignment::Vertical::Center)
             .shaping(iced::widget::text::Shaping::Advanced);
 
+        // long locations are cut to one line instead of wrapping
+        let columns =
+            settings.width as f32 / (settings.entry.description_size() as f32 * COLUMN_WIDTH);
+        let subtitle = self.subtitle(searchtext, &settings.entry).map(|location| {
+            iced::widget::text::Text::new(truncate_width(&location, columns as usize).into_owned())
+                .size(settings.entry.description_size())
+                .style(iced::Color {
+                    a: text_color.a * LOCATION_ALPHA,
+                    ..text_color
+                })
+                .line_height(LINE_HEIGHT)
+                .horizontal_alignment(iced::alignment::Horizontal::Left)
+                .shaping(iced::widget::text::Shaping::Advanced)
+        });
+
         let description = match &self.description {
             Some(desc) => {
-                let paragraphs = desc_to_paragraphs(desc);
+                let paragraphs = description_paragraphs(desc, settings.entry.markdown);
                 let paragraphs = paragraphs
-                    .unwrap_or(vec![desc.to_string()])
                     .into_iter()
                     .map(|paragraph| {
                         iced::widget::text::Text::new(paragraph)
-                            .size(settings.entry.description_size)
+                            .size(settings.entry.description_size())
+                            .line_height(LINE_HEIGHT)
                             .style(iced::Color::from(text_color))
                             .horizontal_alignment(iced::alignment::Horizontal::Left)
                             .vertical_alignment(iced::alignment::Vertical::Center)
@@ -368,29 +1236,60 @@ impl StoreEntry {
             None => iced::widget::Column::new(),
         };
 
-        let icon = iced::widget::image::Image::new(
-            self.icon
-                .clone()
-                .unwrap_or_else(|| icon::default_icon(&settings.icon)),
-        );
-
-        let icon = icon
-            .height(settings.entry.common.text_size())
-            .width(settings.entry.common.text_size());
+        let icon: iced::Element<'a, Message, Renderer> = match self.shown_icon(&settings.icon) {
+            Some(icon) => iced::widget::image::Image::new(icon)
+                .height(settings.entry.common.text_size())
+                .width(settings.entry.common.text_size())
+                .into(),
+            // keep the names of all entries lined up
+            None => iced::widget::Space::new(
+                settings.entry.common.text_size(),
+                settings.entry.common.text_size(),
+            )
+            .into(),
+        };
 
-        let icon_row = iced::widget::Row::new()
+        let mut icon_row = iced::widget::Row::new()
             .height(iced::Length::Fixed(
-                (settings.entry.common.text_size() + 4) as f32,
+                (settings.entry.common.text_size() + TITLE_PADDING) as f32,
             ))
             .spacing(2)
             .align_items(iced::Alignment::Center)
             .push(icon)
             .push(title_text);
 
-        let column = iced::widget::Column::new()
+        if let Some(keyword) = self.keyword_badge(&settings.entry) {
+            let badge = iced::widget::text::Text::new(keyword.to_string())
+                .size(settings.entry.description_size())
+                .style(text_color)
+                .vertical_alignment(iced::alignment::Vertical::Center)
+                .shaping(iced::widget::text::Shaping::Advanced);
+            icon_row = icon_row.push(
+                iced::widget::container::Container::new(badge)
+                    .padding([0, 4])
+                    .style(theme::ContainerStyle::Badge),
+            );
+        }
+
+        // let the user know what will happen if they select this entry
+        if selected {
+            let action_text = iced::widget::text::Text::new(self.action(action).label())
+                .size(settings.entry.description_size())
+                .style(text_color)
+                .horizontal_alignment(iced::alignment::Horizontal::Right)
+                .vertical_alignment(iced::alignment::Vertical::Center);
+            icon_row = icon_row
+                .push(iced::widget::Space::with_width(iced::Length::Fill))
+                .push(action_text);
+        }
+
+        let mut column = iced::widget::Column::new()
             .width(iced::Length::Fill)
-            .push(icon_row)
-            .push(description);
+            .push(icon_row);
+        if let Some(subtitle) = subtitle {
+            column = column.push(subtitle);
+        }
+        let column = column.push(description);
 
         // need an empty container to create padding around title.
         // let _container =
@@ -399,25 +1298,254 @@ impl StoreEntry {
         let button = iced::widget::button::Button::new(column)
             .on_press(message_func(my_id))
             .style(button_style)
+            .padding(BUTTON_PADDING)
             .width(iced::Length::Fill);
 
         let element: iced::Element<'_, _, _> = button.into();
         element
     }
 
+    // height of the widget built by build_entry. Descriptions are
+    // assumed to take one line per paragraph, so entries with
+    // descriptions that wrap are taller than this
+    pub fn row_height(&self, settings: &ui::UISettings) -> f32 {
+        let line = settings.entry.description_size() as f32 * LINE_HEIGHT;
+        let mut lines = match &self.description {
+            Some(desc) => description_paragraphs(desc, settings.entry.markdown).len(),
+            None => 0,
+        };
+        if self.subtitle("", &settings.entry).is_some() {
+            lines += 1;
+        }
+        let title = (settings.entry.common.text_size() + TITLE_PADDING) as f32;
+        2.0 * BUTTON_PADDING + title + lines as f32 * line
+    }
+
+    // the resolved target shown below the entry name, if enabled.
+    // copy entries are skipped so that snippets stay hidden
+    pub fn subtitle(&self, searchtext: &str, settings: &EntrySettings) -> Option<String> {
+        if !settings.show_location || matches!(self.entry, EntryType::CopyEntry(_)) {
+            return None;
+        }
+        Some(self.format_selection(searchtext))
+    }
+
+    // the keyword shown next to the entry name, if enabled
+    pub fn keyword_badge(&self, settings: &EntrySettings) -> Option<&str> {
+        match &self.keyword {
+            Keyword::RawKeyword(k) | Keyword::EscapedKeyword(k) if settings.show_keywords => {
+                Some(k)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn frecency(&self) -> u32 {
+        self.frecency
+    }
+
+    pub fn set_frecency(&mut self, frecency: u32) {
+        self.frecency = frecency;
+    }
+
+    pub fn mtime(&self) -> Option<std::time::SystemTime> {
+        self.mtime
+    }
+
+    pub fn set_mtime(&mut self, mtime: Option<std::time::SystemTime>) {
+        self.mtime = mtime;
+    }
+
+    // target of the entry, before any keyword parameter is filled in
+    pub fn location(&self) -> &str {
+        match &self.entry {
+            EntryType::FileEntry(s) => s,
+            EntryType::SystemEntry(s) => s,
+            EntryType::CopyEntry(s) => s,
+            EntryType::RunsEntry(s) => s,
+            EntryType::DirEntry(s) => s,
+        }
+    }
+
+    // the local file or directory that the entry opens, if it is
+    // not a url or a command. The path may not exist
+    fn local_path(&self) -> Option<std::path::PathBuf> {
+        let loc = match &self.entry {
+            EntryType::FileEntry(loc) | EntryType::DirEntry(loc) => loc,
+            _ => return None,
+        };
+
+        match Url::parse(loc) {
+            Ok(url) if url.scheme() == "file" => url.to_file_path().ok(),
+            Ok(url) if !icon::is_drive_path(&url) => None,
+            _ => Some(std::path::PathBuf::from(loc)),
+        }
+    }
+
+    // the local file that can be previewed for this entry, if
+    // any. Entries that take a keyword parameter are not previewed
+    pub fn preview_path(&self) -> Option<std::path::PathBuf> {
+        if !matches!(
+            (&self.entry, &self.keyword),
+            (EntryType::FileEntry(_), Keyword::None)
+        ) {
+            return None;
+        }
+
+        self.local_path().filter(|p| p.is_file())
+    }
+
+    // when the local file of a file entry was last modified. None
+    // for other entries, or if the file cannot be found
+    pub fn modified(&self) -> Option<std::time::SystemTime> {
+        if !matches!(self.entry, EntryType::FileEntry(_)) {
+            return None;
+        }
+
+        let path = self.local_path()?;
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    // the local file that selecting the entry with Action::Reveal
+    // shows in the file manager. Entries that take a keyword
+    // parameter are not revealed
+    fn reveal_path(&self) -> Option<std::path::PathBuf> {
+        match self.keyword {
+            Keyword::None => self.local_path().filter(|p| p.exists()),
+            _ => None,
+        }
+    }
+
+    // what kind of target this entry has: a location, url, or system command
+    pub fn kind(&self) -> &'static str {
+        match &self.entry {
+            EntryType::SystemEntry(_) => "system",
+            EntryType::CopyEntry(_) => "copy",
+            EntryType::RunsEntry(_) => "runs",
+            EntryType::DirEntry(_) => "dir",
+            EntryType::FileEntry(loc) => match Url::parse(&format_param(loc, "")) {
+                Ok(url) if !icon::is_drive_path(&url) => "url",
+                _ => "location",
+            },
+        }
+    }
+
+    // names of the entries that selecting this entry runs, in order
+    pub fn runs(&self) -> &[String] {
+        &self.runs
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn keyword(&self) -> Option<&str> {
+        match &self.keyword {
+            Keyword::None => None,
+            Keyword::RawKeyword(k) => Some(k),
+            Keyword::EscapedKeyword(k) => Some(k),
+        }
+    }
+
     // pull out the icon type of this entry in preparation for
     // determing it. current icontype is replaced with pending value
     pub fn icontype(&self) -> &icon::IconType {
         &self.icon_type
     }
 
+    // problems with the entry that are not severe enough to stop it
+    // from being loaded
+    pub fn warnings(&self) -> Vec<String> {
+        match self.icon_type.custom_path() {
+            Some(p) if !p.exists() => vec![format!(
+                "Icon for entry ['{}'] not found: {}",
+                self.name,
+                p.display()
+            )],
+            _ => Vec::new(),
+        }
+    }
+
     pub fn icon(&mut self, icon: Icon) {
         self.icon = Some(icon);
     }
 
+    // drop the icon, so that it can be freed once the icon cache
+    // evicts it. It is looked up again the next time it is shown
+    pub fn forget_icon(&mut self) {
+        self.icon = None;
+    }
+
     pub fn icon_loaded(&self) -> bool {
         self.icon.is_some()
     }
+
+    // whether the entry is shown with an icon at all
+    pub fn has_icon(&self) -> bool {
+        self.show_icon
+    }
+
+    // the icon to draw for this entry. Until the icon is loaded,
+    // this is the default icon
+    pub fn shown_icon(&self, settings: &icon::IconSettings) -> Option<Icon> {
+        self.show_icon.then(|| {
+            self.icon
+                .clone()
+                .unwrap_or_else(|| icon::default_icon(settings))
+        })
+    }
+}
+
+// the file name at the end of a name like "project/src/main.rs", if
+// the name has more than one component
+fn basename(name: &str) -> Option<String> {
+    let base = name
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .filter(|b| !b.is_empty() && *b != name)?;
+    Some(base.to_string())
+}
+
+// latin spelling of text that uses other scripts, so that it can be
+// found by typing on a latin keyboard. Returns None for text that
+// is already ascii
+fn transliterate(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        None
+    } else {
+        Some(deunicode::deunicode(text))
+    }
+}
+
+// shorten text to at most max_width columns, ending it with an
+// ellipsis if anything was removed. Text is only cut between grapheme
+// clusters, and wide characters such as CJK count as two columns
+pub fn truncate_width(text: &str, max_width: usize) -> Cow<'_, str> {
+    if text.width() <= max_width {
+        return Cow::Borrowed(text);
+    }
+    if max_width == 0 {
+        return Cow::Borrowed("");
+    }
+
+    // leave room for the ellipsis
+    let budget = max_width - 1;
+    let mut used = 0;
+    let mut truncated = String::new();
+    for grapheme in text.graphemes(true) {
+        used += grapheme.width();
+        if used > budget {
+            break;
+        }
+        truncated.push_str(grapheme);
+    }
+    truncated.push('…');
+    Cow::Owned(truncated)
 }
 
 fn format_param<S: AsRef<str>>(fmt_str: &str, searchtext: S) -> String {
@@ -428,6 +1556,187 @@ fn format_param<S: AsRef<str>>(fmt_str: &str, searchtext: S) -> String {
         .join("%")
 }
 
+// open a url template with the whole query filled in as its
+// parameter. Used when there is no entry to select
+pub fn open_fallback(
+    template: &str,
+    query: &str,
+    settings: &settings::Settings,
+) -> Result<(), Error> {
+    let selection = format_param(template, urlencoding::encode(query));
+
+    ::log::info!(r#"Opening fallback "{}""#, selection);
+
+    open_location(&selection, settings)
+}
+
+// open a location of a file entry or a fallback url. Every url that
+// jolly opens goes through here, so the scheme is always checked
+fn open_location(location: &str, settings: &settings::Settings) -> Result<(), Error> {
+    check_scheme(location, settings)?;
+    match Url::parse(location) {
+        Ok(url) if settings.use_scheme_handler && !icon::is_drive_path(&url) => {
+            platform::open_url_with_handler(&url)
+        }
+        _ => platform::open_file(location),
+    }
+    .map_err(Error::PlatformError)
+}
+
+// score and grade a token whose characters appear in the text in
+// order, but not as one substring. Matches where most of the
+// characters start words, according to starts, score boundary_w
+// more. Matches that start closer to the start of the text, or that
+// keep more of the token together, are graded higher. Zero if the
+// characters do not all appear
+fn fuzzy_score(text: &str, token: &str, starts: &[bool], boundary_w: u32) -> (u32, u32) {
+    let text: Vec<char> = text.chars().collect();
+    let token: Vec<char> = token.chars().collect();
+    let Some(positions) = subsequence(&text, &token, |_| false) else {
+        return (0, 0);
+    };
+    let Some(&start) = positions.first() else {
+        return (0, 0);
+    };
+
+    // one point less for each character before the match
+    let mut grade = (FUZZY_START_W as usize).saturating_sub(start) as u32;
+
+    // the share of neighbouring characters of the token that are
+    // also neighbours in the text
+    let pairs = positions.len() - 1;
+    let adjacent = positions.windows(2).filter(|w| w[1] == w[0] + 1).count();
+    if pairs > 0 {
+        grade += FUZZY_RUN_W * adjacent as u32 / pairs as u32;
+    }
+
+    let mut score = FUZZY_NAME_W;
+
+    // the case of the text can change its length, in which case the
+    // starts of words no longer line up with it
+    if boundary_w > 0 && starts.len() == text.len() {
+        let aligned = subsequence(&text, &token, |i| starts[i]).unwrap_or_default();
+        let hits = aligned.iter().filter(|i| starts[**i]).count();
+        if hits >= 2 && hits * 2 >= token.len() {
+            score += boundary_w.min(MAX_BOUNDARY_BONUS);
+        }
+    }
+    (score, grade)
+}
+
+// positions of the characters of token in text, in order. Each
+// character is matched at the first position that is preferred and
+// still lets the rest of the token match, or else at the first
+// position it appears
+fn subsequence(
+    text: &[char],
+    token: &[char],
+    prefer: impl Fn(usize) -> bool,
+) -> Option<Vec<usize>> {
+    let fits = |text: &[char], token: &[char]| {
+        let mut text = text.iter();
+        token.iter().all(|c| text.any(|t| t == c))
+    };
+
+    let mut positions = Vec::with_capacity(token.len());
+    let mut from = 0;
+    for (k, c) in token.iter().enumerate() {
+        let mut candidates = (from..text.len()).filter(|i| text[*i] == *c);
+        let first = candidates.clone().next()?;
+        let chosen = candidates
+            .find(|i| prefer(*i) && fits(&text[i + 1..], &token[k + 1..]))
+            .unwrap_or(first);
+        positions.push(chosen);
+        from = chosen + 1;
+    }
+    Some(positions)
+}
+
+// whether each character of text starts a word. A word starts at a
+// letter or digit that begins the text or follows whitespace or a
+// separator like '-' or '_', or at an uppercase letter after a
+// lowercase one, like the C in "gitCheckout"
+fn word_starts(text: &str) -> Vec<bool> {
+    let mut prev: Option<char> = None;
+    text.chars()
+        .map(|c| {
+            let start = match prev {
+                None => c.is_alphanumeric(),
+                Some(p) => {
+                    (!p.is_alphanumeric() && c.is_alphanumeric())
+                        || (p.is_lowercase() && c.is_uppercase())
+                }
+            };
+            prev = Some(c);
+            start
+        })
+        .collect()
+}
+
+// check if a location can be opened. Urls other than local files
+// are assumed to exist
+fn location_exists(location: &str) -> bool {
+    match Url::parse(location) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().map_or(false, |p| p.exists()),
+        Ok(url) if !icon::is_drive_path(&url) => true,
+        _ => std::path::Path::new(location).exists(),
+    }
+}
+
+// refuse to open urls whose scheme is not allowed by the
+// settings. Selections that are not urls are always allowed
+fn check_scheme(selection: &str, settings: &settings::Settings) -> Result<(), Error> {
+    match Url::parse(selection) {
+        Ok(url) if !icon::is_drive_path(&url) && !settings.scheme_allowed(url.scheme()) => {
+            Err(Error::DisallowedScheme(url.scheme().to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+// split a description into paragraphs to display, falling back
+// according to mode if the markdown is not supported
+fn description_paragraphs(desc: &str, mode: MarkdownMode) -> Vec<String> {
+    if let Some(paragraphs) = desc_to_paragraphs(desc) {
+        return paragraphs;
+    }
+
+    match mode {
+        MarkdownMode::Strict => Vec::new(),
+        MarkdownMode::Raw => vec![desc.to_string()],
+        MarkdownMode::Plain => desc_to_plain(desc),
+    }
+}
+
+// strip all markdown formatting, keeping one paragraph per block element
+fn desc_to_plain(desc: &str) -> Vec<String> {
+    use pulldown_cmark::{Event, Parser, Tag};
+    let mut result = Vec::new();
+    let mut cur_paragraph = String::new();
+    for event in Parser::new(desc) {
+        match event {
+            Event::Text(txt) | Event::Code(txt) => cur_paragraph.push_str(&txt),
+            Event::SoftBreak | Event::HardBreak => cur_paragraph.push(' '),
+            Event::End(
+                Tag::Paragraph | Tag::Heading(..) | Tag::Item | Tag::CodeBlock(_) | Tag::BlockQuote,
+            ) => {
+                let paragraph = cur_paragraph.trim_end();
+                if !paragraph.is_empty() {
+                    result.push(paragraph.to_string());
+                }
+                cur_paragraph.clear();
+            }
+            _ => (),
+        }
+    }
+
+    let paragraph = cur_paragraph.trim_end();
+    if !paragraph.is_empty() {
+        result.push(paragraph.to_string());
+    }
+    result
+}
+
 fn desc_to_paragraphs(desc: &str) -> Option<Vec<String>> {
     use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
     let p = Parser::new(desc);
@@ -493,7 +1802,7 @@ mod tests {
 
         if let toml::Value::Table(table) = value {
             let (k, v) = table.into_iter().next().unwrap();
-            StoreEntry::from_value(k, v).unwrap()
+            StoreEntry::from_value(k, v, None).unwrap()
         } else {
             panic!("Toml is not a Table")
         }
@@ -508,11 +1817,12 @@ mod tests {
         );
 
         // if we give a lowercase query, then default case insensitive match
-        assert_eq!(entry.score("fo"), STARTSWITH_TAG_W);
+        assert_eq!(entry.score("fo"), STARTSWITH_NAME_W);
         // if we give a
         assert_eq!(entry.score("FO"), STARTSWITH_TAG_W);
         assert_eq!(entry.score("FOO"), FULL_TAG_W);
-        assert_eq!(entry.score("fO"), PARTIAL_NAME_W);
+        assert_eq!(entry.score("fO"), STARTSWITH_NAME_W);
+        assert_eq!(entry.score("OO"), PARTIAL_NAME_W);
     }
 
     #[test]
@@ -531,11 +1841,25 @@ mod tests {
         assert_eq!(entry.score("az"), PARTIAL_TAG_W);
 
         assert_eq!(entry.score("baz"), FULL_TAG_W);
-        assert_eq!(entry.score("bar fo"), STARTSWITH_TAG_W);
+        assert_eq!(entry.score("bar fo"), STARTSWITH_NAME_W);
+        assert_eq!(entry.score("bar ba"), STARTSWITH_TAG_W);
         assert_eq!(entry.score("bar az"), PARTIAL_TAG_W);
         assert_eq!(entry.score(""), 0);
     }
 
+    #[test]
+    fn name_prefix_score() {
+        let entry = parse_entry(
+            r#"['github']
+                location = "https://github.com""#,
+        );
+
+        // prefixes of the name beat matches in the middle of the name
+        assert_eq!(entry.score("git"), STARTSWITH_NAME_W);
+        assert_eq!(entry.score("hub"), PARTIAL_NAME_W);
+        assert_eq!(entry.score("github"), FULL_NAME_W);
+    }
+
     #[test]
     fn keword_score() {
         let entry = parse_entry(
@@ -546,7 +1870,7 @@ mod tests {
         );
 
         // if you dont use a keyword, score normally
-        assert_eq!(entry.score("fo"), STARTSWITH_TAG_W);
+        assert_eq!(entry.score("fo"), STARTSWITH_NAME_W);
 
         // otherwise get big bonus for using keyword
         assert_eq!(entry.score("y foo"), FULL_KEYWORD_W);
@@ -571,6 +1895,22 @@ mod tests {
                         .collect(),
                     icon: None,
                     icon_type: IconType::file("test/location"),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: None,
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
             (
@@ -584,6 +1924,22 @@ mod tests {
                     tags: [].into_iter().map(str::to_string).collect(),
                     icon: None,
                     icon_type: IconType::file("test/location"),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: None,
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
             (
@@ -597,6 +1953,22 @@ mod tests {
                     tags: [].into_iter().map(str::to_string).collect(),
                     icon: None,
                     icon_type: IconType::url(url::Url::parse("tel:12345").unwrap()),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: None,
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
             (
@@ -613,6 +1985,22 @@ mod tests {
                         .collect(),
                     icon: None,
                     icon_type: IconType::file("test/location/foo.txt"),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: Some("foo.txt".to_string()),
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
             (
@@ -626,6 +2014,22 @@ mod tests {
                     tags: [].into_iter().map(str::to_string).collect(),
                     icon: None,
                     icon_type: IconType::file("foo.txt"),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: None,
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
             (
@@ -639,6 +2043,22 @@ mod tests {
                     tags: [].into_iter().map(str::to_string).collect(),
                     icon: None,
                     icon_type: IconType::file("foo.txt"),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: None,
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
             (
@@ -652,6 +2072,22 @@ mod tests {
                     tags: [].into_iter().map(str::to_string).collect(),
                     icon: None,
                     icon_type: IconType::custom("asdf.png"),
+                    no_copy: false,
+                    await_exit: false,
+                    extra_locations: Vec::new(),
+                    first_existing: false,
+                    color: None,
+                    url_terms: Vec::new(),
+                    frecency: 0,
+                    mtime: None,
+                    transliterated: None,
+                    basename: None,
+                    runs: Vec::new(),
+                    show_icon: true,
+                    default_arg: None,
+                    categories: Vec::new(),
+                    priority: 0,
+                    env: Vec::new(),
                 },
             ),
         ];
@@ -684,12 +2120,147 @@ mod tests {
                 .collect(),
             icon: None,
             icon_type: IconType::system("foo bar"),
+            no_copy: false,
+            await_exit: false,
+            extra_locations: Vec::new(),
+            first_existing: false,
+            color: None,
+            url_terms: Vec::new(),
+            frecency: 0,
+            mtime: None,
+            transliterated: None,
+            basename: basename(&dirname),
+            runs: Vec::new(),
+            show_icon: true,
+            default_arg: None,
+            categories: Vec::new(),
+            priority: 0,
+            env: Vec::new(),
         };
 
         let entry = parse_entry(&toml);
         assert_eq!(expected_entry, entry);
     }
 
+    #[test]
+    fn system_entry_env() {
+        let entry = parse_entry(
+            r#"['foo']
+               system = 'foo bar'
+               env = { FOO = "bar", BAZ = "quux" }"#,
+        );
+
+        assert_eq!(
+            entry.env,
+            vec![
+                ("BAZ".to_string(), "quux".to_string()),
+                ("FOO".to_string(), "bar".to_string())
+            ]
+        );
+
+        let value: toml::Value = toml::from_str(
+            r#"location = 'foo'
+               env = { FOO = "bar" }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            StoreEntry::from_value("foo".into(), value, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn system_entry_await() {
+        let entry = parse_entry("['foo']\nsystem = 'foo bar'\nawait = true");
+        assert!(entry.awaits_exit());
+        let entry = parse_entry("['foo']\nsystem = 'foo bar'");
+        assert!(!entry.awaits_exit());
+
+        let value: toml::Value = toml::from_str("location = 'foo'\nawait = true").unwrap();
+        assert!(matches!(
+            StoreEntry::from_value("foo".into(), value, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn system_entry_collapse_whitespace() {
+        let toml = r#"['backup']
+            system = '''
+                rsync -a
+                  --delete \
+                  ~/notes/   remote:notes/
+            '''
+            collapse_whitespace = true
+            "#;
+        let entry = parse_entry(toml);
+        assert_eq!(
+            entry.format_selection("backup"),
+            "rsync -a --delete ~/notes/   remote:notes/"
+        );
+
+        // without the key, the command is run as written
+        let entry = parse_entry(&toml.replace("collapse_whitespace = true", ""));
+        assert!(entry.format_selection("backup").contains('\n'));
+
+        // single line commands keep their spacing
+        let entry = parse_entry("['foo']\nsystem = '  echo  \"a  b\"'\ncollapse_whitespace = true");
+        assert_eq!(entry.format_selection("foo"), "  echo  \"a  b\"");
+
+        // keyword parameters are filled into the collapsed command
+        let entry = parse_entry(
+            "['say %s']\nsystem = '''echo\n  %s'''\nkeyword = 's'\ncollapse_whitespace = true",
+        );
+        assert_eq!(entry.format_selection("s hi"), "echo hi");
+
+        let value: toml::Value =
+            toml::from_str("location = 'foo'\ncollapse_whitespace = true").unwrap();
+        assert!(matches!(
+            StoreEntry::from_value("foo".into(), value, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn dir_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirname = dir.path().to_string_lossy();
+        let entry = parse_entry(&format!("['projects']\ndir = '{dirname}'"));
+
+        assert_eq!(entry.entry, EntryType::DirEntry(dirname.to_string()));
+        assert_eq!(entry.kind(), "dir");
+        assert_eq!(entry.icon_type, IconType::file(dir.path()));
+        assert_eq!(entry.format_selection("projects"), dirname);
+        assert_eq!(entry.action(Action::Copy), Action::Copy);
+
+        let entry = parse_entry("['code %s']\ndir = '/src/%s'\nkeyword = 'c'");
+        assert_eq!(entry.format_selection("c jolly"), "/src/jolly");
+
+        let value: toml::Value =
+            toml::from_str(&format!("location = 'foo'\ndir = '{dirname}'")).unwrap();
+        assert!(matches!(
+            StoreEntry::from_value("foo".into(), value, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn dir_entry_opens_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "").unwrap();
+
+        // dir entries are opened with the file manager, which refuses
+        // anything that is not a directory. Location entries would
+        // open the file
+        let entry = parse_entry(&format!("['notes']\ndir = '{}'", file.display()));
+        let result = entry.handle_selection("notes", &Default::default());
+        match result {
+            Err(Error::PlatformError(e)) => assert!(e.to_string().contains("is not a directory")),
+            other => panic!("expected an error, got {other:?}"),
+        }
+    }
+
     #[test]
     fn single_dir_entry() {
         let dir = tempfile::tempdir().unwrap();
@@ -710,6 +2281,22 @@ mod tests {
                 .collect(),
             icon: None,
             icon_type: IconType::file(dirname.to_string()),
+            no_copy: false,
+            await_exit: false,
+            extra_locations: Vec::new(),
+            first_existing: false,
+            color: None,
+            url_terms: Vec::new(),
+            frecency: 0,
+            mtime: None,
+            transliterated: None,
+            basename: basename(&dirname),
+            runs: Vec::new(),
+            show_icon: true,
+            default_arg: None,
+            categories: Vec::new(),
+            priority: 0,
+            env: Vec::new(),
         };
 
         let entry = parse_entry(&toml);
@@ -758,6 +2345,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keyword_default_arg() {
+        let entry = parse_entry(
+            r#"['translate %s']
+               url = 'https://example.com/?q=%s'
+               keyword = 'tr'"#,
+        );
+        assert_eq!(entry.format_name("tr"), "translate %s");
+        assert_eq!(entry.format_selection("tr"), "https://example.com/?q=");
+
+        let entry = parse_entry(
+            r#"['translate %s']
+               url = 'https://example.com/?q=%s'
+               keyword = 'tr'
+               default_arg = 'hello world'"#,
+        );
+        assert_eq!(entry.format_name("tr"), "translate hello world");
+        assert_eq!(
+            entry.format_selection("tr"),
+            "https://example.com/?q=hello%20world"
+        );
+        // a typed parameter always wins over the default
+        assert_eq!(
+            entry.format_selection("tr foo"),
+            "https://example.com/?q=foo"
+        );
+
+        let value: toml::Value = toml::from_str(
+            r#"location = 'foo'
+               default_arg = 'bar'"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            StoreEntry::from_value("foo".into(), value, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn multiple_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&b, "").unwrap();
+
+        let toml = format!(
+            r#"['notes']
+               location = ['{}', '{}']"#,
+            a.display(),
+            b.display()
+        );
+        let entry = parse_entry(&toml);
+        // every location is opened, whether it exists or not
+        assert_eq!(
+            entry.file_targets("notes").unwrap(),
+            vec![a.display().to_string(), b.display().to_string()]
+        );
+
+        let toml = format!(
+            r#"['notes']
+               location = ['{}', '{}']
+               first_existing = true"#,
+            a.display(),
+            b.display()
+        );
+        let entry = parse_entry(&toml);
+        assert_eq!(
+            entry.file_targets("notes").unwrap(),
+            vec![b.display().to_string()]
+        );
+
+        std::fs::remove_file(&b).unwrap();
+        assert!(entry.file_targets("notes").is_err());
+
+        // only entries with several locations can pick one of them
+        let value: toml::Value = toml::from_str(
+            r#"location = 'foo'
+               first_existing = true"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            StoreEntry::from_value("foo".into(), value, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn multiple_locations_keyword() {
+        let entry = parse_entry(
+            r#"['logs %s']
+               location = ['/var/log/%s.log', '/tmp/%s.log']
+               keyword = 'log'"#,
+        );
+        assert_eq!(
+            entry.file_targets("log foo").unwrap(),
+            vec!["/var/log/foo.log", "/tmp/foo.log"]
+        );
+    }
+
+    #[test]
+    fn truncate_display_width() {
+        assert_eq!(truncate_width("hello world", 11), "hello world");
+        assert_eq!(truncate_width("hello world", 8), "hello w…");
+        assert_eq!(truncate_width("hello", 0), "");
+
+        // CJK characters are two columns wide
+        assert_eq!(truncate_width("日本語", 6), "日本語");
+        assert_eq!(truncate_width("日本語テキスト", 7), "日本語…");
+        assert_eq!(truncate_width("日本語テキスト", 8), "日本語…");
+        assert_eq!(truncate_width("日本語テキスト", 9), "日本語テ…");
+
+        // combining marks stay with the character they modify
+        let accents = "e\u{301}e\u{301}e\u{301}x";
+        assert_eq!(truncate_width(accents, 4), accents);
+        assert_eq!(truncate_width(accents, 3), "e\u{301}e\u{301}…");
+
+        assert_eq!(truncate_width("😀😀😀", 4), "😀…");
+        assert_eq!(truncate_width("a😀b", 3), "a…");
+        for width in 0..12 {
+            let truncated = truncate_width("ab日本😀e\u{301}cd", width);
+            assert!(truncated.width() <= width, "{width}: {truncated}");
+        }
+    }
+
     #[test]
     fn test_format() {
         let tests = [
@@ -803,6 +2514,772 @@ mod tests {
         }
     }
 
+    #[test]
+    fn action_follows_modifiers() {
+        use keyboard::Modifiers;
+        use settings::OpenCopyModifier;
+
+        let open = Action::from_modifiers(Modifiers::default(), OpenCopyModifier::None);
+        assert_eq!(open, Action::Open);
+        assert_eq!(open.label(), "Open");
+
+        let copy = Action::from_modifiers(Modifiers::COMMAND, OpenCopyModifier::None);
+        assert_eq!(copy, Action::Copy);
+        assert_eq!(copy.label(), "Copy");
+
+        // other modifiers dont change the action
+        let shift = Action::from_modifiers(Modifiers::SHIFT, OpenCopyModifier::None);
+        assert_eq!(shift, Action::Open);
+        let both = Modifiers::COMMAND | Modifiers::SHIFT;
+        assert_eq!(
+            Action::from_modifiers(both, OpenCopyModifier::None),
+            Action::Copy
+        );
+
+        // unless they are the open_copy modifier
+        let open_copy = Action::from_modifiers(both, OpenCopyModifier::Shift);
+        assert_eq!(open_copy, Action::OpenAndCopy);
+        assert_eq!(open_copy.label(), "Open & Copy");
+        assert_eq!(
+            Action::from_modifiers(both, OpenCopyModifier::Alt),
+            Action::Copy
+        );
+        assert_eq!(
+            Action::from_modifiers(Modifiers::SHIFT, OpenCopyModifier::Shift),
+            Action::Open
+        );
+    }
+
+    #[test]
+    fn long_tokens() {
+        let long = "a".repeat(100_000);
+        let entry = parse_entry("[aaa]\nlocation = 'bar'\ntags = ['aaaa']");
+
+        assert_eq!(entry.score(&long), 0);
+        assert_eq!(entry.score(&format!("aaa {long}")), 0);
+        assert_eq!(entry.score(&format!("{long} aaa")), 0);
+
+        // tokens as long as the longest tag still match
+        assert_eq!(entry.score("aaaa"), FULL_TAG_W);
+        assert_eq!(entry.score("aaa aaaa"), FULL_TAG_W);
+
+        // the keyword only depends on the first token, so long
+        // parameters do not affect it
+        let entry = parse_entry("[foo]\nlocation = 'bar'\nkeyword = 'k'");
+        assert_eq!(entry.score(&format!("k {long}")), FULL_KEYWORD_W);
+
+        // a large store with a long token has no matches
+        let toml: String = (0..1000)
+            .map(|i| format!("['entry{i}']\ntags = ['tag{i}', 'other']\n"))
+            .collect();
+        let store = crate::store::tests::parse_store(&toml).unwrap();
+        assert!(store.find_matches(&long).is_empty());
+        assert_eq!(store.find_matches("entry999 tag999"), vec![999]);
+    }
+
+    #[test]
+    fn category_score() {
+        let entry = parse_entry(
+            r#"['foo']
+		location = 'bar'
+		tags = ["home"]
+		categories = ["work", "personal"]"#,
+        );
+
+        assert_eq!(entry.score("work"), FULL_CATEGORY_W);
+        assert_eq!(entry.score("pers"), STARTSWITH_CATEGORY_W);
+        assert_eq!(entry.score("rso"), PARTIAL_CATEGORY_W);
+        assert_eq!(entry.score("home"), FULL_TAG_W);
+        assert_eq!(entry.score("home work"), FULL_TAG_W);
+
+        // a category match outranks the same regular tag match
+        let toml = r#"['tagged']
+                      location = 'bar'
+                      tags = ["work"]
+
+                      ['categorized']
+                      location = 'bar'
+                      categories = ["work"]
+
+                      ['newest']
+                      location = 'bar'
+                      tags = ["work"]"#;
+        let store = crate::store::tests::parse_store(toml).unwrap();
+        let matches = store.find_matches("wor");
+        assert_eq!(store.get(matches[0]).name(), "categorized");
+        assert_eq!(store.find_matches("work")[0], matches[0]);
+        assert_eq!(matches.len(), 3);
+    }
+
+    #[test]
+    fn match_basename() {
+        assert_eq!(basename("project/src/main.rs").as_deref(), Some("main.rs"));
+        assert_eq!(basename("C:\\Users\\foo\\").as_deref(), Some("foo"));
+        assert_eq!(basename("main.rs"), None);
+        assert_eq!(basename("/"), None);
+
+        let entry = parse_entry("['project/src/main.rs']\nlocation = 'bar'");
+        let base = search::SearchSettings {
+            match_basename: true,
+            ..Default::default()
+        };
+        assert_eq!(entry.score("main.rs"), PARTIAL_NAME_W);
+        assert_eq!(entry.score_with("main.rs", &base), FULL_NAME_W);
+        assert_eq!(entry.score_with("mai", &base), STARTSWITH_NAME_W);
+        assert_eq!(entry.score_with("proj", &base), STARTSWITH_NAME_W);
+        assert_eq!(entry.score_with("src", &base), PARTIAL_NAME_W);
+
+        let toml = r#"['project/src/main.rs']
+                      location = 'a'
+
+                      ['project/src/domain.rs']
+                      location = 'b'
+
+                      ['project/main.rs.orig']
+                      location = 'c'"#;
+        let mut store = crate::store::tests::parse_store(toml).unwrap();
+        assert_eq!(store.find_matches("main.rs"), [2, 1, 0]);
+        store.set_search_settings(base);
+        assert_eq!(store.find_matches("main.rs"), [0, 2, 1]);
+    }
+
+    #[test]
+    fn split_paths() {
+        let entry = parse_entry("['project/src/main.rs']\nlocation = 'bar'");
+        let split = search::SearchSettings {
+            split_paths: true,
+            ..Default::default()
+        };
+
+        assert_eq!(entry.score("src"), PARTIAL_NAME_W);
+        assert_eq!(entry.score_with("src", &split), FULL_TAG_W);
+        assert_eq!(entry.score_with("main.rs", &split), FULL_TAG_W);
+        assert_eq!(entry.score_with("mai", &split), STARTSWITH_TAG_W);
+        assert_eq!(entry.score_with("proj", &split), STARTSWITH_NAME_W);
+        assert_eq!(entry.score_with("src main", &split), STARTSWITH_TAG_W);
+
+        let entry = parse_entry("['C:\\Users\\foo']\nlocation = 'bar'");
+        assert_eq!(entry.score_with("users", &split), FULL_TAG_W);
+
+        // names without separators are unchanged
+        let entry = parse_entry("[src]\nlocation = 'bar'");
+        assert_eq!(entry.score_with("sr", &split), STARTSWITH_NAME_W);
+
+        let toml = r#"['project/src/main.rs']
+                      location = 'bar'
+
+                      ['srcery']
+                      location = 'bar'"#;
+        let mut store = crate::store::tests::parse_store(toml).unwrap();
+        assert_eq!(store.find_matches("src"), [1, 0]);
+        store.set_search_settings(split);
+        assert_eq!(store.find_matches("src"), [0, 1]);
+    }
+
+    #[test]
+    fn preview_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+
+        let entry = |kind: &str, loc: &str| {
+            let mut table = toml::Table::new();
+            table.insert(kind.into(), toml::Value::String(loc.into()));
+            StoreEntry::from_value("foo".into(), toml::Value::Table(table), None).unwrap()
+        };
+        let file_str = file.display().to_string();
+
+        assert_eq!(
+            entry("location", &file_str).preview_path(),
+            Some(file.clone())
+        );
+        let file_url = Url::from_file_path(&file).unwrap();
+        assert_eq!(entry("url", file_url.as_str()).preview_path(), Some(file));
+
+        // only existing files can be previewed
+        let dir_str = dir.path().display().to_string();
+        assert_eq!(entry("location", &dir_str).preview_path(), None);
+        assert_eq!(entry("location", "nonexistent.txt").preview_path(), None);
+        assert_eq!(entry("url", "https://example.com").preview_path(), None);
+        assert_eq!(entry("system", &file_str).preview_path(), None);
+        assert_eq!(entry("copy", &file_str).preview_path(), None);
+
+        let keyword = parse_entry(&format!("[foo]\nlocation = '{file_str}'\nkeyword = 'k'"));
+        assert_eq!(keyword.preview_path(), None);
+    }
+
+    #[test]
+    fn phrase_matching() {
+        let entry = parse_entry("['Team Meeting Notes']\nlocation = 'bar'\ntags = ['work']");
+        let phrase = search::SearchSettings {
+            phrase: true,
+            ..Default::default()
+        };
+
+        // tokens can match anywhere, in any order
+        assert_eq!(entry.score("notes meeting"), PARTIAL_NAME_W);
+        assert_eq!(entry.score("meeting work"), PARTIAL_NAME_W);
+
+        // a phrase has to appear in the name or a tag as written
+        assert_eq!(entry.score_with("notes meeting", &phrase), 0);
+        assert_eq!(entry.score_with("meeting work", &phrase), 0);
+        assert_eq!(entry.score_with("meeting notes", &phrase), PARTIAL_NAME_W);
+        assert_eq!(
+            entry.score_with(" team meeting ", &phrase),
+            STARTSWITH_NAME_W
+        );
+        assert_eq!(entry.score_with("team meeting notes", &phrase), FULL_NAME_W);
+        assert_eq!(entry.score_with("work", &phrase), FULL_TAG_W);
+        assert_eq!(entry.score_with("  ", &phrase), 0);
+
+        // keywords are still the first word of the query
+        let keyword =
+            parse_entry("['search %s']\nurl = 'https://example.com/?q=%s'\nkeyword = 'ddg'");
+        assert!(keyword.score_with("ddg some words", &phrase) >= FULL_KEYWORD_W);
+    }
+
+    #[test]
+    fn transliterated_names() {
+        let entry = parse_entry("['Москва']\nlocation = 'bar'");
+        let search = search::SearchSettings {
+            transliterate: true,
+            ..Default::default()
+        };
+
+        assert_eq!(entry.transliterated.as_deref(), Some("Moskva"));
+        assert_eq!(entry.score("moskva"), 0);
+        assert_eq!(entry.score_with("moskva", &search), FULL_NAME_W);
+        assert_eq!(entry.score_with("mosk", &search), STARTSWITH_NAME_W);
+        assert_eq!(entry.score_with("Moskva", &search), FULL_NAME_W);
+        // the real name still matches
+        assert_eq!(entry.score_with("москва", &search), FULL_NAME_W);
+
+        // ascii names have nothing to transliterate
+        let entry = parse_entry("['Moscow']\nlocation = 'bar'");
+        assert_eq!(entry.transliterated, None);
+    }
+
+    #[test]
+    fn quoted_queries() {
+        let entry = parse_entry("['new york pizza']\nlocation = 'bar'");
+        assert_eq!(entry.score("\"york pizza\""), PARTIAL_NAME_W);
+        assert_eq!(entry.score("\"new york\" pizza"), PARTIAL_NAME_W);
+        // the words of a quoted token must appear together
+        assert_eq!(entry.score("\"new pizza\""), 0);
+        assert_eq!(entry.score("new pizza"), PARTIAL_NAME_W);
+        assert_eq!(entry.score("\"new york pizza"), FULL_NAME_W);
+
+        let entry = parse_entry(
+            r#"['weather %s']
+               url = 'https://example.com/?q=%s'
+               keyword = 'w'"#,
+        );
+        assert_eq!(
+            entry.format_selection("w \"new york\""),
+            "https://example.com/?q=new%20york"
+        );
+        assert_eq!(entry.format_name("w \"new york\""), "weather new york");
+        assert_eq!(
+            entry.format_selection("w \"new york"),
+            "https://example.com/?q=new%20york"
+        );
+    }
+
+    #[test]
+    fn score_breakdown() {
+        let entry = parse_entry(
+            "['web %s']\nurl = 'https://example.com/%s'\nkeyword = 'web'\ntags = ['internet']",
+        );
+        let breakdown = entry.explain_score("web inter", &Default::default());
+        assert!(breakdown.keyword);
+        assert_eq!(breakdown.priority, 0);
+        assert_eq!(breakdown.score, FULL_KEYWORD_W);
+        assert_eq!(
+            breakdown.tokens,
+            [
+                TokenScore {
+                    token: "web".to_string(),
+                    matches: vec![
+                        ("partial_name", PARTIAL_NAME_W),
+                        ("startswith_name", STARTSWITH_NAME_W)
+                    ],
+                    score: STARTSWITH_NAME_W,
+                },
+                TokenScore {
+                    token: "inter".to_string(),
+                    matches: vec![
+                        ("partial_tag", PARTIAL_TAG_W),
+                        ("startswith_tag", STARTSWITH_TAG_W)
+                    ],
+                    score: STARTSWITH_TAG_W,
+                },
+            ]
+        );
+
+        // every token is explained, even after one fails to match
+        let breakdown = entry.explain_score("nope web", &Default::default());
+        assert!(!breakdown.keyword);
+        assert_eq!(breakdown.score, 0);
+        assert_eq!(breakdown.tokens.len(), 2);
+        assert!(breakdown.tokens[0].matches.is_empty());
+        assert_eq!(breakdown.tokens[1].score, STARTSWITH_NAME_W);
+
+        // the breakdown agrees with the score
+        for query in ["web", "WEB", "web %s", "internet", "x"] {
+            assert_eq!(
+                entry.explain_score(query, &Default::default()).score,
+                entry.score(query)
+            );
+        }
+    }
+
+    #[test]
+    fn match_ranges() {
+        let entry = parse_entry("['Foo Bar Foo']\nlocation = 'baz'\ntags = ['qux']");
+        let matched = |query: &str| {
+            entry
+                .match_ranges(query)
+                .into_iter()
+                .map(|(start, end)| &entry.name[start..end])
+                .collect::<Vec<_>>()
+        };
+
+        // contains, every occurrence is matched
+        assert_eq!(entry.match_ranges("oo"), [(1, 3), (9, 11)]);
+        // prefix
+        assert_eq!(entry.match_ranges("foo b"), [(0, 3), (4, 5), (8, 11)]);
+        // multiple tokens, with touching and overlapping matches merged
+        assert_eq!(matched("bar o"), ["oo", "Bar", "oo"]);
+        assert_eq!(entry.match_ranges("o bar \"o b\""), [(1, 7), (9, 11)]);
+        assert_eq!(matched("\"foo bar\""), ["Foo Bar"]);
+
+        // same case rules as scoring
+        assert_eq!(matched("Bar"), ["Bar"]);
+        assert!(entry.match_ranges("BAR").is_empty());
+        // tags are not part of the name
+        assert!(entry.match_ranges("qux").is_empty());
+        assert!(entry.match_ranges("").is_empty());
+
+        // ranges are in bytes, so they can be used to slice the name
+        let entry = parse_entry("['Übersicht Ärger']\nlocation = 'baz'");
+        assert_eq!(entry.match_ranges("über är"), [(0, 5), (11, 14)]);
+
+        // the keyword is not highlighted in the name
+        let entry = parse_entry("['wiki %s']\nurl = 'https://example.com/%s'\nkeyword = 'wi'");
+        assert!(entry.match_ranges("wi rust").is_empty());
+        assert!(entry.match_ranges("wi").is_empty());
+        assert_eq!(entry.match_ranges("wik"), [(0, 3)]);
+    }
+
+    #[test]
+    fn url_host_matching() {
+        let entry = parse_entry("['Jolly source']\nurl = 'https://www.github.com/apgoetz/jolly'");
+        assert_eq!(entry.url_terms, ["github.com", "apgoetz", "jolly"]);
+
+        let search_url = search::SearchSettings {
+            search_url: true,
+            ..Default::default()
+        };
+        assert_eq!(entry.score("github"), 0);
+        assert_eq!(entry.score_with("github", &search_url), STARTSWITH_TAG_W);
+        assert_eq!(entry.score_with("apgoetz", &search_url), FULL_TAG_W);
+        assert_eq!(entry.score_with("hub", &search_url), PARTIAL_TAG_W);
+        // the name still matches as usual
+        assert_eq!(entry.score_with("source", &search_url), PARTIAL_NAME_W);
+
+        // keyword placeholders are not searched, and paths have no url
+        let entry = parse_entry("['search']\nurl = 'https://duckduckgo.com/?q=%s'\nkeyword = 'dd'");
+        assert_eq!(entry.url_terms, ["duckduckgo.com"]);
+        let entry = parse_entry("['notes']\nlocation = 'notes/today.txt'");
+        assert!(entry.url_terms.is_empty());
+        assert_eq!(entry.score_with("today", &search_url), 0);
+    }
+
+    #[test]
+    fn tag_prefix_matching() {
+        let entry = parse_entry("['foo']\nlocation = 'bar'\ntags = ['photography']");
+        let prefix = search::SearchSettings {
+            tag_match: search::TagMatch::Prefix,
+            ..Default::default()
+        };
+
+        // by default tags match anywhere
+        assert_eq!(entry.score("graph"), PARTIAL_TAG_W);
+        assert_eq!(entry.score_with("graph", &prefix), 0);
+
+        // prefix and full tag matches are unchanged
+        assert_eq!(entry.score("photo"), STARTSWITH_TAG_W);
+        assert_eq!(entry.score_with("photo", &prefix), STARTSWITH_TAG_W);
+        assert_eq!(entry.score_with("photography", &prefix), FULL_TAG_W);
+
+        let setting: search::SearchSettings = toml::from_str("tag_match = 'prefix'").unwrap();
+        assert_eq!(setting.tag_match, search::TagMatch::Prefix);
+    }
+
+    #[test]
+    fn synonym_queries() {
+        let entry = parse_entry("['documentation']\nlocation = 'bar'\ntags = ['reference']");
+        let search: search::SearchSettings =
+            toml::from_str("synonyms = { docs = ['documentation', 'manual'] }").unwrap();
+
+        assert_eq!(entry.score("docs"), 0);
+        assert_eq!(entry.score_with("docs", &search), FULL_NAME_W);
+        assert_eq!(entry.score_with("Docs", &search), FULL_NAME_W);
+        // the word itself still matches as usual
+        assert_eq!(entry.score_with("doc", &search), STARTSWITH_NAME_W);
+        // every token must still match something
+        assert_eq!(entry.score_with("docs reference", &search), FULL_TAG_W);
+        assert_eq!(entry.score_with("docs missing", &search), 0);
+        // synonyms are not expanded in the other direction
+        let entry = parse_entry("['docs']\nlocation = 'bar'");
+        assert_eq!(entry.score_with("manual", &search), 0);
+    }
+
+    #[test]
+    fn name_metadata() {
+        let entry = parse_entry("['backup (old)']\nlocation = 'bar'\ntags = ['disk']");
+        let search = search::SearchSettings {
+            name_metadata: Some("(".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(entry.score("old"), PARTIAL_NAME_W);
+        assert_eq!(entry.score_with("old", &search), 0);
+        assert_eq!(entry.score_with("backup", &search), FULL_NAME_W);
+        assert_eq!(entry.score_with("backup disk", &search), FULL_TAG_W);
+
+        // the metadata is still shown
+        assert_eq!(entry.format_name("backup"), "backup (old)");
+    }
+
+    #[test]
+    fn copy_entry() {
+        let entry = parse_entry("[foo]\ncopy = 'secret %s'\nkeyword = 'k'");
+        assert_eq!(entry.entry, EntryType::CopyEntry("secret %s".into()));
+        assert_eq!(entry.format_selection("k bar"), "secret bar");
+
+        // copy entries are always copied
+        assert_eq!(entry.action(Action::Open), Action::Copy);
+        assert_eq!(entry.action(Action::Copy), Action::Copy);
+
+        // and there is nothing to open
+        assert!(entry
+            .handle_selection("k bar", &settings::Settings::default())
+            .is_ok());
+
+        let entry = parse_entry("[foo]\nlocation = 'bar'");
+        assert_eq!(entry.action(Action::Open), Action::Open);
+
+        let value: toml::Value = toml::from_str("[foo]\ncopy = 'a'\nlocation = 'b'").unwrap();
+        let (k, v) = value
+            .as_table()
+            .unwrap()
+            .clone()
+            .into_iter()
+            .next()
+            .unwrap();
+        assert!(matches!(
+            StoreEntry::from_value(k, v, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn fuzzy_matching() {
+        let firefox = parse_entry("['firefox']\nlocation = 'bar'\ntags = ['browser']");
+        assert_eq!(firefox.score("frfx"), FUZZY_NAME_W);
+        assert_eq!(firefox.score("rfx"), FUZZY_NAME_W);
+        assert_eq!(firefox.score("xf"), 0);
+        assert_eq!(firefox.score(""), 0);
+        // fuzzy matches are only a fallback
+        assert_eq!(firefox.score("fox"), PARTIAL_NAME_W);
+        assert_eq!(firefox.score("frfx brow"), FUZZY_NAME_W);
+
+        // matches that start later are graded lower, and matches that
+        // keep characters together are graded higher
+        let grade = |query| firefox.fuzzy_grade(query, &Default::default());
+        assert_eq!(grade("frfx"), FUZZY_START_W);
+        assert_eq!(grade("rfx"), FUZZY_START_W - 2);
+        assert_eq!(grade("irfox"), FUZZY_START_W - 1 + FUZZY_RUN_W * 3 / 4);
+        assert_eq!(grade("fox"), 0);
+
+        let off = search::SearchSettings {
+            fuzzy: false,
+            ..Default::default()
+        };
+        assert_eq!(firefox.score_with("frfx", &off), 0);
+        assert_eq!(firefox.score_with("fox", &off), PARTIAL_NAME_W);
+
+        // an exact match ranks above a fuzzy one
+        let store = crate::store::tests::parse_store(
+            "['frfx notes']\nlocation = 'a'\n['firefox']\nlocation = 'b'",
+        )
+        .unwrap();
+        assert_eq!(store.find_matches("frfx"), [0, 1]);
+        assert_eq!(store.find_matches("firefox"), [1]);
+
+        // and the grade orders fuzzy matches with the same score
+        let store = crate::store::tests::parse_store(
+            "['firefox']\nlocation = 'a'\n['my firefox']\nlocation = 'b'",
+        )
+        .unwrap();
+        assert_eq!(store.find_matches("frfx"), [0, 1]);
+
+        let breakdown = firefox.explain_score("frfx", &Default::default());
+        assert_eq!(breakdown.tokens[0].matches, [("fuzzy_name", FUZZY_NAME_W)]);
+    }
+
+    #[test]
+    fn word_boundary_bonus() {
+        let starts = |text| {
+            word_starts(text)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, s)| *s)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(starts("git checkout"), [0, 4]);
+        assert_eq!(starts("gitCheckout"), [0, 3]);
+        assert_eq!(starts("my-file_name.txt"), [0, 3, 8, 13]);
+        assert_eq!(starts("  GIT"), [2]);
+        assert!(starts("").is_empty());
+
+        // characters are matched at the starts of words when they can be
+        let text: Vec<char> = "git checkout".chars().collect();
+        let token: Vec<char> = "gc".chars().collect();
+        assert_eq!(subsequence(&text, &token, |_| false), Some(vec![0, 4]));
+        let token: Vec<char> = "gio".chars().collect();
+        let starts = word_starts("git checkout");
+        assert_eq!(
+            subsequence(&text, &token, |i| starts[i]),
+            Some(vec![0, 1, 9])
+        );
+
+        let checkout = parse_entry("['git checkout']\nlocation = 'a'");
+        let bonus = search::SearchSettings::default().boundary_bonus;
+        assert_eq!(checkout.score("gco"), FUZZY_NAME_W + bonus);
+        // the bonus needs most of the token to start words
+        assert_eq!(checkout.score("gtek"), FUZZY_NAME_W);
+        let camel = parse_entry("['gitCheckout']\nlocation = 'a'");
+        assert_eq!(camel.score("gco"), FUZZY_NAME_W + bonus);
+
+        let off = search::SearchSettings {
+            boundary_bonus: 0,
+            ..Default::default()
+        };
+        assert_eq!(checkout.score_with("gco", &off), FUZZY_NAME_W);
+        let no_fuzzy = search::SearchSettings {
+            fuzzy: false,
+            ..Default::default()
+        };
+        assert_eq!(checkout.score_with("gco", &no_fuzzy), 0);
+
+        // even a bonus that was not checked when the config was
+        // loaded keeps fuzzy matches below partial name matches
+        let huge = search::SearchSettings {
+            boundary_bonus: u32::MAX,
+            ..Default::default()
+        };
+        assert!(checkout.score_with("gco", &huge) < PARTIAL_NAME_W);
+
+        // the initialism ranks above entries that only contain the
+        // letters in order
+        let store = crate::store::tests::parse_store(
+            "['git checkout']\nlocation = 'a'\n['gecko']\nlocation = 'b'\n['logic tools']\nlocation = 'c'",
+        )
+        .unwrap();
+        assert_eq!(store.find_matches("gco"), [0, 1, 2]);
+    }
+
+    #[test]
+    fn priority_score() {
+        let plain = parse_entry("[foo]\nlocation = 'bar'");
+        let priority = parse_entry("[foo]\nlocation = 'bar'\npriority = 50");
+
+        assert_eq!(priority.score("fo"), plain.score("fo") + 50);
+        assert!(priority.score("fo") > plain.score("foo"));
+
+        // priority does not make an entry match
+        assert_eq!(priority.score("baz"), 0);
+        assert_eq!(priority.score(""), 0);
+    }
+
+    #[test]
+    fn icon_name_field() {
+        let entry = parse_entry("[foo]\nlocation = 'bar'\nicon_name = 'firefox'");
+        assert_eq!(entry.icon_type, IconType::named("firefox"));
+
+        let toml = "[foo]\nlocation = 'bar'\nicon_name = 'firefox'\nicon = 'firefox.png'";
+        let value: toml::Value = toml::from_str(toml).unwrap();
+        let (k, v) = value
+            .as_table()
+            .unwrap()
+            .clone()
+            .into_iter()
+            .next()
+            .unwrap();
+        assert!(matches!(
+            StoreEntry::from_value(k, v, None),
+            Err(Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn url_scheme_filtering() {
+        let mut settings = settings::Settings::default();
+
+        // by default, web and mail urls and plain paths are allowed,
+        // but file urls are not
+        assert!(check_scheme("file:///usr/bin/xterm", &settings).is_err());
+        for selection in [
+            "https://example.com",
+            "http://example.com",
+            "mailto:foo@example.com",
+            "/path/to/file",
+            "C:\\path\\to\\file",
+        ] {
+            assert!(check_scheme(selection, &settings).is_ok(), "{selection}");
+        }
+
+        assert!(matches!(
+            check_scheme("javascript:alert(1)", &settings),
+            Err(Error::DisallowedScheme(s)) if s == "javascript"
+        ));
+
+        // an allowlist rejects anything not on it
+        settings.allowed_schemes = Some(vec!["HTTPS".into()]);
+        assert!(check_scheme("https://example.com", &settings).is_ok());
+        assert!(check_scheme("/path/to/file", &settings).is_ok());
+        assert!(matches!(
+            check_scheme("file:///etc/passwd", &settings),
+            Err(Error::DisallowedScheme(s)) if s == "file"
+        ));
+
+        // the denylist wins over the allowlist
+        settings.denied_schemes = vec!["https".into()];
+        assert!(check_scheme("https://example.com", &settings).is_err());
+
+        // denied urls are never opened, whatever opens them
+        let settings = settings::Settings::default();
+        for text in [
+            "[foo]\nurl = 'javascript:alert(1)'",
+            "[foo]\nlocation = 'ms-msdt:/id'",
+            "[foo]\ndir = 'file:///usr/bin'",
+            "[foo]\nlocation = ['/not/there', 'data:text/html,hi']\nfirst_existing = true",
+        ] {
+            assert!(
+                matches!(
+                    parse_entry(text).handle_selection("", &settings),
+                    Err(Error::DisallowedScheme(_))
+                ),
+                "{text}"
+            );
+        }
+        assert!(matches!(
+            open_fallback("javascript:%s", "alert(1)", &settings),
+            Err(Error::DisallowedScheme(s)) if s == "javascript"
+        ));
+    }
+
+    #[test]
+    fn markdown_fallback_modes() {
+        let desc = "# Title\n\nsome *emphasis* text\n\n- one\n- two";
+
+        // heading and list are not supported by the paragraph parser
+        assert!(desc_to_paragraphs(desc).is_none());
+
+        assert!(description_paragraphs(desc, MarkdownMode::Strict).is_empty());
+        assert_eq!(
+            description_paragraphs(desc, MarkdownMode::Raw),
+            vec![desc.to_string()]
+        );
+        assert_eq!(
+            description_paragraphs(desc, MarkdownMode::Plain),
+            vec!["Title", "some emphasis text", "one", "two"]
+        );
+
+        // supported markdown is the same regardless of mode
+        let desc = "first\n\nsecond";
+        for mode in [MarkdownMode::Strict, MarkdownMode::Raw, MarkdownMode::Plain] {
+            assert_eq!(description_paragraphs(desc, mode), vec!["first", "second"]);
+        }
+    }
+
+    #[test]
+    fn parse_markdown_mode() {
+        let settings: EntrySettings = toml::from_str(r#"markdown = "plain""#).unwrap();
+        assert_eq!(settings.markdown, MarkdownMode::Plain);
+        assert_eq!(EntrySettings::default().markdown, MarkdownMode::Raw);
+    }
+
+    #[test]
+    fn location_subtitle() {
+        let shown: EntrySettings = toml::from_str("show_location = true").unwrap();
+        let hidden = EntrySettings::default();
+
+        let file = parse_entry("[foo]\nlocation = '/home/user/notes.txt'");
+        assert_eq!(file.subtitle("foo", &hidden), None);
+        assert_eq!(
+            file.subtitle("foo", &shown).as_deref(),
+            Some("/home/user/notes.txt")
+        );
+
+        let url = parse_entry("['search %s']\nurl = 'https://example.com/?q=%s'\nkeyword = 's'");
+        assert_eq!(
+            url.subtitle("s jolly launcher", &shown).as_deref(),
+            Some("https://example.com/?q=jolly%20launcher")
+        );
+
+        let copy = parse_entry("[foo]\ncopy = 'secret'");
+        assert_eq!(copy.subtitle("foo", &shown), None);
+    }
+
+    #[test]
+    fn keyword_badges() {
+        let shown: EntrySettings = toml::from_str("show_keywords = true").unwrap();
+        let hidden = EntrySettings::default();
+
+        let keyword =
+            parse_entry("['search %s']\nurl = 'https://example.com/?q=%s'\nkeyword = 'g'");
+        assert_eq!(keyword.keyword_badge(&shown), Some("g"));
+        assert_eq!(keyword.keyword_badge(&hidden), None);
+
+        let file = parse_entry("[foo]\nlocation = 'bar'");
+        assert_eq!(file.keyword_badge(&shown), None);
+    }
+
+    #[test]
+    fn entry_colors() {
+        let entry = parse_entry("[foo]\nlocation = 'bar'\ncolor = '#ff8800'");
+        assert_eq!(entry.color, Some([0xff, 0x88, 0x00, 0xff]));
+        let entry = parse_entry("[foo]\nlocation = 'bar'\ncolor = 'rgba(0, 0, 255, 0.5)'");
+        assert_eq!(entry.color, Some([0, 0, 255, 128]));
+        assert_eq!(parse_entry("[foo]\nlocation = 'bar'").color, None);
+
+        let value: toml::Value = toml::from_str("location = 'bar'\ncolor = 'nope'").unwrap();
+        assert!(StoreEntry::from_value("foo".into(), value, None).is_err());
+    }
+
+    #[test]
+    fn entry_color_used_when_selected() {
+        use iced::widget::button::StyleSheet;
+
+        let theme = theme::Theme::default();
+        let background =
+            |entry: &StoreEntry, selected| theme.active(&entry.button_style(selected)).background;
+
+        let entry = parse_entry("[foo]\nlocation = 'bar'\ncolor = '#ff8800'");
+        let orange = iced::Color::from_rgb8(0xff, 0x88, 0x00);
+        assert_eq!(background(&entry, true), Some(orange.into()));
+        assert_eq!(background(&entry, false), None);
+
+        // other entries use the highlight color of the theme
+        let entry = parse_entry("[foo]\nlocation = 'bar'");
+        let highlight: iced::Color = theme.highlight_color().into();
+        assert_eq!(background(&entry, true), Some(highlight.into()));
+    }
+
     #[test]
     fn test_keyword_icontypes_are_parsed() {
         let entry = parse_entry(
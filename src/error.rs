@@ -25,7 +25,24 @@ impl fmt::Display for Error {
                 write!(f, "while parsing jolly.toml: \n")?;
                 e.fmt(f)
             }
-            Error::IcedError(e) => e.fmt(f),
+            Error::IcedError(e) => {
+                let detail = iced_detail(e);
+                if is_no_display(e) {
+                    write!(f, "no display available, so jolly cannot open its window")?;
+                    if let Some(detail) = detail {
+                        write!(f, " ({detail})")?;
+                    }
+                    f.write_str(
+                        "\nto use jolly without a window, try --export json or --explain QUERY NAME",
+                    )
+                } else {
+                    write!(f, "jolly could not start: {e}")?;
+                    if let Some(detail) = detail {
+                        write!(f, ": {detail}")?;
+                    }
+                    Ok(())
+                }
+            }
             Error::IoError(file, e) => {
                 if let Some(file) = file {
                     write!(f, "with file '{file}': \n")?;
@@ -49,3 +66,60 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {}
+
+// the underlying cause of an iced error, since its own message does
+// not include it
+fn iced_detail(e: &iced::Error) -> Option<String> {
+    match e {
+        iced::Error::ExecutorCreationFailed(e) => Some(e.to_string()),
+        iced::Error::WindowCreationFailed(e) => Some(e.to_string()),
+        iced::Error::GraphicsCreationFailed(e) => Some(e.to_string()),
+    }
+}
+
+// true if the window could not be created because there is no
+// display to put it on, e.g. when running over ssh
+fn is_no_display(e: &iced::Error) -> bool {
+    let iced::Error::WindowCreationFailed(cause) = e else {
+        return false;
+    };
+    let cause = cause.to_string().to_lowercase();
+    ["display", "x server", "wayland"]
+        .iter()
+        .any(|hint| cause.contains(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_error(msg: &str) -> Error {
+        let cause = io::Error::other(msg.to_string());
+        Error::IcedError(iced::Error::WindowCreationFailed(Box::new(cause)))
+    }
+
+    #[test]
+    fn iced_error_messages() {
+        let msg = window_error("Failed to open connection to X server").to_string();
+        assert!(msg.starts_with("no display available"), "{msg}");
+        assert!(msg.contains("(Failed to open connection to X server)"));
+        assert!(msg.contains("--export json"));
+
+        let msg = window_error("WAYLAND_DISPLAY not set").to_string();
+        assert!(msg.starts_with("no display available"), "{msg}");
+
+        // other window errors are reported as they are
+        let msg = window_error("out of memory").to_string();
+        assert_eq!(
+            msg,
+            "jolly could not start: the application window could not be created: out of memory"
+        );
+
+        let cause = io::Error::other("no threads");
+        let msg = Error::IcedError(iced::Error::ExecutorCreationFailed(cause)).to_string();
+        assert_eq!(
+            msg,
+            "jolly could not start: the futures executor could not be created: no threads"
+        );
+    }
+}
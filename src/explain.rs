@@ -0,0 +1,136 @@
+// diagnostics that show how an entry was scored for a query, so that
+// configs can be tuned without guessing
+
+use crate::entry::FULL_KEYWORD_W;
+use crate::{search::KeywordMode, settings::Settings, store::Store};
+
+// describe how the entry with the given name scores for the query,
+// token by token
+pub fn explain(
+    store: &Store,
+    settings: &Settings,
+    query: &str,
+    name: &str,
+) -> Result<String, String> {
+    let id = store
+        .get_by_name(name)
+        .ok_or_else(|| format!("No entry named '{name}'"))?;
+    let entry = store.get(id);
+    let query = settings.search.normalize(query);
+    let breakdown = entry.explain_score(&query, &settings.search);
+
+    let mut lines = vec![
+        format!("entry: {}", entry.name()),
+        format!("query: {query}"),
+    ];
+
+    if breakdown.tokens.is_empty() {
+        lines.push("the query has no tokens, so nothing matches".to_string());
+    }
+
+    for token in &breakdown.tokens {
+        let matches = if token.matches.is_empty() {
+            "no match".to_string()
+        } else {
+            token
+                .matches
+                .iter()
+                .map(|(signal, weight)| format!("{signal}={weight}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        lines.push(format!(
+            "token \"{}\": {matches} -> {}",
+            token.token, token.score
+        ));
+    }
+
+    // every token has to match, so the worst token decides
+    if let Some(lowest) = breakdown.tokens.iter().map(|t| t.score).min() {
+        lines.push(format!("lowest token score: {lowest}"));
+    }
+    if breakdown.keyword {
+        lines.push(match settings.search.keyword_mode {
+            KeywordMode::Max => format!("keyword: matched, the score is at least {FULL_KEYWORD_W}"),
+            KeywordMode::Add => format!("keyword: matched, {FULL_KEYWORD_W} is added to the score"),
+        });
+    }
+    lines.push(format!("priority: {}", breakdown.priority));
+    if let Some(expr) = &settings.search.score_expr {
+        lines.push(format!("score_expr: {expr}"));
+    }
+    lines.push(format!("score: {}", breakdown.score));
+
+    if breakdown.score > 0 {
+        let matches = store.scored_matches(&query);
+        if let Some(rank) = matches.iter().position(|m| m.0 == id) {
+            let mut line = format!("rank: {} of {} matches", rank + 1, matches.len());
+            if rank >= settings.ui.max_results {
+                line.push_str(&format!(
+                    ", only the first {} are shown",
+                    settings.ui.max_results
+                ));
+            }
+            lines.push(line);
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::{PARTIAL_NAME_W, PARTIAL_TAG_W, STARTSWITH_NAME_W, STARTSWITH_TAG_W};
+    use crate::store;
+
+    #[test]
+    fn explain_entry() {
+        let toml = r#"['foo bar']
+                      location = 'a'
+                      tags = ['baz']
+                      priority = 1
+
+                      ['foobar']
+                      location = 'b'"#;
+        let store = store::tests::parse_store(toml).unwrap();
+        let settings = Settings::default();
+
+        let report = explain(&store, &settings, "foo ba", "foo bar").unwrap();
+        let lines: Vec<_> = report.lines().collect();
+        let foo = format!(
+            "token \"foo\": partial_name={PARTIAL_NAME_W} startswith_name={STARTSWITH_NAME_W} -> {STARTSWITH_NAME_W}"
+        );
+        assert_eq!(
+            lines,
+            [
+                "entry: foo bar".to_string(),
+                "query: foo ba".to_string(),
+                foo.clone(),
+                format!(
+                    "token \"ba\": partial_name={PARTIAL_NAME_W} partial_tag={PARTIAL_TAG_W} startswith_tag={STARTSWITH_TAG_W} -> {STARTSWITH_TAG_W}"
+                ),
+                format!("lowest token score: {STARTSWITH_TAG_W}"),
+                "priority: 1".to_string(),
+                format!("score: {}", STARTSWITH_TAG_W + 1),
+                "rank: 1 of 2 matches".to_string(),
+            ]
+        );
+
+        // tokens that do not match are still explained
+        let report = explain(&store, &settings, "qux foo", "foobar").unwrap();
+        assert!(report.contains("token \"qux\": no match -> 0"), "{report}");
+        assert!(report.contains(&foo), "{report}");
+        assert!(report.ends_with("score: 0"));
+
+        assert!(explain(&store, &settings, "foo", "missing").is_err());
+
+        // keyword matches say how much the keyword is worth
+        let store =
+            store::tests::parse_store("['search']\nurl = 'https://example.com/%s'\nkeyword = 's'")
+                .unwrap();
+        let report = explain(&store, &settings, "s foo", "search").unwrap();
+        let keyword = format!("keyword: matched, the score is at least {FULL_KEYWORD_W}");
+        assert!(report.contains(&keyword), "{report}");
+    }
+}
@@ -0,0 +1,194 @@
+// exports the parsed store in formats that other launchers can consume
+
+use crate::entry::StoreEntry;
+use crate::store::Store;
+
+// a key that can be included in each exported entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Type,
+    Location,
+    Tags,
+    Keyword,
+    Icon,
+}
+
+// the fields that are exported by default, in order
+pub const ALL_FIELDS: [Field; 6] = [
+    Field::Name,
+    Field::Type,
+    Field::Location,
+    Field::Tags,
+    Field::Keyword,
+    Field::Icon,
+];
+
+impl Field {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Type => "type",
+            Field::Location => "location",
+            Field::Tags => "tags",
+            Field::Keyword => "keyword",
+            Field::Icon => "icon",
+        }
+    }
+
+    // the json value of this field for an entry
+    fn value(&self, e: &StoreEntry) -> String {
+        match self {
+            Field::Name => json_string(e.name()),
+            Field::Type => json_string(e.kind()),
+            Field::Location => json_string(e.location()),
+            Field::Tags => {
+                let tags: Vec<_> = e.tags().iter().map(|t| json_string(t)).collect();
+                format!("[{}]", tags.join(","))
+            }
+            Field::Keyword => e.keyword().map_or("null".to_string(), json_string),
+            Field::Icon => Some(e.icontype().kind())
+                .filter(|_| e.has_icon())
+                .map_or("null".to_string(), json_string),
+        }
+    }
+}
+
+// parse a comma separated list of field names, like "name,tags"
+pub fn parse_fields(list: &str) -> Result<Vec<Field>, String> {
+    let fields = list
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(|name| {
+            ALL_FIELDS
+                .iter()
+                .find(|f| f.key() == name)
+                .copied()
+                .ok_or_else(|| {
+                    let known: Vec<_> = ALL_FIELDS.iter().map(Field::key).collect();
+                    format!(
+                        "Unknown field '{name}', expected one of: {}",
+                        known.join(", ")
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if fields.is_empty() {
+        Err("No fields given".to_string())
+    } else {
+        Ok(fields)
+    }
+}
+
+// serialize the store as a json array, with one entry object per
+// line. Each object has the given fields, in order
+pub fn to_json(store: &Store, fields: &[Field]) -> String {
+    let entries: Vec<_> = store
+        .entries()
+        .map(|e| {
+            let keys: Vec<_> = fields
+                .iter()
+                .map(|f| format!("{}:{}", json_string(f.key()), f.value(e)))
+                .collect();
+            format!("{{{}}}", keys.join(","))
+        })
+        .collect();
+
+    if entries.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n]", entries.join(",\n"))
+    }
+}
+
+// quote and escape a string as a json string literal
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::parse_store;
+
+    #[test]
+    fn export_fixture_store() {
+        let toml = r#"['notes.txt']
+                      tags = ['text', 'notes']
+
+                      ['search %s']
+                      url = 'https://example.com/?q=%s'
+                      keyword = 'ex'
+
+                      ['say "hi"']
+                      system = 'echo hi'
+                      icon_name = 'utilities-terminal'"#;
+
+        let store = parse_store(toml).unwrap();
+
+        let expected = [
+            r#"["#,
+            r#"{"name":"notes.txt","type":"location","location":"notes.txt","tags":["text","notes"],"keyword":null,"icon":"file"},"#,
+            r#"{"name":"search %s","type":"url","location":"https://example.com/?q=%s","tags":[],"keyword":"ex","icon":"url"},"#,
+            r#"{"name":"say \"hi\"","type":"system","location":"echo hi","tags":[],"keyword":null,"icon":"named"}"#,
+            r#"]"#,
+        ]
+        .join("\n");
+
+        assert_eq!(to_json(&store, &ALL_FIELDS), expected);
+        assert_eq!(to_json(&parse_store("").unwrap(), &ALL_FIELDS), "[]");
+    }
+
+    #[test]
+    fn selected_fields() {
+        let toml = r#"['search %s']
+                      url = 'https://example.com/?q=%s'
+                      keyword = 'ex'
+                      tags = ['web']"#;
+        let store = parse_store(toml).unwrap();
+
+        let fields = parse_fields("location, name").unwrap();
+        assert_eq!(fields, [Field::Location, Field::Name]);
+        assert_eq!(
+            to_json(&store, &fields),
+            "[\n{\"location\":\"https://example.com/?q=%s\",\"name\":\"search %s\"}\n]"
+        );
+
+        let fields = parse_fields("tags,keyword").unwrap();
+        assert_eq!(
+            to_json(&store, &fields),
+            "[\n{\"tags\":[\"web\"],\"keyword\":\"ex\"}\n]"
+        );
+
+        let err = parse_fields("name,url").unwrap_err();
+        assert!(err.contains("'url'"), "{err}");
+        assert!(err.contains("name, type, location, tags, keyword, icon"));
+        assert!(parse_fields("").is_err());
+        assert!(parse_fields(" , ").is_err());
+    }
+
+    #[test]
+    fn json_escapes() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(json_string("line\nbreak\t"), r#""line\nbreak\t""#);
+        assert_eq!(json_string("\u{1}"), r#""\u0001""#);
+        assert_eq!(json_string("C:\\dir"), r#""C:\\dir""#);
+    }
+}
@@ -0,0 +1,248 @@
+// remembers how often and how recently each entry was selected, so
+// that entries that are used a lot can be preferred over entries
+// with the same score. The history is kept in a small toml file next
+// to jolly.toml, keyed by entry name
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use crate::error::Error;
+
+// name of the history file, in the directory of the config file
+pub const HISTORY_NAME: &str = "jolly-history.toml";
+
+// a selection counts half as much after this long
+const HALF_LIFE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// weights of entries are below this. They are only compared between
+// entries with the same score, so even the most used entry never
+// outranks a better match, like a keyword match
+pub const MAX_WEIGHT: u32 = 1000;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Usage {
+    // number of selections, decayed to the time of the last one
+    count: f64,
+    // seconds since the unix epoch
+    last_used: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct History {
+    // where the history is saved, if anywhere
+    path: Option<PathBuf>,
+    usage: BTreeMap<String, Usage>,
+}
+
+impl History {
+    // the history that belongs to a config file
+    pub fn for_config(config: &Path) -> Self {
+        let path = config.with_file_name(HISTORY_NAME);
+        let usage = match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                ::log::warn!("ignoring {}: {e}", path.display());
+                BTreeMap::new()
+            }),
+            // jolly has not been used with this config yet
+            Err(_) => BTreeMap::new(),
+        };
+        History {
+            path: Some(path),
+            usage,
+        }
+    }
+
+    // an entry was selected at the given time
+    pub fn record(&mut self, name: &str, now: SystemTime) {
+        let now = unix_secs(now);
+        let usage = self.usage.entry(name.to_string()).or_default();
+        usage.count = usage.count * decay(usage.last_used, now) + 1.0;
+        usage.last_used = usage.last_used.max(now);
+    }
+
+    // number of selections of an entry, where older selections count
+    // for less
+    pub fn frecency(&self, name: &str, now: SystemTime) -> f64 {
+        match self.usage.get(name) {
+            Some(usage) => usage.count * decay(usage.last_used, unix_secs(now)),
+            None => 0.0,
+        }
+    }
+
+    // frecency of an entry, scaled to between 0 and MAX_WEIGHT. Each
+    // selection moves the weight closer to the maximum, without
+    // ever reaching it
+    pub fn weight(&self, name: &str, now: SystemTime) -> u32 {
+        let frecency = self.frecency(name, now);
+        (MAX_WEIGHT as f64 * frecency / (frecency + 1.0)) as u32
+    }
+
+    // write the history to its file, if it has one
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text = toml::to_string(&self.usage).map_err(|e| Error::CustomError(e.to_string()))?;
+        fs::write(path, text).map_err(|e| Error::IoError(Some(path.display().to_string()), e))
+    }
+}
+
+// saves the history on a thread of its own, so that selecting an
+// entry does not wait for the disk. The thread is started by the
+// first save. Dropping the writer waits for the last save, since
+// jolly usually exits right after a selection
+#[derive(Debug, Default)]
+pub struct Writer {
+    thread: Option<(mpsc::Sender<History>, JoinHandle<()>)>,
+}
+
+impl Writer {
+    pub fn save(&mut self, history: &History) {
+        if history.path.is_none() {
+            return;
+        }
+        let (saves, _) = self.thread.get_or_insert_with(|| {
+            let (saves, histories) = mpsc::channel();
+            (saves, std::thread::spawn(move || save_worker(histories)))
+        });
+        if saves.send(history.clone()).is_err() {
+            ::log::warn!("could not save the usage history, the writer has stopped");
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if let Some((saves, thread)) = self.thread.take() {
+            drop(saves);
+            let _ = thread.join();
+        }
+    }
+}
+
+// save histories until the writer is dropped. Only the newest of the
+// histories that are waiting needs to be saved
+fn save_worker(histories: mpsc::Receiver<History>) {
+    while let Ok(mut history) = histories.recv() {
+        while let Ok(newer) = histories.try_recv() {
+            history = newer;
+        }
+        if let Err(e) = history.save() {
+            ::log::warn!("could not save the usage history: {e}");
+        }
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// how much a selection at then is worth at now. Selections from the
+// future, which happen when the clock changes, count fully
+fn decay(then: u64, now: u64) -> f64 {
+    let elapsed = now.saturating_sub(then) as f64;
+    0.5f64.powf(elapsed / HALF_LIFE.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decay_math() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let mut history = History::default();
+        assert_eq!(history.frecency("a", start), 0.0);
+        assert_eq!(history.weight("a", start), 0);
+
+        history.record("a", start);
+        history.record("a", start);
+        assert_eq!(history.frecency("a", start), 2.0);
+
+        // selections lose half their worth every half life
+        let later = start + HALF_LIFE;
+        assert!((history.frecency("a", later) - 1.0).abs() < 1e-9);
+        assert!((history.frecency("a", later + HALF_LIFE) - 0.5).abs() < 1e-9);
+
+        // a new selection adds to what is left of the old ones
+        history.record("a", later);
+        assert!((history.frecency("a", later) - 2.0).abs() < 1e-9);
+
+        // a recent selection is worth more than several old ones
+        let mut recent = History::default();
+        recent.record("b", later);
+        let mut old = History::default();
+        for _ in 0..3 {
+            old.record("b", start - 2 * HALF_LIFE);
+        }
+        assert!(recent.frecency("b", later) > old.frecency("b", later));
+
+        // weights grow with use, but stay below the bound
+        let mut heavy = History::default();
+        let mut last = 0;
+        for _ in 0..1000 {
+            heavy.record("c", start);
+            let weight = heavy.weight("c", start);
+            assert!(weight >= last && weight < MAX_WEIGHT, "{weight}");
+            last = weight;
+        }
+        assert_eq!(heavy.weight("d", start), 0);
+
+        // a clock that goes backwards does not inflate the history
+        let mut skewed = History::default();
+        skewed.record("e", later);
+        assert_eq!(skewed.frecency("e", start), 1.0);
+        skewed.record("e", start);
+        assert_eq!(skewed.frecency("e", later), 2.0);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("jolly.toml");
+        let now = SystemTime::now();
+
+        let mut history = History::for_config(&config);
+        assert_eq!(history.weight("foo bar", now), 0);
+        history.record("foo bar", now);
+        history.save().unwrap();
+        assert!(dir.path().join(HISTORY_NAME).exists());
+
+        let loaded = History::for_config(&config);
+        assert_eq!(loaded, history);
+        assert!(loaded.weight("foo bar", now) > 0);
+
+        // a broken history file is ignored
+        fs::write(dir.path().join(HISTORY_NAME), "not toml [").unwrap();
+        assert_eq!(History::for_config(&config).weight("foo bar", now), 0);
+
+        // histories without a file are not saved
+        assert!(History::default().save().is_ok());
+    }
+
+    #[test]
+    fn writer_saves_before_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("jolly.toml");
+        let now = SystemTime::now();
+
+        let mut history = History::for_config(&config);
+        let mut writer = Writer::default();
+        for _ in 0..3 {
+            history.record("foo", now);
+            writer.save(&history);
+        }
+        writer.save(&History::default());
+
+        // dropping the writer waits for the newest history to be saved
+        drop(writer);
+        assert_eq!(History::for_config(&config), history);
+    }
+}
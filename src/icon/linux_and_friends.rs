@@ -1,9 +1,14 @@
 #![cfg(all(unix, not(target_os = "macos")))]
 // for now, this covers linux and the bsds
-use super::{icon_from_svg, Context, Icon, IconError, DEFAULT_ICON_SIZE};
+use super::{
+    icon_from_svg, Context, Icon, IconError, DEFAULT_ICON_CACHE_SIZE, DEFAULT_ICON_CONCURRENCY,
+    DEFAULT_ICON_RETRIES, DEFAULT_ICON_SIZE,
+};
 
+use crate::config::one_or_many;
 use serde;
 use std::io::Read;
+use std::path::PathBuf;
 use xdg_mime::SharedMimeInfo;
 
 // set in build script
@@ -15,30 +20,65 @@ const SNIFFSIZE: usize = 8 * 1024;
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 #[serde(default)]
 pub struct Os {
-    pub theme: String,
+    // icon themes to search, in order of preference
+    #[serde(deserialize_with = "one_or_many")]
+    pub theme: Vec<String>,
     xdg_folder: Option<String>,
+    // number of icons to load at once
+    pub concurrency: usize,
+    // number of times a failed icon lookup is retried
+    pub retries: usize,
+    // show a tile with the domain's initial for urls without an icon
+    pub domain_tiles: bool,
+    // number of icons kept in memory
+    pub cache_size: usize,
+    // look up the icons of every entry on startup
+    pub preload: bool,
 }
 
 impl Default for Os {
     fn default() -> Self {
         Self {
-            theme: DEFAULT_THEME.into(),
+            theme: vec![DEFAULT_THEME.into()],
             xdg_folder: None,
+            concurrency: DEFAULT_ICON_CONCURRENCY,
+            retries: DEFAULT_ICON_RETRIES,
+            domain_tiles: false,
+            cache_size: DEFAULT_ICON_CACHE_SIZE,
+            preload: false,
         }
     }
 }
 
+impl Os {
+    // icon themes that do not seem to be installed. The default
+    // theme is checked when jolly is built instead
+    pub fn validate(&self) -> Vec<String> {
+        let installed = freedesktop_icons::list_themes();
+        self.theme
+            .iter()
+            .filter(|t| *t != DEFAULT_THEME)
+            .filter(|t| !installed.iter().any(|i| i.eq_ignore_ascii_case(t)))
+            .map(|t| format!("config.ui.icon.theme '{t}' does not seem to be installed"))
+            .collect()
+    }
+}
+
 impl super::IconInterface for Os {
     fn get_default_icon(&self) -> Result<Icon, IconError> {
-        self.get_icon_for_iname("text-x-generic")
+        self.get_icon_for_iname("text-x-generic", DEFAULT_ICON_SIZE as u32)
     }
 
-    fn get_icon_for_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Icon, IconError> {
+    fn get_icon_for_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        size: u32,
+    ) -> Result<Icon, IconError> {
         let path = path.as_ref();
         let inames = self.get_iname_for_file(path)?;
 
         for iname in &inames {
-            let icon = self.get_icon_for_iname(iname);
+            let icon = self.get_icon_for_iname(iname, size);
             if icon.is_ok() {
                 return icon;
             }
@@ -46,9 +86,13 @@ impl super::IconInterface for Os {
         Err(format!("No valid icon. inames were {:?}", inames).into())
     }
 
-    fn get_icon_for_url(&self, url: &str) -> Result<Icon, IconError> {
+    fn get_icon_for_url(&self, url: &str, size: u32) -> Result<Icon, IconError> {
         let iname = self.get_iname_for_url(url)?;
-        self.get_icon_for_iname(&iname)
+        self.get_icon_for_iname(&iname, size)
+    }
+
+    fn get_icon_for_name(&self, name: &str, size: u32) -> Result<Icon, IconError> {
+        self.get_icon_for_iname(name, size)
     }
 
     // for linux apps we need to make sure there are some default mime
@@ -157,16 +201,20 @@ impl Os {
             .collect())
     }
 
-    fn get_icon_for_iname(&self, icon_name: &str) -> Result<Icon, IconError> {
+    // find an icon in the icon themes, preferring icons made for size
+    // pixels. SVG icons are rasterized at size pixels
+    fn get_icon_for_iname(&self, icon_name: &str, size: u32) -> Result<Icon, IconError> {
         use freedesktop_icons::lookup;
 
         let icon_name = icon_name.strip_suffix(".desktop").unwrap_or(icon_name);
 
-        let icon_path = lookup(icon_name)
-            .with_size(DEFAULT_ICON_SIZE)
-            .with_theme(&self.theme)
-            .find()
-            .ok_or("Could not lookup icon")?;
+        let icon_path = find_in_themes(&self.theme, |theme| {
+            lookup(icon_name)
+                .with_size(u16::try_from(size).unwrap_or(u16::MAX))
+                .with_theme(theme)
+                .find()
+        })
+        .ok_or("Could not lookup icon")?;
 
         // TODO handle other supported icon types
         if icon_path
@@ -178,7 +226,7 @@ impl Os {
             .extension()
             .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
         {
-            icon_from_svg(&icon_path)
+            icon_from_svg(&icon_path, size)
         } else {
             Err(format!(
                 "unsupported icon file type for icon {}",
@@ -189,6 +237,15 @@ impl Os {
     }
 }
 
+// walk the theme fallback chain, returning the first icon path that
+// one of the themes can resolve
+fn find_in_themes<F>(themes: &[String], mut lookup: F) -> Option<PathBuf>
+where
+    F: FnMut(&str) -> Option<PathBuf>,
+{
+    themes.iter().find_map(|t| lookup(t))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -197,6 +254,7 @@ mod tests {
     use tempfile;
 
     use super::*;
+    use crate::icon::{IconInterface, IconType};
     use iced::advanced::image::Data;
 
     // helper struct to allow building mock xdg data for testing
@@ -276,8 +334,9 @@ mod tests {
 
         fn os(&self, theme: &str) -> Os {
             Os {
-                theme: theme.into(),
+                theme: vec![theme.into()],
                 xdg_folder: Some(self.0.path().to_str().unwrap().into()),
+                ..Default::default()
             }
         }
     }
@@ -321,7 +380,10 @@ mod tests {
             .join("icon/jolly");
 
         let icon = Os::default()
-            .get_icon_for_iname(svg_icon.as_os_str().to_str().unwrap())
+            .get_icon_for_iname(
+                svg_icon.as_os_str().to_str().unwrap(),
+                DEFAULT_ICON_SIZE as u32,
+            )
             .unwrap();
         // expect pixel data from the icon
         assert!(matches!(
@@ -332,5 +394,74 @@ mod tests {
                 pixels: _
             }
         ));
+
+        // theme icons are rasterized at the size they are asked for
+        let icon = Os::default()
+            .try_load_icon_at(IconType::named(svg_icon.to_str().unwrap()), 96)
+            .unwrap();
+        assert!(matches!(
+            icon.data(),
+            Data::Rgba {
+                width: 96,
+                height: 96,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn icon_names_use_theme_lookup() {
+        // as above, freedesktop_icons treats unknown names as file
+        // paths, which lets us check that named icons are resolved
+        // by the icon theme lookup instead of as a custom icon file
+        let svg_icon = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+            .join("icon/jolly");
+
+        let icon = Os::default()
+            .try_load_icon(IconType::named(svg_icon.to_str().unwrap()))
+            .unwrap();
+        assert!(matches!(icon.data(), Data::Rgba { .. }));
+
+        // a custom icon with the same value is not found, since it has no extension
+        assert!(Os::default()
+            .try_load_icon(IconType::custom(&svg_icon))
+            .is_err());
+
+        assert!(Os::default()
+            .try_load_icon(IconType::named("not-a-real-icon-name"))
+            .is_err());
+    }
+
+    #[test]
+    fn theme_fallback_chain() {
+        // build a mock icon dir with two themes, where only the
+        // second theme has the icon we are looking for
+        let dir = tempfile::tempdir().unwrap();
+        create_dir(dir.path().join("first")).unwrap();
+        create_dir(dir.path().join("second")).unwrap();
+        write(dir.path().join("second/test-icon.png"), b"").unwrap();
+
+        let themes: Vec<String> = vec!["first".into(), "second".into()];
+        let mut searched = Vec::new();
+
+        let found = find_in_themes(&themes, |theme| {
+            searched.push(theme.to_string());
+            let p = dir.path().join(theme).join("test-icon.png");
+            p.exists().then_some(p)
+        });
+
+        assert_eq!(found, Some(dir.path().join("second/test-icon.png")));
+        assert_eq!(searched, themes);
+
+        assert_eq!(find_in_themes(&themes[..1], |_| None), None);
+    }
+
+    #[test]
+    fn theme_one_or_many() {
+        let os: Os = toml::from_str(r#"theme = "Papirus""#).unwrap();
+        assert_eq!(os.theme, vec!["Papirus".to_string()]);
+
+        let os: Os = toml::from_str(r#"theme = ["Papirus", "hicolor"]"#).unwrap();
+        assert_eq!(os.theme, vec!["Papirus".to_string(), "hicolor".to_string()]);
     }
 }
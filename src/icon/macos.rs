@@ -1,6 +1,9 @@
 #![cfg(target_os = "macos")]
 
-use super::{Context, Icon, IconError, IconInterface, DEFAULT_ICON_SIZE};
+use super::{
+    Context, Icon, IconError, IconInterface, DEFAULT_ICON_CACHE_SIZE, DEFAULT_ICON_CONCURRENCY,
+    DEFAULT_ICON_RETRIES, DEFAULT_ICON_SIZE,
+};
 use core_graphics::geometry::{CGPoint, CGRect, CGSize};
 use core_graphics::image::CGImageRef;
 use objc::rc::StrongPtr;
@@ -9,8 +12,39 @@ use objc::{class, msg_send, sel, sel_impl};
 use serde;
 use url::Url;
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Default)]
-pub struct Os;
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Os {
+    // number of icons to load at once
+    pub concurrency: usize,
+    // number of times a failed icon lookup is retried
+    pub retries: usize,
+    // show a tile with the domain's initial for urls without an icon
+    pub domain_tiles: bool,
+    // number of icons kept in memory
+    pub cache_size: usize,
+    // look up the icons of every entry on startup
+    pub preload: bool,
+}
+
+impl Default for Os {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_ICON_CONCURRENCY,
+            retries: DEFAULT_ICON_RETRIES,
+            domain_tiles: false,
+            cache_size: DEFAULT_ICON_CACHE_SIZE,
+            preload: false,
+        }
+    }
+}
+
+impl Os {
+    // there are no macos specific icon settings to check
+    pub fn validate(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
 
 impl IconInterface for Os {
     fn get_default_icon(&self) -> Result<Icon, IconError> {
@@ -26,13 +60,17 @@ impl IconInterface for Os {
         }
     }
 
-    fn get_icon_for_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Icon, IconError> {
+    fn get_icon_for_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        _size: u32,
+    ) -> Result<Icon, IconError> {
         // now we have an icon! At this point, we can start
         // using the nicer wrappers from core_graphics-rs
         unsafe { icon_for_file(path.as_ref().as_os_str().into()) }
     }
 
-    fn get_icon_for_url(&self, url: &str) -> Result<Icon, IconError> {
+    fn get_icon_for_url(&self, url: &str, _size: u32) -> Result<Icon, IconError> {
         Url::parse(url).context("url is not valid")?; // TODO, hoist this out of all 3 implementations
 
         unsafe {
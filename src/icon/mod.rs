@@ -36,6 +36,7 @@ use url::Url;
 
 mod linux_and_friends;
 mod macos;
+mod tile;
 mod windows;
 
 use lazy_static::lazy_static;
@@ -56,6 +57,15 @@ const SUPPORTED_ICON_EXTS: &[&str] = &[
 ];
 
 const DEFAULT_ICON_SIZE: u16 = 48; // TODO, support other icon sizes
+const DEFAULT_ICON_CONCURRENCY: usize = 4;
+const DEFAULT_ICON_RETRIES: usize = 2;
+// icons are never evicted from the cache unless a size is configured
+const DEFAULT_ICON_CACHE_SIZE: usize = usize::MAX;
+// icons are not preloaded for stores with more entries than this
+pub const MAX_PRELOAD_ENTRIES: usize = 1000;
+// delay before the first retry of a failed icon lookup. Doubled for
+// each retry after that
+const ICON_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(20);
 
 #[cfg(target_os = "macos")]
 pub use macos::Os as IconSettings;
@@ -115,11 +125,22 @@ trait IconInterface {
     fn get_default_icon(&self) -> Result<Icon, IconError>;
 
     // icon that would be used for a path that must exist
-    // path is guaranteed to be already canonicalized
-    fn get_icon_for_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Icon, IconError>;
+    // path is guaranteed to be already canonicalized. Platforms that
+    // can pick an icon size use size pixels
+    fn get_icon_for_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        size: u32,
+    ) -> Result<Icon, IconError>;
 
     // icon to use for a specific url or protocol handler.
-    fn get_icon_for_url(&self, url: &str) -> Result<Icon, IconError>;
+    fn get_icon_for_url(&self, url: &str, size: u32) -> Result<Icon, IconError>;
+
+    // icon to use for a named icon from the platform's icon
+    // theme. Not every platform has named icons
+    fn get_icon_for_name(&self, _name: &str, _size: u32) -> Result<Icon, IconError> {
+        Err("icon names are not supported on this platform".into())
+    }
 
     // provided method: version of get_default_icon that caches its
     // value. One value for lifetime of application
@@ -132,20 +153,39 @@ trait IconInterface {
             .clone()
     }
 
-    // provided method: uses icon interfaces to turn icontype into icon
-    fn load_icon(&self, itype: IconType) -> Icon {
-        let icon = self.try_load_icon(itype);
-        icon.unwrap_or(self.cached_default())
+    // provided method: uses icon interfaces to turn icontype into
+    // icon, retrying failed lookups. If domain_tiles is set, urls
+    // without an icon get a tile with their domain's initial. SVG
+    // icons are rasterized at size pixels
+    fn load_icon(&self, itype: IconType, retries: usize, domain_tiles: bool, size: u32) -> Icon {
+        let icon = retry_load(retries, ICON_RETRY_BACKOFF, || {
+            self.try_load_icon_at(itype.clone(), size)
+        });
+        icon.ok()
+            .or_else(|| {
+                itype
+                    .url_host()
+                    .filter(|_| domain_tiles)
+                    .map(tile::domain_tile)
+            })
+            .unwrap_or_else(|| self.cached_default())
     }
 
-    // convert an icontype into an icon
+    // convert an icontype into an icon of the default size
     fn try_load_icon(&self, itype: IconType) -> Result<Icon, IconError> {
+        self.try_load_icon_at(itype, DEFAULT_ICON_SIZE as u32)
+    }
+
+    // convert an icontype into an icon, rasterizing SVG icons at
+    // size pixels
+    fn try_load_icon_at(&self, itype: IconType, size: u32) -> Result<Icon, IconError> {
         match itype.0 {
-            IconVariant::Url(u) => self.get_icon_for_url(u.as_str()),
+            IconVariant::Url(u) => self.get_icon_for_url(u.as_str(), size),
+            IconVariant::Named(n) => self.get_icon_for_name(&n, size),
             IconVariant::File(p) => {
                 if p.exists() {
                     if let Ok(p) = p.canonicalize() {
-                        self.get_icon_for_file(p)
+                        self.get_icon_for_file(p, size)
                     } else {
                         Err("File Icon does not exist".into())
                     }
@@ -162,7 +202,7 @@ trait IconInterface {
                 {
                     Ok(Icon::from_path(p))
                 } else if ext.eq_ignore_ascii_case("svg") {
-                    icon_from_svg(&p)
+                    icon_from_svg(&p, size)
                 } else {
                     Err("is unsupported icon type".into())
                 }
@@ -173,15 +213,15 @@ trait IconInterface {
                 use which::which;
 
                 if let Some(exe) = command.split(" ").next().and_then(|e| which(e).ok()) {
-                    self.try_load_icon(IconType::file(exe))
+                    self.try_load_icon_at(IconType::file(exe), size)
                 } else if let Some(exe) = command
                     .split(" ")
                     .next()
-                    .and_then(|exe| self.try_load_icon(IconType::file(exe)).ok())
+                    .and_then(|exe| self.try_load_icon_at(IconType::file(exe), size).ok())
                 {
                     Ok(exe)
                 } else {
-                    self.try_load_icon(IconType::file(command))
+                    self.try_load_icon_at(IconType::file(command), size)
                 }
             }
         }
@@ -201,9 +241,7 @@ impl IconType {
     pub fn url(url: Url) -> Self {
         // hack to make paths that start with disk drives not show up as URLs
         #[cfg(target_os = "windows")]
-        if url.scheme().len() == 1
-            && "abcdefghijklmnopqrstuvwxyz".contains(url.scheme().chars().next().unwrap())
-        {
+        if is_drive_path(&url) {
             return Self(IconVariant::File(url.as_ref().into()));
         }
 
@@ -216,6 +254,49 @@ impl IconType {
     pub fn system<S: ToString>(cmd: S) -> Self {
         Self(IconVariant::System(cmd.to_string()))
     }
+
+    pub fn named<S: ToString>(name: S) -> Self {
+        Self(IconVariant::Named(name.to_string()))
+    }
+
+    // short description of how the icon is looked up
+    pub fn kind(&self) -> &'static str {
+        match &self.0 {
+            IconVariant::Url(_) => "url",
+            IconVariant::File(_) => "file",
+            IconVariant::System(_) => "system",
+            IconVariant::CustomIcon(_) => "custom",
+            IconVariant::Named(_) => "named",
+        }
+    }
+
+    // host of the url, if this is a url icon
+    fn url_host(&self) -> Option<&str> {
+        match &self.0 {
+            IconVariant::Url(u) => u.host_str().filter(|h| !h.is_empty()),
+            _ => None,
+        }
+    }
+
+    // path of the icon file, if this is a custom icon
+    pub fn custom_path(&self) -> Option<&std::path::Path> {
+        match &self.0 {
+            IconVariant::CustomIcon(p) => Some(p),
+            _ => None,
+        }
+    }
+}
+
+// windows paths such as C:\foo parse as urls with a single letter
+// scheme. We only treat them as paths if the drive letter is followed
+// by a path separator, so that single letter url schemes still work
+pub(crate) fn is_drive_path(url: &Url) -> bool {
+    let mut scheme = url.scheme().chars();
+    let is_drive_letter =
+        matches!((scheme.next(), scheme.next()), (Some(c), None) if c.is_ascii_alphabetic());
+
+    is_drive_letter
+        && url.as_str()[url.scheme().len() + 1..].starts_with(|c: char| c == '/' || c == '\\')
 }
 
 // represents the necessary information in an entry to look up an icon
@@ -231,6 +312,8 @@ enum IconVariant {
     System(String),
     // override "normal" icon and use icon from this path
     CustomIcon(std::path::PathBuf),
+    // override "normal" icon and use icon with this name from the icon theme
+    Named(String),
 }
 
 impl Hash for IconVariant {
@@ -240,6 +323,7 @@ impl Hash for IconVariant {
             IconVariant::File(p) => p.hash(state),
             IconVariant::CustomIcon(p) => p.hash(state),
             IconVariant::System(p) => p.hash(state),
+            IconVariant::Named(n) => n.hash(state),
         }
     }
 }
@@ -276,8 +360,52 @@ impl PartialEq for IconVariant {
                     false
                 }
             }
+            IconVariant::Named(s) => {
+                if let IconVariant::Named(o) = other {
+                    s == o
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+// load an icon on the current thread, reporting failures instead of
+// falling back to the default icon
+pub fn try_load_icon(is: &IconSettings, itype: IconType) -> Result<Icon, String> {
+    is.try_load_icon(itype).map_err(|e| e.to_string())
+}
+
+// run an icon lookup, retrying it with a backoff if it fails. Some
+// platform lookups (shell and COM calls on windows) can fail
+// transiently even when an icon exists
+fn retry_load<F>(
+    retries: usize,
+    backoff: std::time::Duration,
+    mut load: F,
+) -> Result<Icon, IconError>
+where
+    F: FnMut() -> Result<Icon, IconError>,
+{
+    let mut result = load();
+    let mut delay = backoff;
+    for _ in 0..retries {
+        if result.is_ok() {
+            break;
         }
+        std::thread::sleep(delay);
+        delay *= 2;
+        result = load();
     }
+    result
+}
+
+// size in pixels that icons shown at display_size are rasterized
+// at. Icons are never rasterized below the default size, so that they
+// stay sharp on high dpi screens
+pub fn raster_size(display_size: u16) -> u32 {
+    display_size.max(DEFAULT_ICON_SIZE) as u32
 }
 
 pub fn default_icon(is: &IconSettings) -> Icon {
@@ -287,11 +415,43 @@ pub fn default_icon(is: &IconSettings) -> Icon {
 use crate::Message;
 use iced::futures::channel::mpsc;
 
+// problems with the icon settings, including ones that are specific
+// to this platform
+pub fn validate_settings(settings: &IconSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    if settings.concurrency == 0 {
+        problems.push("config.ui.icon.concurrency must be at least 1, using 1".to_string());
+    }
+    if settings.cache_size == 0 {
+        problems.push(
+            "config.ui.icon.cache_size must be at least 1, otherwise icons are never kept"
+                .to_string(),
+        );
+    }
+    problems.extend(settings.validate());
+    problems
+}
+
 // represents an icon cache that can look up icons in a deferred worker thread
-#[derive(Default)]
+//
+// the cache holds at most capacity icons. Each icon remembers when it
+// was last used, and the least recently used icon is evicted to make
+// room for new ones. Entries keep their own copy of their icon, so
+// they have to let go of evicted icons for them to be freed
 pub struct IconCache {
     cmd: Option<std::sync::mpsc::Sender<IconCommand>>,
-    cache: HashMap<IconType, Option<Icon>>,
+    cache: HashMap<IconType, (Option<Icon>, u64)>,
+    capacity: usize,
+    // incremented on every use of the cache
+    clock: u64,
+    // icons evicted since the last call to take_evicted
+    evicted: Vec<IconType>,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IconCache {
@@ -299,13 +459,19 @@ impl IconCache {
         Self {
             cmd: None,
             cache: HashMap::new(),
+            capacity: DEFAULT_ICON_CACHE_SIZE,
+            clock: 0,
+            evicted: Vec::new(),
         }
     }
 
     pub fn get(&mut self, it: &IconType) -> Option<Icon> {
+        self.clock += 1;
+
         // if the key is the cache, either we have the icon or it has
         // already been scheduled. either way, send it.
-        if let Some(icon) = self.cache.get(it) {
+        if let Some((icon, used)) = self.cache.get_mut(it) {
+            *used = self.clock;
             return icon.clone();
         }
 
@@ -314,7 +480,7 @@ impl IconCache {
         if let Some(cmd) = &self.cmd {
             cmd.send(IconCommand::LoadIcon(it.clone()))
                 .expect("Could not send new icon lookup command");
-            self.cache.insert(it.clone(), None);
+            self.insert(it.clone(), None);
         }
 
         // at this point, we know we had a cache miss
@@ -322,7 +488,43 @@ impl IconCache {
     }
 
     pub fn add_icon(&mut self, it: IconType, i: Icon) {
-        self.cache.insert(it, Some(i));
+        self.clock += 1;
+        self.insert(it, Some(i));
+    }
+
+    // change the number of icons that are kept, evicting icons if
+    // there are too many
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn insert(&mut self, it: IconType, icon: Option<Icon>) {
+        self.cache.insert(it, (icon, self.clock));
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.cache.len() > self.capacity {
+            let oldest = self
+                .cache
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(it, _)| it.clone());
+            match oldest {
+                Some(it) => {
+                    self.cache.remove(&it);
+                    self.evicted.push(it);
+                }
+                None => break,
+            };
+        }
+    }
+
+    // the icons that were evicted since this was last called, which
+    // entries still holding them should drop
+    pub fn take_evicted(&mut self) -> Vec<IconType> {
+        std::mem::take(&mut self.evicted)
     }
 
     pub fn set_cmd(&mut self, cmd: std::sync::mpsc::Sender<IconCommand>) {
@@ -332,7 +534,8 @@ impl IconCache {
 
 #[derive(Debug)]
 pub enum IconCommand {
-    LoadSettings(IconSettings),
+    // settings for the worker, and the size to rasterize icons at
+    LoadSettings(IconSettings, u32),
     LoadIcon(IconType),
 }
 
@@ -349,48 +552,75 @@ pub fn icon_worker() -> mpsc::Receiver<Message> {
             .try_send(Message::StartedIconWorker(input))
             .expect("Could not send iconworker back to application");
 
-        let command = match command_stream.recv() {
-            Ok(i) => i,
-            _ => return,
-        };
-        let settings = if let IconCommand::LoadSettings(settings) = command {
-            settings
-        } else {
-            return;
-        };
-
-        loop {
-            let command = match command_stream.recv() {
-                Ok(i) => i,
-                _ => break,
-            };
+        run_icon_worker(command_stream, output);
+    });
+    sub_stream
+}
 
-            match command {
-                IconCommand::LoadIcon(icontype) => {
-                    // todo: handle error
-                    output
-                        .try_send(Message::IconReceived(
-                            icontype.clone(),
-                            settings.load_icon(icontype),
-                        ))
-                        .expect("Could not send icon back  application");
+// handle icon commands until the command channel is closed. Icons
+// are loaded by a pool of threads, so they may be sent back in any
+// order
+fn run_icon_worker(
+    command_stream: std::sync::mpsc::Receiver<IconCommand>,
+    output: mpsc::Sender<Message>,
+) {
+    use std::sync::{Arc, Mutex};
+
+    let (settings, size) = match command_stream.recv() {
+        Ok(IconCommand::LoadSettings(settings, size)) => (settings, size),
+        _ => return,
+    };
+
+    let (work, work_stream) = std::sync::mpsc::channel::<IconType>();
+    let work_stream = Arc::new(Mutex::new(work_stream));
+
+    let pool: Vec<_> = (0..settings.concurrency.max(1))
+        .map(|_| {
+            let work_stream = work_stream.clone();
+            let settings = settings.clone();
+            let mut output = output.clone();
+            std::thread::spawn(move || loop {
+                let icontype = match work_stream.lock().unwrap().recv() {
+                    Ok(i) => i,
+                    _ => break,
+                };
+
+                // todo: handle error
+                output
+                    .try_send(Message::IconReceived(
+                        icontype.clone(),
+                        settings.load_icon(icontype, settings.retries, settings.domain_tiles, size),
+                    ))
+                    .expect("Could not send icon back  application");
+            })
+        })
+        .collect();
+
+    loop {
+        match command_stream.recv() {
+            Ok(IconCommand::LoadIcon(icontype)) => {
+                if work.send(icontype).is_err() {
+                    break;
                 }
-                _ => break,
             }
+            _ => break,
         }
-    });
-    sub_stream
+    }
+
+    // let the pool finish any icons that are already queued
+    drop(work);
+    for thread in pool {
+        let _ = thread.join();
+    }
 }
 
-// convert an svg file into a pixmap
-fn icon_from_svg(path: &std::path::Path) -> Result<Icon, IconError> {
+// convert an svg file into a pixmap of icon_size pixels
+fn icon_from_svg(path: &std::path::Path, icon_size: u32) -> Result<Icon, IconError> {
     use resvg::usvg::TreeParsing;
     let svg_data = std::fs::read(path).context("could not open file")?;
     let utree = resvg::usvg::Tree::from_data(&svg_data, &Default::default())
         .context("could not parse svg")?;
 
-    let icon_size = DEFAULT_ICON_SIZE as u32;
-
     let mut pixmap =
         resvg::tiny_skia::Pixmap::new(icon_size, icon_size).context("could not create pixmap")?;
 
@@ -417,7 +647,7 @@ fn icon_from_svg(path: &std::path::Path) -> Result<Icon, IconError> {
 mod tests {
     use crate::icon::IconType;
 
-    use super::{Icon, IconError, IconInterface, IconSettings};
+    use super::{Icon, IconCache, IconError, IconInterface, IconSettings, DEFAULT_ICON_SIZE};
     use iced::advanced::image;
 
     pub(crate) fn hash_eq_icon(icon: &Icon, ficon: &Icon) -> bool {
@@ -462,6 +692,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let mut icache = IconCache::new();
+        icache.set_capacity(2);
+
+        let icon = || Icon::from_pixels(1, 1, &[0, 0, 0, 255]);
+        let (a, b, c) = (
+            IconType::named("a"),
+            IconType::named("b"),
+            IconType::named("c"),
+        );
+
+        icache.add_icon(a.clone(), icon());
+        icache.add_icon(b.clone(), icon());
+        // using a makes b the oldest icon
+        assert!(icache.get(&a).is_some());
+        icache.add_icon(c.clone(), icon());
+
+        assert_eq!(icache.cache.len(), 2);
+        assert!(icache.get(&b).is_none());
+        assert!(icache.get(&a).is_some());
+        assert!(icache.get(&c).is_some());
+
+        // shrinking the cache evicts right away
+        icache.set_capacity(1);
+        assert_eq!(icache.cache.len(), 1);
+        assert!(icache.get(&c).is_some());
+
+        // every eviction is reported once
+        assert_eq!(icache.take_evicted(), [b, a]);
+        assert!(icache.take_evicted().is_empty());
+    }
+
     #[test]
     fn default_icon_is_iconlike() {
         iconlike(
@@ -478,11 +741,161 @@ mod tests {
         let cur_exe = std::env::current_exe().unwrap();
 
         iconlike(
-            IconSettings::default().get_icon_for_file(&cur_exe).unwrap(),
+            IconSettings::default()
+                .get_icon_for_file(&cur_exe, DEFAULT_ICON_SIZE as u32)
+                .unwrap(),
             "for current executable",
         );
     }
 
+    #[test]
+    fn concurrent_icon_loads() {
+        use crate::Message;
+        use std::collections::HashSet;
+
+        let settings = IconSettings::default();
+        assert!(settings.concurrency > 1);
+
+        // custom icons are not read until they are displayed, so
+        // these do not need to exist
+        let icontypes: Vec<_> = (0..20)
+            .map(|i| IconType::custom(format!("icon{i}.png")))
+            .collect();
+
+        let (input, command_stream) = std::sync::mpsc::channel();
+        let (output, mut results) = iced::futures::channel::mpsc::channel(100);
+
+        input
+            .send(super::IconCommand::LoadSettings(
+                settings,
+                super::DEFAULT_ICON_SIZE as u32,
+            ))
+            .unwrap();
+        for it in &icontypes {
+            input
+                .send(super::IconCommand::LoadIcon(it.clone()))
+                .unwrap();
+        }
+        drop(input);
+
+        super::run_icon_worker(command_stream, output);
+
+        let mut received = HashSet::new();
+        while let Ok(Some(msg)) = results.try_next() {
+            if let Message::IconReceived(it, _) = msg {
+                received.insert(it);
+            }
+        }
+
+        assert_eq!(received, icontypes.into_iter().collect());
+    }
+
+    #[test]
+    fn failed_loads_are_retried() {
+        use std::cell::Cell;
+        use std::time::Duration;
+
+        let icon = Icon::from_pixels(1, 1, &[1, 1, 1, 1]);
+
+        // fails once, then succeeds
+        let calls = Cell::new(0);
+        let result = super::retry_load(2, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            if calls.get() == 1 {
+                Err("transient failure".into())
+            } else {
+                Ok(icon.clone())
+            }
+        });
+        assert!(hash_eq_icon(&result.unwrap(), &icon));
+        assert_eq!(calls.get(), 2);
+
+        // gives up once the retries are used up
+        calls.set(0);
+        let result = super::retry_load(2, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            Err::<Icon, IconError>("permanent failure".into())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 3);
+
+        // successes are not retried
+        calls.set(0);
+        let result = super::retry_load(2, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            Ok(icon.clone())
+        });
+        assert!(result.is_ok());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn domain_tiles_for_urls() {
+        struct NoIcons;
+
+        impl IconInterface for NoIcons {
+            fn get_default_icon(&self) -> Result<Icon, IconError> {
+                Ok(Icon::from_pixels(1, 1, &[1, 1, 1, 1]))
+            }
+
+            fn get_icon_for_file<P: AsRef<std::path::Path>>(
+                &self,
+                _path: P,
+                _size: u32,
+            ) -> Result<Icon, IconError> {
+                Err("no file icons".into())
+            }
+
+            fn get_icon_for_url(&self, _url: &str, _size: u32) -> Result<Icon, IconError> {
+                Err("no url icons".into())
+            }
+        }
+
+        let url = |u: &str| IconType::url(url::Url::parse(u).unwrap());
+        let default = NoIcons.cached_default();
+
+        let tile = NoIcons.load_icon(
+            url("https://example.com/foo"),
+            0,
+            true,
+            DEFAULT_ICON_SIZE as u32,
+        );
+        iconlike(tile.clone(), "for domain tile");
+        assert!(!hash_eq_icon(&tile, &default));
+
+        // the same host always gives the same tile
+        let again = NoIcons.load_icon(
+            url("https://example.com/bar"),
+            0,
+            true,
+            DEFAULT_ICON_SIZE as u32,
+        );
+        assert!(hash_eq_icon(&tile, &again));
+        let other = NoIcons.load_icon(
+            url("https://jolly.example"),
+            0,
+            true,
+            DEFAULT_ICON_SIZE as u32,
+        );
+        assert!(!hash_eq_icon(&tile, &other));
+
+        // only urls with a host get tiles, and only if enabled
+        let disabled = NoIcons.load_icon(
+            url("https://example.com"),
+            0,
+            false,
+            DEFAULT_ICON_SIZE as u32,
+        );
+        assert!(hash_eq_icon(&disabled, &default));
+        let mailto = NoIcons.load_icon(
+            url("mailto:foo@example.com"),
+            0,
+            true,
+            DEFAULT_ICON_SIZE as u32,
+        );
+        assert!(hash_eq_icon(&mailto, &default));
+    }
+
     #[test]
     fn paths_are_canonicalized() {
         struct MockIcon;
@@ -495,13 +908,14 @@ mod tests {
             fn get_icon_for_file<P: AsRef<std::path::Path>>(
                 &self,
                 path: P,
+                _size: u32,
             ) -> Result<Icon, IconError> {
                 let path = path.as_ref();
                 assert!(path.as_os_str() == path.canonicalize().unwrap().as_os_str());
                 self.get_default_icon()
             }
 
-            fn get_icon_for_url(&self, _url: &str) -> Result<Icon, IconError> {
+            fn get_icon_for_url(&self, _url: &str, _size: u32) -> Result<Icon, IconError> {
                 panic!("expected file, not url")
             }
         }
@@ -517,7 +931,7 @@ mod tests {
 
         let icon_type = super::IconType(super::IconVariant::File(filename));
         let mock = MockIcon;
-        mock.load_icon(icon_type);
+        mock.load_icon(icon_type, 0, false, DEFAULT_ICON_SIZE as u32);
     }
 
     #[test]
@@ -568,13 +982,13 @@ mod tests {
         let failed_results: Vec<_> = happycase_urls
             .into_iter()
             .filter_map(|u| {
-                os.get_icon_for_url(&u)
+                os.get_icon_for_url(&u, DEFAULT_ICON_SIZE as u32)
                     .context(format!("failed to load '{u}'"))
                     .err()
                     .map(|e| e.to_string())
             })
             .chain(sadcase_urls.into_iter().filter_map(|u| {
-                os.get_icon_for_url(&u)
+                os.get_icon_for_url(&u, DEFAULT_ICON_SIZE as u32)
                     .ok()
                     .map(|_| format!("successfully loaded '{u}'"))
             }))
@@ -594,7 +1008,7 @@ mod tests {
 
         let os = IconSettings::default();
 
-        os.get_icon_for_file(dir.path())
+        os.get_icon_for_file(dir.path(), DEFAULT_ICON_SIZE as u32)
             .expect("No Icon for folder".into());
 
         for f in files {
@@ -603,7 +1017,7 @@ mod tests {
             file.sync_all().unwrap();
 
             assert!(path.exists());
-            os.get_icon_for_file(&path)
+            os.get_icon_for_file(&path, DEFAULT_ICON_SIZE as u32)
                 .expect(&format!("No Icon for file: {f}"));
         }
     }
@@ -646,6 +1060,45 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn svg_icons_use_requested_size() {
+        use super::*;
+        let test_svg = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+            .join("icon/jolly.svg");
+
+        let svg_icon = IconSettings::default()
+            .try_load_icon_at(IconType::custom(test_svg), 96)
+            .unwrap();
+        assert!(matches!(
+            svg_icon.data(),
+            image::Data::Rgba {
+                width: 96,
+                height: 96,
+                pixels
+            } if pixels.len() == 96 * 96 * 4
+        ));
+
+        // icons are never rasterized below the default size
+        assert_eq!(raster_size(20), DEFAULT_ICON_SIZE as u32);
+        assert_eq!(raster_size(96), 96);
+    }
+
+    #[test]
+    fn drive_paths_are_not_urls() {
+        use super::is_drive_path;
+        use url::Url;
+
+        for path in [r"c:\foo", r"C:\foo", "c:/foo", "Z:/foo/bar.txt"] {
+            let url = Url::parse(path).unwrap();
+            assert!(is_drive_path(&url), "{path} should be a drive path");
+        }
+
+        for url in ["x:foo", "c:", "tel:12345", "https://example.com"] {
+            let url = Url::parse(url).unwrap();
+            assert!(!is_drive_path(&url), "{url} should be a url");
+        }
+    }
+
     #[test]
     fn system_entry_heuristic() {
         use tempfile::tempdir;
@@ -0,0 +1,165 @@
+// generates fallback icons for url entries: a colored tile with the
+// first letter of the site's domain, similar to a contact avatar.
+//
+// letters are drawn with a small built in bitmap font, so that the
+// tiles look the same on every platform and do not depend on the
+// fonts that are installed
+
+use super::{Icon, DEFAULT_ICON_SIZE};
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+// how many pixels wide each dot of a glyph is drawn
+const GLYPH_SCALE: usize = 4;
+
+const TEXT_COLOR: [u8; 3] = [255, 255, 255];
+
+// background colors for tiles. All of them are dark enough to show
+// white text
+const PALETTE: [[u8; 3]; 8] = [
+    [0xc6, 0x28, 0x28],
+    [0xad, 0x14, 0x57],
+    [0x6a, 0x1b, 0x9a],
+    [0x28, 0x35, 0x93],
+    [0x02, 0x77, 0xbd],
+    [0x00, 0x69, 0x5c],
+    [0x2e, 0x7d, 0x32],
+    [0xbf, 0x36, 0x0c],
+];
+
+// one row per line of the glyph, the highest bit of each row is the
+// leftmost dot
+#[rustfmt::skip]
+const LETTERS: [[u8; GLYPH_HEIGHT]; 26] = [
+    [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // A
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110], // B
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110], // C
+    [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110], // D
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111], // E
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000], // F
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111], // G
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // H
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // I
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // J
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001], // K
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111], // L
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001], // M
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001], // N
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // O
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000], // P
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101], // Q
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001], // R
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110], // S
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // T
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // U
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // V
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010], // W
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001], // X
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100], // Y
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111], // Z
+];
+
+#[rustfmt::skip]
+const DIGITS: [[u8; GLYPH_HEIGHT]; 10] = [
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110], // 0
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 1
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111], // 2
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110], // 3
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010], // 4
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110], // 5
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110], // 6
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000], // 7
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110], // 8
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100], // 9
+];
+
+// the character shown on the tile for host. The www. prefix is
+// skipped since it would give most sites the same letter
+fn initial(host: &str) -> Option<char> {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    host.chars()
+        .find(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+}
+
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_HEIGHT]> {
+    match c {
+        'A'..='Z' => LETTERS.get(c as usize - 'A' as usize),
+        '0'..='9' => DIGITS.get(c as usize - '0' as usize),
+        _ => None,
+    }
+}
+
+// pick a background color for host. Uses FNV-1a so that the color
+// for a site never changes between runs or versions of jolly
+fn color(host: &str) -> [u8; 3] {
+    let hash = host
+        .to_ascii_lowercase()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+    PALETTE[(hash % PALETTE.len() as u64) as usize]
+}
+
+// render the tile for a url host
+pub fn domain_tile(host: &str) -> Icon {
+    let size = DEFAULT_ICON_SIZE as usize;
+    let background = color(host);
+
+    let mut pixels: Vec<u8> = std::iter::repeat([background[0], background[1], background[2], 255])
+        .take(size * size)
+        .flatten()
+        .collect();
+
+    if let Some(rows) = initial(host).and_then(glyph) {
+        let left = (size - GLYPH_WIDTH * GLYPH_SCALE) / 2;
+        let top = (size - GLYPH_HEIGHT * GLYPH_SCALE) / 2;
+
+        for y in 0..GLYPH_HEIGHT * GLYPH_SCALE {
+            let row = rows[y / GLYPH_SCALE];
+            for x in 0..GLYPH_WIDTH * GLYPH_SCALE {
+                if row & (1 << (GLYPH_WIDTH - 1 - x / GLYPH_SCALE)) != 0 {
+                    let offset = ((top + y) * size + left + x) * 4;
+                    pixels[offset..offset + 3].copy_from_slice(&TEXT_COLOR);
+                }
+            }
+        }
+    }
+
+    Icon::from_pixels(size as u32, size as u32, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_initials() {
+        assert_eq!(initial("example.com"), Some('E'));
+        assert_eq!(initial("www.github.com"), Some('G'));
+        assert_eq!(initial("9gag.com"), Some('9'));
+        assert_eq!(initial("-.example"), Some('E'));
+        assert_eq!(initial("ñandu.example"), Some('A'));
+        assert_eq!(initial("..."), None);
+
+        assert!(glyph('A').is_some());
+        assert!(glyph('Z').is_some());
+        assert!(glyph('0').is_some());
+        assert!(glyph('a').is_none());
+    }
+
+    #[test]
+    fn tile_colors_are_deterministic() {
+        assert_eq!(color("example.com"), color("example.com"));
+        assert_eq!(color("example.com"), color("EXAMPLE.com"));
+
+        // different hosts do not all get the same color
+        let colors: std::collections::HashSet<_> =
+            ["a.com", "b.com", "c.com", "d.com", "e.com", "f.com"]
+                .iter()
+                .map(|h| color(h))
+                .collect();
+        assert!(colors.len() > 1);
+    }
+}
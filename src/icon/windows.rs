@@ -33,7 +33,19 @@ impl Context<()> for BOOL {
 }
 
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
-pub struct Os;
+#[serde(default)]
+pub struct Os {
+    // number of icons to load at once
+    pub concurrency: usize,
+    // number of times a failed icon lookup is retried
+    pub retries: usize,
+    // show a tile with the domain's initial for urls without an icon
+    pub domain_tiles: bool,
+    // number of icons kept in memory
+    pub cache_size: usize,
+    // look up the icons of every entry on startup
+    pub preload: bool,
+}
 
 impl Default for Os {
     fn default() -> Self {
@@ -42,7 +54,13 @@ impl Default for Os {
             use windows::Win32::System::Com::CoIncrementMTAUsage;
             let _ = CoIncrementMTAUsage(); // hack to force COM to be initialized for testing
         }
-        Self
+        Self {
+            concurrency: super::DEFAULT_ICON_CONCURRENCY,
+            retries: super::DEFAULT_ICON_RETRIES,
+            domain_tiles: false,
+            cache_size: super::DEFAULT_ICON_CACHE_SIZE,
+            preload: false,
+        }
     }
 }
 
@@ -111,6 +129,13 @@ impl WideString {
     }
 }
 
+impl Os {
+    // there are no windows specific icon settings to check
+    pub fn validate(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
 impl super::IconInterface for Os {
     fn get_default_icon(&self) -> Result<Icon, IconError> {
         let siid = SHSTOCKICONID(0); // SIID_DOCNOASSOC
@@ -148,7 +173,11 @@ impl super::IconInterface for Os {
         }
     }
 
-    fn get_icon_for_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Icon, IconError> {
+    fn get_icon_for_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        size: u32,
+    ) -> Result<Icon, IconError> {
         let wide_path = WideString::from_path(path.as_ref())?;
 
         if wide_path.0.len() > MAX_PATH as usize {
@@ -173,8 +202,8 @@ impl super::IconInterface for Os {
 			       | 0x20, //SIIGBF_CROPTOSQUARE
             );
             let size = SIZE {
-                cx: DEFAULT_ICON_SIZE as i32,
-                cy: DEFAULT_ICON_SIZE as i32,
+                cx: size as i32,
+                cy: size as i32,
             };
 
             let hbitmap = ifactory
@@ -191,7 +220,7 @@ impl super::IconInterface for Os {
         }
     }
 
-    fn get_icon_for_url(&self, url: &str) -> Result<Icon, IconError> {
+    fn get_icon_for_url(&self, url: &str, _size: u32) -> Result<Icon, IconError> {
         //  https://devblogs.microsoft.com/oldnewthing/20150914-00/?p=91601
         let flags = ASSOCF(0x80 | 0x1000); // ASSOCF_REMAPRUNDLL | ASSOCF_ISPROTOCOL
 
@@ -679,9 +708,9 @@ mod tests {
         std::fs::File::create(&equal).unwrap();
         std::fs::File::create(&under).unwrap();
 
-        os.get_icon_for_file(over).unwrap_err();
-        os.get_icon_for_file(equal).unwrap_err();
-        os.get_icon_for_file(under).unwrap();
+        os.get_icon_for_file(over, DEFAULT_ICON_SIZE as u32).unwrap_err();
+        os.get_icon_for_file(equal, DEFAULT_ICON_SIZE as u32).unwrap_err();
+        os.get_icon_for_file(under, DEFAULT_ICON_SIZE as u32).unwrap();
     }
 
     #[test]
@@ -8,6 +8,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use ::log::trace;
+use iced::futures::channel::oneshot;
+use iced::futures::StreamExt;
 use iced::widget::text::Shaping;
 use iced::widget::text_input;
 use iced::widget::{Text, TextInput};
@@ -16,20 +18,41 @@ use iced::{executor, Application, Command, Element, Length, Renderer, Size};
 use lazy_static;
 use std::sync::mpsc;
 
+pub mod bench;
 pub mod cli;
 pub mod config;
 mod custom;
 mod entry;
 pub mod error;
+pub mod explain;
+pub mod export;
+mod history;
 mod icon;
 mod log;
 mod platform;
+mod preview;
+mod score_expr;
+mod search;
 mod search_results;
 mod settings;
 pub mod store;
 mod theme;
+mod timer;
 mod ui;
 
+// how long toast notices are shown
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(2);
+
+// how long to wait for another press of enter before the chosen
+// action is taken, when enter cycles actions
+const ACTION_CYCLE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(400);
+
+// maximum height of image previews
+const PREVIEW_IMAGE_HEIGHT: f32 = 200.0;
+
+// longest selected text that is used to seed the query
+const MAX_SEED_LEN: usize = 200;
+
 lazy_static::lazy_static! {
     static ref TEXT_INPUT_ID : text_input::Id = text_input::Id::unique();
 }
@@ -39,9 +62,27 @@ pub enum Message {
     ExternalEvent(event::Event),
     EntrySelected(entry::EntryId),
     EntryHovered(entry::EntryId),
+    CursorMoved(iced::Point),
+    ResultsScrolled(f32),
+    CopyName,
+    NavigationKey(keyboard::KeyCode),
+    SelectionRead(Option<String>),
     DimensionsChanged(f32, f32),
     StartedIconWorker(mpsc::Sender<icon::IconCommand>),
+    StartedTimer(mpsc::Sender<(std::time::Instant, Message)>),
     IconReceived(icon::IconType, icon::Icon),
+    StealFocus,
+    DismissWarnings,
+    SettingsReloaded(Box<settings::Settings>),
+    PreviewLoaded(entry::EntryId, preview::Preview),
+    ToastExpired(String),
+    ActionCycleExpired(u64),
+    AnimationFrame(std::time::Instant),
+    SelectionOpened(Result<(), std::sync::Arc<entry::Error>>),
+    CommandFinished(
+        String,
+        Result<platform::CommandStatus, std::sync::Arc<entry::Error>>,
+    ),
 }
 
 #[derive(Debug)]
@@ -60,56 +101,476 @@ impl Default for StoreLoadedState {
 #[derive(Default)]
 pub struct Jolly {
     searchtext: String,
+    query: String, // searchtext after normalization
     store_state: StoreLoadedState,
     search_results: search_results::SearchResults,
     modifiers: keyboard::Modifiers,
     settings: settings::Settings,
     icache: icon::IconCache,
     bounds: iced::Rectangle,
-    focused_once: bool, // for some reason gnome defocusses
-                        // the jolly window when launching, so we have to ignore
-                        // defocus events until we receive a focus event.
+    max_height: Option<u32>, // cap on the window height, in pixels
+    warnings: Vec<String>,   // shown in a footer until dismissed
+    config_path: Option<std::path::PathBuf>, // watched for settings changes
+    preview: Option<(entry::EntryId, Option<preview::Preview>)>, // preview of the selection, once loaded
+    toast: Option<String>,            // short notice shown below the results
+    hover: search_results::HoverGate, // decides if hovering selects a result
+    action_cycle: search_results::ActionCycle, // action chosen by pressing enter again
+    timers: timer::Timers,            // messages that are delivered after a delay
+    history: history::History,        // how often each entry was selected
+    history_writer: history::Writer,  // saves the history off the UI thread
+    keyword_entry: Option<entry::EntryId>, // entry whose keyword starts the query
+    opening: bool,                    // window is hidden while an entry is opened
+    awaiting: bool,                   // an entry's command is running until it exits
+    focused_once: bool,               // for some reason gnome defocusses
+                                      // the jolly window when launching, so we have to ignore
+                                      // defocus events until we receive a focus event.
+}
+
+// commands to run when jolly starts up
+fn startup_command(settings: &settings::Settings, timers: &mut timer::Timers) -> Command<Message> {
+    // steal focus after startup: fixed bug on windows where it is
+    // possible to start jolly without focus. Some window managers
+    // fight this, so it can be delayed or skipped
+    let steal_focus = match settings.ui.focus_delay() {
+        Some(delay) if delay.is_zero() => window::gain_focus(),
+        Some(delay) => {
+            timers.after(delay, Message::StealFocus);
+            Command::none()
+        }
+        None => Command::none(),
+    };
+
+    // start with the text the user copied before summoning jolly
+    let seed = if settings.ui.seed_from_selection {
+        clipboard::read(Message::SelectionRead)
+    } else {
+        Command::none()
+    };
+
+    Command::batch([
+        window::change_mode(window::Mode::Windowed),
+        text_input::focus(TEXT_INPUT_ID.clone()),
+        steal_focus,
+        seed,
+    ])
+}
+
+// the query to start with for the selected text. Only the first
+// line is used, and text that is too long to be a query is ignored
+fn seed_query(selection: &str) -> Option<String> {
+    let line = selection.lines().map(str::trim).find(|l| !l.is_empty())?;
+    if line.chars().count() > MAX_SEED_LEN {
+        None
+    } else {
+        Some(line.to_string())
+    }
+}
+
+// the search box captures most key presses, including the ones that
+// only move the selection, so those are watched for separately
+fn navigation_event(event: event::Event, _status: event::Status) -> Option<Message> {
+    match event {
+        event::Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers,
+        }) => search_results::navigation_key(key_code, modifiers).map(Message::NavigationKey),
+        _ => None,
+    }
+}
+
+// run work that blocks, like waiting for a command to exit, on a
+// thread of its own so that it does not hold up the executor. The
+// thread is started once the future is first polled
+async fn on_thread<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, oneshot::Canceled> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+    receiver.await
+}
+
+// give the search box focus if the selection moved into it
+fn focus_command(focus: Option<search_results::Focus>) -> Command<Message> {
+    match focus {
+        Some(search_results::Focus::SearchBox) => text_input::focus(TEXT_INPUT_ID.clone()),
+        _ => Command::none(),
+    }
+}
+
+// window title that includes the number of search results
+fn title_with_count(count: usize) -> String {
+    match count {
+        1 => "jolly \u{2014} 1 result".to_string(),
+        n => format!("jolly \u{2014} {n} results"),
+    }
 }
 
 impl Jolly {
+    // build jolly with an already loaded store, skipping the startup
+    // commands. Only intended for testing the update and view logic
+    pub fn with_store(store: store::Store, settings: settings::Settings) -> Self {
+        let config = config::Config {
+            settings,
+            store: Ok(store),
+            ..Default::default()
+        };
+        Self::new(config).0
+    }
+
     fn move_to_err(&mut self, err: error::Error) -> Command<<Jolly as Application>::Message> {
         ::log::error!("{err}");
         self.store_state = StoreLoadedState::Finished(err);
         Command::none()
     }
 
+    // start loading the preview of the selected entry, if it changed
+    fn update_preview(&mut self) -> Command<<Jolly as Application>::Message> {
+        let store = match &self.store_state {
+            StoreLoadedState::LoadSucceeded(s, _) if self.settings.ui.preview => s,
+            _ => return Command::none(),
+        };
+
+        let id = match self.search_results.selected() {
+            Some(id) => id,
+            None => {
+                self.preview = None;
+                return Command::none();
+            }
+        };
+
+        if matches!(self.preview, Some((current, _)) if current == id) {
+            return Command::none();
+        }
+
+        match store.get(id).preview_path() {
+            Some(path) => {
+                self.preview = Some((id, None));
+                Command::perform(async move { preview::load(&path) }, move |p| {
+                    Message::PreviewLoaded(id, p)
+                })
+            }
+            None => {
+                self.preview = None;
+                Command::none()
+            }
+        }
+    }
+
+    // shows the preview of the selected entry, once it is loaded
+    fn preview_pane(&self) -> Option<Element<'_, Message, Renderer<theme::Theme>>> {
+        let content: Element<_, _> = match &self.preview {
+            Some((_, Some(preview::Preview::Text(text)))) => Text::new(text)
+                .size(self.settings.ui.entry.description_size())
+                .shaping(Shaping::Advanced)
+                .into(),
+            Some((_, Some(preview::Preview::Image(handle)))) => widget::Image::new(handle.clone())
+                .height(PREVIEW_IMAGE_HEIGHT)
+                .into(),
+            Some((_, Some(preview::Preview::Unavailable(reason)))) => {
+                Text::new(format!("No preview: {reason}"))
+                    .size(self.settings.ui.entry.description_size())
+                    .into()
+            }
+            _ => return None,
+        };
+
+        Some(widget::container(content).padding(5).into())
+    }
+
+    // enter was pressed, but no result is selected
+    fn handle_empty_enter(&mut self) -> Command<<Jolly as Application>::Message> {
+        match self.settings.ui.empty_enter {
+            ui::EmptyEnter::Close => iced::window::close(),
+            ui::EmptyEnter::Ignore => Command::none(),
+            ui::EmptyEnter::Fallback => {
+                let url = match &self.settings.fallback_url {
+                    Some(url) if !self.query.trim().is_empty() => url,
+                    _ => return Command::none(),
+                };
+
+                let result = entry::open_fallback(url, &self.query, &self.settings);
+
+                if let Err(e) = result.map_err(error::Error::StoreError) {
+                    self.move_to_err(e)
+                } else {
+                    iced::window::close()
+                }
+            }
+        }
+    }
+
+    // enter was pressed. If enter cycles actions and the selected
+    // entry has more than one, the action is only taken once enter
+    // has not been pressed again for a moment
+    fn handle_enter(&mut self) -> Command<<Jolly as Application>::Message> {
+        if self.awaiting {
+            return Command::none();
+        }
+
+        let id = match self.search_results.selected() {
+            Some(id) => id,
+            None => return self.handle_empty_enter(),
+        };
+
+        let actions = match &self.store_state {
+            StoreLoadedState::LoadSucceeded(s, _) => s.get(id).actions(self.settings.allow_copy),
+            _ => return Command::none(),
+        };
+
+        // held modifiers already choose the action
+        if !self.settings.ui.enter_cycles_actions
+            || self.action() != entry::Action::Open
+            || actions.len() < 2
+        {
+            return self.handle_selection(id);
+        }
+
+        let press = self.action_cycle.press(id, actions.len());
+        self.timers
+            .after(ACTION_CYCLE_INTERVAL, Message::ActionCycleExpired(press));
+        Command::none()
+    }
+
     fn handle_selection(&mut self, id: entry::EntryId) -> Command<<Jolly as Application>::Message> {
+        self.take_action(id, self.action())
+    }
+
+    // select an entry with the requested action. The entry may not
+    // allow the action, in which case it does what it can instead
+    fn take_action(
+        &mut self,
+        id: entry::EntryId,
+        action: entry::Action,
+    ) -> Command<<Jolly as Application>::Message> {
         // we can only continue if the store is loaded
         let store = match &self.store_state {
             StoreLoadedState::LoadSucceeded(s, _) => s,
             _ => return Command::none(),
         };
 
+        // only one command is waited for at a time
+        if self.awaiting {
+            ::log::info!("Ignoring selection while a command is running");
+            return Command::none();
+        }
+
         let entry = store.get(id);
+        let action = entry.action(action);
 
-        // if the user is pressing the command key, we want to copy to
-        // clipboard instead of opening the link
-        if self.modifiers.command() {
-            let result = entry.format_selection(&self.searchtext);
-            let msg = format!("copied to clipboard: {}", &result);
+        // keyword entries can wait for the user to type a parameter
+        // instead of running without one
+        if entry.missing_param(&self.query) {
+            match self.settings.empty_keyword {
+                settings::EmptyKeyword::Run => (),
+                settings::EmptyKeyword::Ignore => return Command::none(),
+                settings::EmptyKeyword::Prefill => {
+                    let query = format!("{} ", entry.keyword().unwrap_or_default());
+                    return Command::batch([
+                        self.update(Message::SearchTextChanged(query)),
+                        text_input::move_cursor_to_end(TEXT_INPUT_ID.clone()),
+                    ]);
+                }
+            }
+        }
 
-            ::log::info!("{msg}");
+        // remember the selection, so that entries that are used often
+        // rank higher next time
+        self.history
+            .record(entry.name(), std::time::SystemTime::now());
+        self.history_writer.save(&self.history);
 
-            let cmds = [
-                clipboard::write(result),
-                self.move_to_err(error::Error::FinalMessage(msg)),
-            ];
-            Command::batch(cmds)
-        } else {
-            let result = entry.handle_selection(&self.searchtext);
+        // entries that run other entries open each of them in order
+        if !entry.runs().is_empty() {
+            return match store.resolve_runs(id) {
+                Ok(targets) => {
+                    let entries = targets.into_iter().map(|t| store.get(t).clone()).collect();
+                    self.open_entries(entries)
+                }
+                Err(e) => self.move_to_err(error::Error::StoreError(e)),
+            };
+        }
 
-            if let Err(e) = result.map_err(error::Error::StoreError) {
-                self.move_to_err(e)
-            } else {
-                iced::window::close()
+        if action == entry::Action::Reveal {
+            let entry = entry.clone();
+            return self.reveal_entry(entry);
+        }
+
+        if action == entry::Action::Open && entry.awaits_exit() {
+            let entry = entry.clone();
+            return self.await_entry(entry);
+        }
+
+        if action == entry::Action::Open {
+            let entry = entry.clone();
+            return self.open_entries(vec![entry]);
+        }
+
+        let result = self
+            .settings
+            .copy_text(&entry.format_selection(&self.query));
+
+        // if the user is also pressing the open_copy modifier, the
+        // entry is opened as well, which closes the window
+        if action == entry::Action::OpenAndCopy {
+            ::log::info!("copied to clipboard: {}", &result);
+            let entry = entry.clone();
+            return Command::batch([clipboard::write(result), self.open_entries(vec![entry])]);
+        }
+
+        self.copy_to_clipboard(result)
+    }
+
+    // copy the name of an entry, as it is shown in the results
+    fn copy_name(&mut self, id: entry::EntryId) -> Command<<Jolly as Application>::Message> {
+        let name = match &self.store_state {
+            StoreLoadedState::LoadSucceeded(s, _) if self.settings.allow_copy => {
+                s.get(id).format_name(&self.query)
             }
+            _ => return Command::none(),
+        };
+        self.copy_to_clipboard(name)
+    }
+
+    // copy text to the clipboard, and let the user know it was copied
+    fn copy_to_clipboard(&mut self, result: String) -> Command<<Jolly as Application>::Message> {
+        let msg = format!("copied to clipboard: {}", &result);
+        ::log::info!("{msg}");
+
+        let feedback = match self.settings.copy_feedback {
+            settings::CopyFeedback::Message => self.move_to_err(error::Error::FinalMessage(msg)),
+            settings::CopyFeedback::Toast => {
+                self.toast = Some(msg.clone());
+                self.timers
+                    .after(TOAST_DURATION, Message::ToastExpired(msg));
+                Command::none()
+            }
+            settings::CopyFeedback::None => iced::window::close(),
+        };
+        Command::batch([clipboard::write(result), feedback])
+    }
+
+    // hide the window before opening the entries, so that it
+    // disappears right away. The entries are opened one after the
+    // other, stopping at the first error. The window is closed once
+    // all of them have been opened
+    fn open_entries(&mut self, entries: Vec<entry::StoreEntry>) -> Command<Message> {
+        let query = self.query.clone();
+        let settings = self.settings.clone();
+        // launching may wait for the program to start
+        let open = Command::perform(
+            on_thread(move || {
+                entries
+                    .iter()
+                    .try_for_each(|e| e.handle_selection(&query, &settings))
+            }),
+            |result| {
+                let result = result.unwrap_or_else(|_| {
+                    Err(entry::Error::PlatformError(platform::Error::IoError(
+                        std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "the launch stopped unexpectedly",
+                        ),
+                    )))
+                });
+                Message::SelectionOpened(result.map_err(std::sync::Arc::new))
+            },
+        );
+        self.opening = true;
+        Command::batch([window::change_mode(window::Mode::Hidden), open])
+    }
+
+    // show the file of an entry in the file manager. Like opening
+    // it, the window is hidden first and closed afterwards
+    fn reveal_entry(&mut self, entry: entry::StoreEntry) -> Command<Message> {
+        // the file manager may take a while to start
+        let reveal = Command::perform(on_thread(move || entry.reveal()), |result| {
+            let result = result.unwrap_or_else(|_| {
+                Err(entry::Error::PlatformError(platform::Error::IoError(
+                    std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        "the file manager stopped unexpectedly",
+                    ),
+                )))
+            });
+            Message::SelectionOpened(result.map_err(std::sync::Arc::new))
+        });
+        self.opening = true;
+        Command::batch([window::change_mode(window::Mode::Hidden), reveal])
+    }
+
+    // run a system entry in the background. The window stays open
+    // to report how the command finished
+    fn await_entry(&mut self, entry: entry::StoreEntry) -> Command<Message> {
+        let query = self.query.clone();
+        let name = entry.format_name(&query);
+        self.toast = Some(format!("running {name}..."));
+        self.awaiting = true;
+        Command::perform(
+            on_thread(move || entry.handle_awaited_selection(&query)),
+            move |result| {
+                let result = result.unwrap_or_else(|_| {
+                    Err(entry::Error::PlatformError(platform::Error::IoError(
+                        std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "the command stopped unexpectedly",
+                        ),
+                    )))
+                });
+                Message::CommandFinished(name, result.map_err(std::sync::Arc::new))
+            },
+        )
+    }
+
+    // the action shown for the selected entry. While enter is
+    // cycling through actions, this is the chosen action
+    fn shown_action(&self, store: &store::Store) -> entry::Action {
+        match self.action_cycle.pending() {
+            Some((id, index)) if self.search_results.selected() == Some(id) => store
+                .get(id)
+                .actions(self.settings.allow_copy)
+                .get(index)
+                .copied()
+                .unwrap_or_else(|| self.action()),
+            _ => self.action(),
         }
     }
+
+    // what selecting an entry does, based on the held modifier keys
+    fn action(&self) -> entry::Action {
+        if !self.settings.allow_copy {
+            return entry::Action::Open;
+        }
+        entry::Action::from_modifiers(self.modifiers, self.settings.open_copy_modifier)
+    }
+
+    // footer line that shows any warnings from loading the config
+    fn warnings_footer(&self) -> Option<Element<'_, Message, Renderer<theme::Theme>>> {
+        if self.warnings.is_empty() {
+            return None;
+        }
+
+        let text_size = self.settings.ui.entry.description_size();
+        let dismiss = widget::Button::new(Text::new("\u{2715}").size(text_size))
+            .style(theme::ButtonStyle::Transparent)
+            .on_press(Message::DismissWarnings);
+
+        let row = widget::Row::new()
+            .push(
+                Text::new(self.warnings.join("\n"))
+                    .size(text_size)
+                    .shaping(Shaping::Advanced)
+                    .style(iced::theme::Text::Color(
+                        self.settings.ui.theme.danger_color(),
+                    ))
+                    .width(Length::Fill),
+            )
+            .push(dismiss)
+            .align_items(iced::Alignment::Center);
+
+        Some(widget::container(row).padding(5).into())
+    }
 }
 
 impl Application for Jolly {
@@ -122,12 +583,34 @@ impl Application for Jolly {
         let mut jolly = Self::default();
 
         jolly.settings = config.settings;
+        jolly.icache.set_capacity(jolly.settings.ui.icon.cache_size);
+
+        for w in &config.warnings {
+            ::log::warn!("{w}");
+        }
+        jolly.warnings = config.warnings;
 
         jolly.bounds.width = jolly.settings.ui.width as f32;
+        let screen_height = platform::screen_height();
+        jolly.max_height = jolly.settings.ui.max_height(screen_height);
+        jolly
+            .warnings
+            .extend(jolly.settings.ui.max_height_warning(screen_height));
+        jolly.config_path = config.path.clone();
+        if let Some(path) = &config.path {
+            jolly.history = history::History::for_config(path);
+        }
 
         jolly.store_state = match config.store {
-            Ok(store) => {
-                let msg = format!("Loaded {} entries", store.len());
+            Ok(mut store) => {
+                store.set_history(&jolly.history, std::time::SystemTime::now());
+
+                let msg = match &config.path {
+                    Some(path) if jolly.settings.ui.show_config_path => {
+                        format!("Loaded {} entries from {}", store.len(), path.display())
+                    }
+                    _ => format!("Loaded {} entries", store.len()),
+                };
 
                 StoreLoadedState::LoadSucceeded(store, msg)
             }
@@ -136,19 +619,16 @@ impl Application for Jolly {
                 StoreLoadedState::Finished(e)
             }
         };
-        (
-            jolly,
-            Command::batch([
-                window::change_mode(window::Mode::Windowed),
-                text_input::focus(TEXT_INPUT_ID.clone()),
-                // steal focus after startup: fixed bug on windows where it is possible to start jolly without focus
-                window::gain_focus(),
-            ]),
-        )
+        let cmd = startup_command(&jolly.settings, &mut jolly.timers);
+        (jolly, cmd)
     }
 
     fn title(&self) -> String {
-        String::from("jolly")
+        if self.settings.ui.title_shows_count && !self.query.is_empty() {
+            title_with_count(self.search_results.entries().len())
+        } else {
+            String::from("jolly")
+        }
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
@@ -173,11 +653,46 @@ impl Application for Jolly {
             }
 
             Message::ExternalEvent(event::Event::Window(w))
-                if w == window::Event::Unfocused && self.focused_once =>
+                if w == window::Event::Unfocused
+                    && self.focused_once
+                    && !self.opening
+                    && !self.awaiting =>
             {
                 return iced::window::close();
             }
 
+            Message::StealFocus => return window::gain_focus(),
+
+            Message::StartedTimer(cmd) => {
+                self.timers.set_cmd(cmd);
+                return Command::none();
+            }
+
+            // the selection only seeds the query if the user has not
+            // started typing yet
+            Message::SelectionRead(selection) => {
+                return match selection.as_deref().and_then(seed_query) {
+                    Some(query) if self.searchtext.is_empty() => {
+                        self.update(Message::SearchTextChanged(query))
+                    }
+                    _ => Command::none(),
+                };
+            }
+
+            // apply new settings without touching the store. The
+            // current query is searched again, so that the results
+            // follow the new settings right away
+            Message::SettingsReloaded(settings) => {
+                ::log::info!("Reloaded settings");
+                if let StoreLoadedState::LoadSucceeded(store, _) = &mut self.store_state {
+                    store.set_search_settings(settings.search.clone());
+                }
+                self.icache.set_capacity(settings.ui.icon.cache_size);
+                self.settings = *settings;
+                self.max_height = self.settings.ui.max_height(platform::screen_height());
+                return self.update(Message::SearchTextChanged(self.searchtext.clone()));
+            }
+
             // handle height change even if UI has failed to load
             Message::DimensionsChanged(width, height) => {
                 let width = if matches!(self.store_state, StoreLoadedState::Finished(_)) {
@@ -186,6 +701,11 @@ impl Application for Jolly {
                     self.settings.ui.width as _
                 };
 
+                let height = match self.max_height {
+                    Some(max) => height.min(max as f32),
+                    None => height,
+                };
+
                 self.bounds.width = width;
                 self.bounds.height = height;
 
@@ -201,21 +721,38 @@ impl Application for Jolly {
         };
 
         // if we are here, we are loaded and we dont want to quit
-        match message {
+        let cmd = match message {
             Message::SearchTextChanged(txt) => {
+                self.query = self.settings.search.normalize(&txt);
                 self.searchtext = txt;
+                self.action_cycle.cancel();
 
-                let matches = store.find_matches(&self.searchtext).into_iter();
+                let matches = store.find_matches(&self.query).into_iter();
 
                 // todo: determine which entries need icons
-                let new_results = search_results::SearchResults::new(matches, &self.settings.ui);
+
+                // every edit to the query rebuilds the results, which
+                // moves the selection back to the best match
+                self.hover.disarm();
+                let mut new_results =
+                    search_results::SearchResults::new(matches, &self.settings.ui);
+                if self.settings.ui.animations {
+                    new_results.animate_from(&self.search_results, std::time::Instant::now());
+                }
 
                 // load icons of whatever matches are being displayed
                 store.load_icons(new_results.entries(), &mut self.icache);
 
+                // the icon of an active keyword is shown in the search box
+                self.keyword_entry = store.active_keyword(&self.query);
+                if let Some(id) = self.keyword_entry {
+                    store.load_icons(&[id], &mut self.icache);
+                }
+
                 self.search_results = new_results;
 
-                Command::none()
+                // new results start scrolled to the top
+                self.search_results.scroll(store)
             }
             Message::ExternalEvent(event::Event::Window(window::Event::FileDropped(path))) => {
                 println!("{:?}", path);
@@ -229,47 +766,93 @@ impl Application for Jolly {
                 {
                     if key == keyboard::KeyCode::Escape {
                         return iced::window::close();
-                    } else if key == keyboard::KeyCode::NumpadEnter
-                        || key == keyboard::KeyCode::Enter
+                    } else if (key == keyboard::KeyCode::NumpadEnter
+                        || key == keyboard::KeyCode::Enter)
+                        && !self.settings.ui.enter_cycles_actions
                     {
-                        let cmd = if let Some(id) = self.search_results.selected() {
-                            self.handle_selection(id)
-                        } else {
-                            iced::window::close()
-                        };
-                        return cmd;
+                        return self.handle_enter();
                     }
                 }
 
+                // when enter cycles actions, every press counts, but
+                // a press can be reported both when the key is typed
+                // and when it is released, so only typing is used
                 if keyboard::Event::CharacterReceived('\r') == e {
-                    let cmd = if let Some(id) = self.search_results.selected() {
-                        self.handle_selection(id)
-                    } else {
-                        iced::window::close()
-                    };
-                    return cmd;
+                    return self.handle_enter();
                 }
 
                 if let keyboard::Event::ModifiersChanged(m) = e {
                     self.modifiers = m;
                 }
 
-                self.search_results.handle_kb(e);
-                Command::none()
+                // keep the mouse from taking the selection away from
+                // the keyboard
+                if let keyboard::Event::KeyPressed { .. } = e {
+                    self.hover.disarm();
+                }
+
+                let focus = focus_command(self.search_results.handle_kb(e));
+                self.action_cycle.follow(self.search_results.selected());
+                Command::batch([focus, self.search_results.scroll(store)])
             }
+            Message::NavigationKey(code) => {
+                self.hover.disarm();
+                let focus = focus_command(self.search_results.handle_key(code));
+                self.action_cycle.follow(self.search_results.selected());
+                Command::batch([focus, self.search_results.scroll(store)])
+            }
+            Message::CopyName => match self.search_results.selected() {
+                Some(id) => self.copy_name(id),
+                None => Command::none(),
+            },
             Message::EntryHovered(entry) => {
-                self.search_results.set_selection(entry);
+                let hovered = if self.settings.ui.hover_requires_motion {
+                    self.hover.hover(entry)
+                } else {
+                    Some(entry)
+                };
+                if let Some(entry) = hovered {
+                    self.search_results.set_selection(entry);
+                    self.action_cycle.follow(self.search_results.selected());
+                }
+                Command::none()
+            }
+            Message::CursorMoved(position) => {
+                if let Some(entry) = self.hover.cursor_moved(position) {
+                    self.search_results.set_selection(entry);
+                    self.action_cycle.follow(self.search_results.selected());
+                }
+                Command::none()
+            }
+            Message::ResultsScrolled(offset) => {
+                self.search_results.scrolled(store, offset);
                 Command::none()
             }
             Message::EntrySelected(entry) => self.handle_selection(entry),
+            Message::DismissWarnings => {
+                self.warnings.clear();
+                Command::none()
+            }
             Message::StartedIconWorker(worker) => {
                 worker
                     .send(icon::IconCommand::LoadSettings(
                         self.settings.ui.icon.clone(),
+                        icon::raster_size(self.settings.ui.entry.text_size()),
                     ))
                     .expect("Could not send message to iconworker");
                 self.icache.set_cmd(worker);
 
+                if self.settings.ui.icon.preload {
+                    if store.len() <= icon::MAX_PRELOAD_ENTRIES {
+                        store.preload_icons(&mut self.icache);
+                    } else {
+                        ::log::info!(
+                            "Not preloading icons, store has more than {} entries",
+                            icon::MAX_PRELOAD_ENTRIES
+                        );
+                    }
+                }
+
                 Command::none()
             }
             Message::IconReceived(it, icon) => {
@@ -279,27 +862,128 @@ impl Application for Jolly {
 
                 Command::none()
             }
+            Message::SelectionOpened(Ok(())) => iced::window::close(),
+            Message::SelectionOpened(Err(e)) => {
+                self.opening = false;
+                let e = std::sync::Arc::try_unwrap(e)
+                    .map(error::Error::StoreError)
+                    .unwrap_or_else(|e| error::Error::CustomError(e.to_string()));
+                Command::batch([
+                    window::change_mode(window::Mode::Windowed),
+                    self.move_to_err(e),
+                ])
+            }
+            Message::CommandFinished(name, result) => {
+                self.awaiting = false;
+                self.toast = None;
+                let e = match result {
+                    Ok(status) if status.success => {
+                        error::Error::FinalMessage(format!("{name} finished successfully"))
+                    }
+                    Ok(status) => error::Error::CustomError(format!("{name} failed: {status}")),
+                    Err(e) => std::sync::Arc::try_unwrap(e)
+                        .map(error::Error::StoreError)
+                        .unwrap_or_else(|e| error::Error::CustomError(e.to_string())),
+                };
+                self.move_to_err(e)
+            }
+            Message::ActionCycleExpired(press) => match self.action_cycle.expire(press) {
+                Some((id, index)) => {
+                    let actions = store.get(id).actions(self.settings.allow_copy);
+                    match actions.get(index) {
+                        Some(action) => self.take_action(id, *action),
+                        None => Command::none(),
+                    }
+                }
+                None => Command::none(),
+            },
+            Message::AnimationFrame(now) => {
+                self.search_results.tick(now);
+                Command::none()
+            }
+            Message::ToastExpired(msg) => {
+                // a newer toast may have replaced this one
+                if self.toast.as_ref() == Some(&msg) {
+                    self.toast = None;
+                }
+                Command::none()
+            }
+            Message::PreviewLoaded(id, preview) => {
+                if matches!(self.preview, Some((current, _)) if current == id) {
+                    self.preview = Some((id, Some(preview)));
+                }
+                Command::none()
+            }
             _ => Command::none(),
-        }
+        };
+
+        // the selection may have changed
+        Command::batch([cmd, self.update_preview()])
     }
 
     fn view(&self) -> Element<'_, Message, Renderer<Self::Theme>> {
         use StoreLoadedState::*;
 
         let ui: Element<_, Renderer<Self::Theme>> = match &self.store_state {
-            LoadSucceeded(store, msg) => widget::Column::new()
-                .push(
-                    TextInput::new(msg, &self.searchtext)
-                        .on_input(Message::SearchTextChanged)
-                        .size(self.settings.ui.search.common.text_size())
-                        .id(TEXT_INPUT_ID.clone())
-                        .padding(self.settings.ui.search.padding),
-                )
-                .push(
-                    self.search_results
-                        .view(&self.searchtext, store, Message::EntrySelected),
-                )
-                .into(),
+            LoadSucceeded(store, msg) => {
+                let text_size = self.settings.ui.search.common.text_size();
+                // long queries may be shown shortened, in which case
+                // edits to what is shown are applied to the whole query
+                let shown = self.settings.ui.shown_query(&self.searchtext);
+                let input = TextInput::new(msg, &shown.text)
+                    .on_input(move |edited| {
+                        Message::SearchTextChanged(shown.edit(&self.searchtext, &edited))
+                    })
+                    .size(text_size)
+                    .id(TEXT_INPUT_ID.clone())
+                    .padding(self.settings.ui.search.padding);
+
+                // text input icons can only be font glyphs, so the
+                // keyword icon is drawn at the leading edge of the box
+                let keyword_icon = self
+                    .keyword_entry
+                    .and_then(|id| store.get(id).shown_icon(&self.settings.ui.icon));
+                let search_box: Element<_, _> = match keyword_icon {
+                    Some(icon) => widget::Row::new()
+                        .push(widget::Image::new(icon).width(text_size).height(text_size))
+                        .push(input)
+                        .spacing(4)
+                        .align_items(iced::Alignment::Center)
+                        .into(),
+                    None => input.into(),
+                };
+
+                let col = widget::Column::new()
+                    .push(search_box)
+                    .push(self.search_results.view(
+                        &self.query,
+                        store,
+                        self.shown_action(store),
+                        Message::EntrySelected,
+                    ));
+
+                let col = match self.preview_pane() {
+                    Some(preview) => col.push(preview),
+                    None => col,
+                };
+
+                let col = match &self.toast {
+                    Some(toast) => col.push(
+                        widget::container(
+                            Text::new(toast)
+                                .size(self.settings.ui.entry.description_size())
+                                .shaping(Shaping::Advanced),
+                        )
+                        .padding(5),
+                    ),
+                    None => col,
+                };
+
+                match self.warnings_footer() {
+                    Some(footer) => col.push(footer).into(),
+                    None => col.into(),
+                }
+            }
             Pending => Text::new("Loading Bookmarks...").into(),
             Finished(err) => {
                 let errtext = Text::new(err.to_string()).shaping(Shaping::Advanced);
@@ -312,7 +996,7 @@ impl Application for Jolly {
                     style = theme::ContainerStyle::Error;
                     let title = Text::new("Oops, Jolly has encountered an Error...")
                         .style(iced::theme::Text::Color(
-                            ui::Color::from_str("#D64541").into(),
+                            self.settings.ui.theme.danger_color(),
                         ))
                         .size(2 * self.settings.ui.search.common.text_size());
                     children = vec![title.into(), errtext.into()];
@@ -328,7 +1012,9 @@ impl Application for Jolly {
             }
         };
 
-        custom::MeasuredContainer::new(ui, Message::DimensionsChanged).into()
+        custom::MeasuredContainer::new(ui, Message::DimensionsChanged)
+            .padding(self.settings.ui.padding)
+            .into()
     }
 
     fn theme(&self) -> Self::Theme {
@@ -337,7 +1023,959 @@ impl Application for Jolly {
 
     fn subscription(&self) -> iced::Subscription<Message> {
         let channel = subscription::run(icon::icon_worker);
+        let timers = subscription::run(timer::timer_worker);
         let external = subscription::events().map(Message::ExternalEvent);
-        subscription::Subscription::batch([channel, external].into_iter())
+        // result rows capture the cursor movements that hover them,
+        // so cursor movements are watched separately
+        let cursor = subscription::events_with(|event, _| match event {
+            event::Event::Mouse(iced::mouse::Event::CursorMoved { position }) => {
+                Some(Message::CursorMoved(position))
+            }
+            _ => None,
+        });
+        // the search box captures key presses with the command key,
+        // so the shortcut to copy names is watched separately
+        let copy_name = subscription::events_with(|event, _| match event {
+            event::Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::C,
+                modifiers,
+            }) if modifiers.command() && modifiers.shift() => Some(Message::CopyName),
+            _ => None,
+        });
+        let navigation = subscription::events_with(navigation_event);
+        let reload = match &self.config_path {
+            Some(path) => subscription::run_with_id(
+                path.clone(),
+                config::watch_settings(path.clone())
+                    .map(|settings| Message::SettingsReloaded(Box::new(settings))),
+            ),
+            None => subscription::Subscription::none(),
+        };
+        // frames are only watched while results are moving, so
+        // that jolly is idle otherwise
+        let frames = if self.settings.ui.animations && self.search_results.animating() {
+            window::frames().map(Message::AnimationFrame)
+        } else {
+            subscription::Subscription::none()
+        };
+        subscription::Subscription::batch(
+            [
+                channel, timers, external, cursor, copy_name, navigation, reload, frames,
+            ]
+            .into_iter(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action_names(cmd: Command<Message>) -> Vec<String> {
+        cmd.actions().iter().map(|a| format!("{a:?}")).collect()
+    }
+
+    fn press(key_code: keyboard::KeyCode) -> Message {
+        Message::ExternalEvent(event::Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers: keyboard::Modifiers::empty(),
+        }))
+    }
+
+    #[test]
+    fn with_store_is_loaded() {
+        let store = store::tests::parse_store(
+            r#"['foo']
+               location = 'a'
+               ['bar']
+               location = 'b'"#,
+        )
+        .unwrap();
+
+        let mut jolly = Jolly::with_store(store, Default::default());
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::LoadSucceeded(_, _)
+        ));
+        assert!(jolly.search_results.entries().is_empty());
+
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        assert_eq!(jolly.search_results.entries(), [0]);
+
+        let _ = jolly.update(Message::SearchTextChanged("a".into()));
+        assert_eq!(jolly.search_results.entries(), [1]);
+
+        let _ = jolly.update(Message::SearchTextChanged("".into()));
+        assert!(jolly.search_results.entries().is_empty());
+    }
+
+    #[test]
+    fn seed_query_from_selection() {
+        assert_eq!(seed_query("rust lang"), Some("rust lang".into()));
+        assert_eq!(
+            seed_query("\n  first line \nsecond"),
+            Some("first line".into())
+        );
+        assert_eq!(seed_query(" \n\t"), None);
+        assert_eq!(seed_query(&"x".repeat(MAX_SEED_LEN + 1)), None);
+
+        let store = store::tests::parse_store(
+            "['search %s']\nurl = 'https://example.com/?q=%s'\nkeyword = 'sr'",
+        )
+        .unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+
+        let _ = jolly.update(Message::SelectionRead(Some("sr rust\n".into())));
+        assert_eq!(jolly.searchtext, "sr rust");
+        assert_eq!(jolly.search_results.entries(), [0]);
+
+        // text the user already typed is kept
+        let _ = jolly.update(Message::SelectionRead(Some("other".into())));
+        assert_eq!(jolly.searchtext, "sr rust");
+
+        let mut jolly =
+            Jolly::with_store(store::tests::parse_store("").unwrap(), Default::default());
+        let _ = jolly.update(Message::SelectionRead(None));
+        assert_eq!(jolly.searchtext, "");
+    }
+
+    #[test]
+    fn title_shows_result_count() {
+        assert_eq!(title_with_count(0), "jolly \u{2014} 0 results");
+        assert_eq!(title_with_count(1), "jolly \u{2014} 1 result");
+        assert_eq!(title_with_count(5), "jolly \u{2014} 5 results");
+
+        let text = "['foo']\nlocation = 'a'\n['foobar']\nlocation = 'b'";
+        let mut settings = settings::Settings::default();
+        settings.ui.title_shows_count = true;
+        let mut jolly = Jolly::with_store(store::tests::parse_store(text).unwrap(), settings);
+
+        assert_eq!(jolly.title(), "jolly");
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        assert_eq!(jolly.title(), "jolly \u{2014} 2 results");
+        let _ = jolly.update(Message::SearchTextChanged("foob".into()));
+        assert_eq!(jolly.title(), "jolly \u{2014} 1 result");
+        let _ = jolly.update(Message::SearchTextChanged("".into()));
+        assert_eq!(jolly.title(), "jolly");
+
+        // off by default
+        let jolly = Jolly::with_store(store::tests::parse_store(text).unwrap(), Default::default());
+        assert_eq!(jolly.title(), "jolly");
+    }
+
+    #[test]
+    fn keyword_icon_follows_query() {
+        let store = store::tests::parse_store(
+            "['foo']\nlocation = 'a'\n['search %s']\nurl = 'b/%s'\nkeyword = 'sr'",
+        )
+        .unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+
+        let _ = jolly.update(Message::SearchTextChanged("sr".into()));
+        assert_eq!(jolly.keyword_entry, Some(1));
+        let _ = jolly.update(Message::SearchTextChanged("sr rust".into()));
+        assert_eq!(jolly.keyword_entry, Some(1));
+
+        // cleared once the keyword is no longer the first word
+        let _ = jolly.update(Message::SearchTextChanged("srx".into()));
+        assert_eq!(jolly.keyword_entry, None);
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        assert_eq!(jolly.keyword_entry, None);
+    }
+
+    #[test]
+    fn preload_enqueues_entry_icons() {
+        use std::collections::HashSet;
+
+        let text = r#"['foo']
+                      location = 'a'
+                      ['bar']
+                      location = 'a'
+                      ['baz']
+                      url = 'https://example.com'"#;
+
+        // collect the icon types that are looked up once the icon
+        // worker starts
+        let started = |preload| {
+            let mut settings = settings::Settings::default();
+            settings.ui.icon.preload = preload;
+            let mut jolly = Jolly::with_store(store::tests::parse_store(text).unwrap(), settings);
+
+            let (tx, rx) = mpsc::channel();
+            let _ = jolly.update(Message::StartedIconWorker(tx));
+            drop(jolly);
+
+            let mut commands = rx.iter();
+            assert!(matches!(
+                commands.next(),
+                Some(icon::IconCommand::LoadSettings(..))
+            ));
+            commands
+                .map(|c| match c {
+                    icon::IconCommand::LoadIcon(it) => it,
+                    c => panic!("unexpected command {c:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let store = store::tests::parse_store(text).unwrap();
+        let expected: HashSet<_> = store.entries().map(|e| e.icontype().clone()).collect();
+        assert_eq!(expected.len(), 2);
+
+        // each icon type is only looked up once
+        let loaded = started(true);
+        assert_eq!(loaded.len(), expected.len());
+        assert_eq!(loaded.into_iter().collect::<HashSet<_>>(), expected);
+
+        assert!(started(false).is_empty());
+    }
+
+    #[test]
+    fn empty_enter_modes() {
+        let store = store::tests::parse_store("['foo']\nlocation = 'a'").unwrap();
+        let enter = || {
+            Message::ExternalEvent(event::Event::Keyboard(keyboard::Event::KeyReleased {
+                key_code: keyboard::KeyCode::Enter,
+                modifiers: keyboard::Modifiers::empty(),
+            }))
+        };
+
+        let mut settings = settings::Settings::default();
+        let mut jolly = Jolly::with_store(store.clone(), settings.clone());
+        let _ = jolly.update(Message::SearchTextChanged("bar".into()));
+        assert!(jolly.search_results.selected().is_none());
+        assert_eq!(
+            action_names(jolly.update(enter())),
+            ["Action::Window(Action::Close)"]
+        );
+
+        settings.ui.empty_enter = ui::EmptyEnter::Ignore;
+        let mut jolly = Jolly::with_store(store.clone(), settings.clone());
+        let _ = jolly.update(Message::SearchTextChanged("bar".into()));
+        assert!(action_names(jolly.update(enter())).is_empty());
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::LoadSucceeded(_, _)
+        ));
+
+        // without a fallback url, fallback mode does nothing
+        settings.ui.empty_enter = ui::EmptyEnter::Fallback;
+        let mut jolly = Jolly::with_store(store.clone(), settings.clone());
+        let _ = jolly.update(Message::SearchTextChanged("bar".into()));
+        assert!(action_names(jolly.update(enter())).is_empty());
+
+        // use a denied scheme so that we can see the fallback was
+        // attempted without actually opening anything
+        settings.fallback_url = Some("javascript:search(%s)".into());
+        let mut jolly = Jolly::with_store(store, settings);
+        let _ = jolly.update(Message::SearchTextChanged("bar baz".into()));
+        assert!(action_names(jolly.update(enter())).is_empty());
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::Finished(error::Error::StoreError(entry::Error::DisallowedScheme(_)))
+        ));
+    }
+
+    #[test]
+    fn settings_reload_keeps_store() {
+        let store = store::tests::parse_store("['foo']\nlocation = 'a'").unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+
+        let mut settings = settings::Settings::default();
+        settings.ui.theme.background_color = ui::Color::from_str("#123456");
+        assert_ne!(jolly.theme(), settings.ui.theme);
+
+        let cmd = jolly.update(Message::SettingsReloaded(Box::new(settings.clone())));
+        assert!(action_names(cmd).is_empty());
+
+        assert_eq!(jolly.theme(), settings.ui.theme);
+        assert_eq!(jolly.searchtext, "foo");
+        match &jolly.store_state {
+            StoreLoadedState::LoadSucceeded(store, msg) => {
+                assert_eq!(store.len(), 1);
+                assert_eq!(msg, "Loaded 1 entries");
+            }
+            state => panic!("unexpected state {state:?}"),
+        }
+        assert_eq!(jolly.search_results.entries(), [0]);
+    }
+
+    #[test]
+    fn settings_reload_updates_results() {
+        let store = store::tests::parse_store(
+            "['foo a']\nlocation = 'a'\n['foo b']\nlocation = 'b'\n['foo c']\nlocation = 'c'",
+        )
+        .unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        assert_eq!(jolly.search_results.entries().len(), 3);
+
+        // the results use the new settings without another edit
+        let mut settings = settings::Settings::default();
+        settings.ui.max_results = 2;
+        let _ = jolly.update(Message::SettingsReloaded(Box::new(settings)));
+        assert_eq!(jolly.search_results.entries().len(), 2);
+        assert_eq!(jolly.searchtext, "foo");
+    }
+
+    #[test]
+    fn ctrl_n_and_ctrl_p_move_selection() {
+        let store = store::tests::parse_store(
+            "['foo a']\nlocation = 'a'\n['foo b']\nlocation = 'b'\n['foo c']\nlocation = 'c'",
+        )
+        .unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        let results = jolly.search_results.entries().to_vec();
+
+        // the search box captures the key presses, but they are
+        // still seen by the navigation filter
+        let key = |key_code, modifiers| {
+            let event = event::Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            });
+            navigation_event(event, event::Status::Captured)
+        };
+        let ctrl = keyboard::Modifiers::CTRL;
+
+        let _ = jolly.update(key(keyboard::KeyCode::N, ctrl).unwrap());
+        assert_eq!(jolly.search_results.selected(), Some(results[1]));
+        let _ = jolly.update(key(keyboard::KeyCode::N, ctrl).unwrap());
+        assert_eq!(jolly.search_results.selected(), Some(results[2]));
+        let _ = jolly.update(key(keyboard::KeyCode::P, ctrl).unwrap());
+        assert_eq!(jolly.search_results.selected(), Some(results[1]));
+
+        // without ctrl, the letters are left to the search box
+        assert!(key(keyboard::KeyCode::N, keyboard::Modifiers::empty()).is_none());
+        assert!(key(keyboard::KeyCode::Down, keyboard::Modifiers::empty()).is_none());
+
+        // and the same press seen by the search box does not move
+        // the selection a second time
+        let _ = jolly.update(Message::ExternalEvent(event::Event::Keyboard(
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::N,
+                modifiers: ctrl,
+            },
+        )));
+        assert_eq!(jolly.search_results.selected(), Some(results[1]));
+    }
+
+    #[test]
+    fn jump_keys_move_selection() {
+        let toml: String = (0..10)
+            .map(|i| format!("['foo {i}']\nlocation = '{i}'\n"))
+            .collect();
+        let store = store::tests::parse_store(&toml).unwrap();
+        let mut settings = settings::Settings::default();
+        settings.ui.max_results = 10;
+        settings.ui.visible_results = 3;
+        let mut jolly = Jolly::with_store(store, settings);
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        let results = jolly.search_results.entries().to_vec();
+        assert_eq!(results.len(), 10);
+
+        // the search box captures these keys, so only the navigation
+        // filter sees them
+        let mut press = |key_code, modifiers| {
+            let event = event::Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            });
+            if let Some(message) = navigation_event(event, event::Status::Captured) {
+                let _ = jolly.update(message);
+            }
+            let selected = jolly.search_results.selected().unwrap();
+            results.iter().position(|&id| id == selected).unwrap()
+        };
+        let ctrl = keyboard::Modifiers::CTRL;
+        let none = keyboard::Modifiers::empty();
+
+        assert_eq!(press(keyboard::KeyCode::PageDown, none), 3);
+        assert_eq!(press(keyboard::KeyCode::PageDown, none), 6);
+        assert_eq!(press(keyboard::KeyCode::PageDown, none), 9);
+        assert_eq!(press(keyboard::KeyCode::PageDown, none), 9);
+        assert_eq!(press(keyboard::KeyCode::PageUp, none), 6);
+
+        // home and end only jump with ctrl, otherwise they are left
+        // to move the cursor in the search box
+        assert_eq!(press(keyboard::KeyCode::Home, none), 6);
+        assert_eq!(press(keyboard::KeyCode::Home, ctrl), 0);
+        assert_eq!(press(keyboard::KeyCode::End, none), 0);
+        assert_eq!(press(keyboard::KeyCode::End, ctrl), 9);
+
+        // with no results, nothing moves
+        let _ = jolly.update(Message::SearchTextChanged("nothing".into()));
+        let _ = jolly.update(Message::NavigationKey(keyboard::KeyCode::End));
+        assert_eq!(jolly.search_results.selected(), None);
+    }
+
+    #[test]
+    fn preview_follows_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+        let toml = format!(
+            "['notes']\nlocation = '{}'\n['nowhere']\nurl = 'https://example.com'",
+            file.display()
+        );
+        let store = store::tests::parse_store(&toml).unwrap();
+
+        // previews are off by default
+        let mut jolly = Jolly::with_store(store.clone(), Default::default());
+        let cmd = jolly.update(Message::SearchTextChanged("notes".into()));
+        assert!(action_names(cmd).is_empty());
+        assert!(jolly.preview.is_none());
+
+        let mut settings = settings::Settings::default();
+        settings.ui.preview = true;
+        let mut jolly = Jolly::with_store(store, settings);
+
+        let cmd = jolly.update(Message::SearchTextChanged("notes".into()));
+        assert_eq!(action_names(cmd), ["Action::Future"]);
+        assert!(matches!(jolly.preview, Some((0, None))));
+
+        // the same selection does not load again
+        let cmd = jolly.update(Message::SearchTextChanged("note".into()));
+        assert!(action_names(cmd).is_empty());
+
+        let loaded = |id| Message::PreviewLoaded(id, preview::Preview::Text("hello".into()));
+        let _ = jolly.update(loaded(0));
+        assert!(matches!(
+            &jolly.preview,
+            Some((0, Some(preview::Preview::Text(t)))) if t == "hello"
+        ));
+
+        // urls have no preview, and late previews are ignored
+        let cmd = jolly.update(Message::SearchTextChanged("nowhere".into()));
+        assert!(action_names(cmd).is_empty());
+        let _ = jolly.update(loaded(0));
+        assert!(jolly.preview.is_none());
+    }
+
+    #[test]
+    fn query_change_resets_selection() {
+        let store = store::tests::parse_store(
+            r#"['foo1']
+               location = 'a'
+               ['foo2']
+               location = 'b'
+               ['foo3']
+               location = 'c'"#,
+        )
+        .unwrap();
+
+        let mut jolly = Jolly::with_store(store, Default::default());
+
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+        let top = jolly.search_results.entries()[0];
+        assert_eq!(jolly.search_results.selected(), Some(top));
+
+        let _ = jolly.update(press(keyboard::KeyCode::Down));
+        let _ = jolly.update(press(keyboard::KeyCode::Down));
+        assert_ne!(jolly.search_results.selected(), Some(top));
+
+        // deleting characters keeps the same results, but the selection
+        // still snaps back to the best match
+        let _ = jolly.update(Message::SearchTextChanged("fo".into()));
+        assert_eq!(jolly.search_results.selected(), Some(top));
+
+        let _ = jolly.update(press(keyboard::KeyCode::Down));
+        let _ = jolly.update(Message::SearchTextChanged("foo3".into()));
+        assert_eq!(
+            jolly.search_results.selected(),
+            Some(jolly.search_results.entries()[0])
+        );
+    }
+
+    #[test]
+    fn copy_prefix_and_suffix() {
+        let toml = r#"['file']
+                      location = '/path/to/file'
+                      ['url']
+                      url = 'https://example.com'"#;
+
+        let mut settings = settings::Settings::default();
+        settings.copy_prefix = "<".into();
+        settings.copy_suffix = ">".into();
+
+        for (id, expected) in [(0, "</path/to/file>"), (1, "<https://example.com>")] {
+            let store = store::tests::parse_store(toml).unwrap();
+            let mut jolly = Jolly::with_store(store, settings.clone());
+            jolly.modifiers = keyboard::Modifiers::COMMAND;
+
+            let actions = action_names(jolly.handle_selection(id));
+            assert!(actions.contains(&"Action::Clipboard(Action::Write)".to_string()));
+
+            match &jolly.store_state {
+                StoreLoadedState::Finished(error::Error::FinalMessage(msg)) => {
+                    assert_eq!(msg, &format!("copied to clipboard: {expected}"))
+                }
+                state => panic!("unexpected state {state:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn selection_hides_before_opening() {
+        let store = store::tests::parse_store("['foo']\nurl = 'javascript:foo'").unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+
+        // the window is hidden first, and the entry is opened in the background
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(
+            actions,
+            ["Action::Window(Action::SetMode(Hidden))", "Action::Future"]
+        );
+
+        // losing focus while hidden does not close the window early
+        assert!(jolly.opening);
+        let unfocus = Message::ExternalEvent(event::Event::Window(window::Event::Unfocused));
+        jolly.focused_once = true;
+        assert!(action_names(jolly.update(unfocus)).is_empty());
+
+        // once opened, the window closes
+        let actions = action_names(jolly.update(Message::SelectionOpened(Ok(()))));
+        assert_eq!(actions, ["Action::Window(Action::Close)"]);
+
+        // errors bring the window back to show them
+        let err = entry::Error::DisallowedScheme("javascript".into());
+        let msg = Message::SelectionOpened(Err(std::sync::Arc::new(err)));
+        let actions = action_names(jolly.update(msg));
+        assert_eq!(actions, ["Action::Window(Action::SetMode(Windowed))"]);
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::Finished(error::Error::StoreError(entry::Error::DisallowedScheme(_)))
+        ));
+    }
+
+    #[test]
+    fn awaited_command_feedback() {
+        let text = "['build']\nsystem = 'make'\nawait = true";
+        let finished = |status: platform::CommandStatus| {
+            let store = store::tests::parse_store(text).unwrap();
+            let mut jolly = Jolly::with_store(store, Default::default());
+
+            // the window stays open while the command runs
+            let actions = action_names(jolly.handle_selection(0));
+            assert_eq!(actions, ["Action::Future"]);
+            assert!(jolly.awaiting);
+            assert_eq!(jolly.toast.as_deref(), Some("running build..."));
+
+            // selecting again does nothing until the command exits
+            assert!(action_names(jolly.handle_selection(0)).is_empty());
+            assert!(action_names(jolly.update(Message::EntrySelected(0))).is_empty());
+            assert!(action_names(jolly.handle_enter()).is_empty());
+            assert!(jolly.awaiting);
+
+            let _ = jolly.update(Message::CommandFinished("build".into(), Ok(status)));
+            assert!(!jolly.awaiting);
+            assert!(jolly.toast.is_none());
+            match jolly.store_state {
+                StoreLoadedState::Finished(e) => e,
+                _ => panic!("command did not finish"),
+            }
+        };
+
+        let success = platform::CommandStatus {
+            success: true,
+            code: Some(0),
+            stderr: String::new(),
+        };
+        assert!(matches!(
+            finished(success),
+            error::Error::FinalMessage(msg) if msg == "build finished successfully"
+        ));
+
+        let failure = platform::CommandStatus {
+            success: false,
+            code: Some(2),
+            stderr: "no rule to make target\n".into(),
+        };
+        assert!(matches!(
+            finished(failure),
+            error::Error::CustomError(msg) if msg == "build failed: exit status 2: no rule to make target"
+        ));
+    }
+
+    #[test]
+    fn open_and_copy() {
+        let store = store::tests::parse_store("['foo']\nlocation = 'bar'").unwrap();
+        let mut settings = settings::Settings::default();
+        settings.open_copy_modifier = settings::OpenCopyModifier::Shift;
+        let mut jolly = Jolly::with_store(store, settings);
+
+        jolly.modifiers = keyboard::Modifiers::COMMAND | keyboard::Modifiers::SHIFT;
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(
+            actions,
+            [
+                "Action::Clipboard(Action::Write)",
+                "Action::Window(Action::SetMode(Hidden))",
+                "Action::Future"
+            ]
+        );
+        assert!(jolly.opening);
+
+        // the window closes once opened, without showing the copy message
+        let actions = action_names(jolly.update(Message::SelectionOpened(Ok(()))));
+        assert_eq!(actions, ["Action::Window(Action::Close)"]);
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::LoadSucceeded(_, _)
+        ));
+    }
+
+    #[test]
+    fn no_copy_entries_always_open() {
+        let store = store::tests::parse_store("['deploy']\nsystem = 'make deploy'\nno_copy = true")
+            .unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+
+        // the copy modifier is ignored, and the command is run
+        jolly.modifiers = keyboard::Modifiers::COMMAND;
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(
+            actions,
+            ["Action::Window(Action::SetMode(Hidden))", "Action::Future"]
+        );
+        assert!(jolly.opening);
+        assert!(jolly.toast.is_none());
+    }
+
+    #[test]
+    fn copy_can_be_disabled() {
+        let store = store::tests::parse_store("['jolly']\nurl = 'https://example.com'").unwrap();
+        let mut settings = settings::Settings::default();
+        settings.allow_copy = false;
+        settings.open_copy_modifier = settings::OpenCopyModifier::Shift;
+        let mut jolly = Jolly::with_store(store, settings);
+
+        // neither copy modifier does anything, the entry is opened
+        for modifiers in [
+            keyboard::Modifiers::COMMAND,
+            keyboard::Modifiers::COMMAND | keyboard::Modifiers::SHIFT,
+        ] {
+            jolly.modifiers = modifiers;
+            assert_eq!(jolly.action(), entry::Action::Open);
+        }
+
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(
+            actions,
+            ["Action::Window(Action::SetMode(Hidden))", "Action::Future"]
+        );
+        assert!(jolly.toast.is_none());
+
+        // names cannot be copied either
+        assert!(action_names(jolly.copy_name(0)).is_empty());
+    }
+
+    #[test]
+    fn copy_name_shortcut() {
+        let store = store::tests::parse_store(
+            "['weather in %s']\nurl = 'https://example.com/?q=%s'\nkeyword = 'w'",
+        )
+        .unwrap();
+        let mut settings = settings::Settings::default();
+        settings.copy_feedback = settings::CopyFeedback::Toast;
+        let mut jolly = Jolly::with_store(store, settings);
+
+        // nothing is copied without a selection
+        assert!(action_names(jolly.update(Message::CopyName)).is_empty());
+
+        let _ = jolly.update(Message::SearchTextChanged("w paris".into()));
+        let _ = jolly.update(press(keyboard::KeyCode::Down));
+        let actions = action_names(jolly.update(Message::CopyName));
+        assert_eq!(actions, ["Action::Clipboard(Action::Write)"]);
+        assert_eq!(
+            jolly.toast.as_deref(),
+            Some("copied to clipboard: weather in paris")
+        );
+        assert!(matches!(
+            timer::tests::queued(&jolly.timers)[..],
+            [(_, Message::ToastExpired(_))]
+        ));
+    }
+
+    #[test]
+    fn empty_keyword_modes() {
+        let store = store::tests::parse_store(
+            "['weather in %s']\nurl = 'https://example.com/?q=%s'\nkeyword = 'w'",
+        )
+        .unwrap();
+        let jolly_with = |mode| {
+            let mut settings = settings::Settings::default();
+            settings.empty_keyword = mode;
+            Jolly::with_store(store.clone(), settings)
+        };
+        let opened = ["Action::Window(Action::SetMode(Hidden))", "Action::Future"];
+
+        // by default, the entry is opened without a parameter
+        let mut jolly = jolly_with(settings::EmptyKeyword::Run);
+        let _ = jolly.update(Message::SearchTextChanged("weather".into()));
+        assert_eq!(action_names(jolly.handle_selection(0)), opened);
+
+        let mut jolly = jolly_with(settings::EmptyKeyword::Ignore);
+        let _ = jolly.update(Message::SearchTextChanged("weather".into()));
+        assert!(action_names(jolly.handle_selection(0)).is_empty());
+        assert!(!jolly.opening);
+        let _ = jolly.update(Message::SearchTextChanged("w ".into()));
+        assert!(action_names(jolly.handle_selection(0)).is_empty());
+
+        let mut jolly = jolly_with(settings::EmptyKeyword::Prefill);
+        let _ = jolly.update(Message::SearchTextChanged("weather".into()));
+        assert_eq!(action_names(jolly.handle_selection(0)), ["Action::Widget"]);
+        assert_eq!(jolly.searchtext, "w ");
+        assert_eq!(jolly.keyword_entry, Some(0));
+        assert!(!jolly.opening);
+
+        // once there is a parameter, every mode opens the entry
+        for mode in [
+            settings::EmptyKeyword::Ignore,
+            settings::EmptyKeyword::Prefill,
+        ] {
+            let mut jolly = jolly_with(mode);
+            let _ = jolly.update(Message::SearchTextChanged("w paris".into()));
+            assert_eq!(action_names(jolly.handle_selection(0)), opened);
+        }
+    }
+
+    #[test]
+    fn enter_cycles_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "").unwrap();
+        let toml = format!(
+            "['notes']\nlocation = '{}'\n['site']\nurl = 'https://example.com'",
+            file.display()
+        );
+        let store = store::tests::parse_store(&toml).unwrap();
+        let enter = || {
+            Message::ExternalEvent(event::Event::Keyboard(keyboard::Event::CharacterReceived(
+                '\r',
+            )))
+        };
+        let opened = ["Action::Window(Action::SetMode(Hidden))", "Action::Future"];
+
+        let mut settings = settings::Settings::default();
+        settings.ui.enter_cycles_actions = true;
+        let mut jolly = Jolly::with_store(store, settings);
+        let _ = jolly.update(Message::SearchTextChanged("notes".into()));
+        let store = match &jolly.store_state {
+            StoreLoadedState::LoadSucceeded(s, _) => s.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            store.get(0).actions(true),
+            [
+                entry::Action::Open,
+                entry::Action::Reveal,
+                entry::Action::Copy
+            ]
+        );
+        assert_eq!(
+            store.get(1).actions(true),
+            [entry::Action::Open, entry::Action::Copy]
+        );
+        assert_eq!(store.get(1).actions(false), [entry::Action::Open]);
+
+        // the first press waits for another one
+        assert!(action_names(jolly.update(enter())).is_empty());
+        assert!(matches!(
+            timer::tests::queued(&jolly.timers)[..],
+            [(delay, Message::ActionCycleExpired(1))] if delay <= ACTION_CYCLE_INTERVAL
+        ));
+        assert!(!jolly.opening);
+        assert_eq!(jolly.shown_action(&store), entry::Action::Open);
+
+        // which chooses the next action
+        let _ = jolly.update(enter());
+        assert_eq!(jolly.shown_action(&store), entry::Action::Reveal);
+
+        // only the last press takes the action
+        assert!(action_names(jolly.update(Message::ActionCycleExpired(1))).is_empty());
+        assert_eq!(
+            action_names(jolly.update(Message::ActionCycleExpired(2))),
+            opened
+        );
+        assert!(jolly.opening);
+        assert_eq!(jolly.shown_action(&store), entry::Action::Open);
+
+        // typing cancels the chosen action
+        jolly.opening = false;
+        let _ = jolly.update(enter());
+        let _ = jolly.update(Message::SearchTextChanged("note".into()));
+        assert!(action_names(jolly.update(Message::ActionCycleExpired(3))).is_empty());
+        assert!(!jolly.opening);
+
+        // and so does moving the selection, with the keyboard or mouse
+        let _ = jolly.update(Message::SearchTextChanged("e".into()));
+        assert_eq!(jolly.search_results.entries().len(), 2);
+        let _ = jolly.update(enter());
+        let _ = jolly.update(press(keyboard::KeyCode::Down));
+        assert_eq!(jolly.action_cycle.pending(), None);
+        assert!(action_names(jolly.update(Message::ActionCycleExpired(4))).is_empty());
+        let _ = jolly.update(enter());
+        let _ = jolly.update(Message::EntryHovered(0));
+        assert_eq!(jolly.action_cycle.pending(), None);
+        assert!(action_names(jolly.update(Message::ActionCycleExpired(5))).is_empty());
+        assert!(!jolly.opening);
+
+        // entries with a single action are selected right away
+        let mut settings = settings::Settings::default();
+        settings.ui.enter_cycles_actions = true;
+        settings.allow_copy = false;
+        let mut jolly = Jolly::with_store(store, settings);
+        let _ = jolly.update(Message::SearchTextChanged("site".into()));
+        assert_eq!(action_names(jolly.update(enter())), opened);
+    }
+
+    #[test]
+    fn runs_are_opened_together() {
+        let store = store::tests::parse_store(
+            "['a']\nlocation = 'x'\n['b']\nlocation = 'y'\n['both']\nruns = ['a', 'b']",
+        )
+        .unwrap();
+        let mut jolly = Jolly::with_store(store, settings::Settings::default());
+
+        // the copy modifier is ignored, and all entries are opened at once
+        jolly.modifiers = keyboard::Modifiers::COMMAND;
+        let actions = action_names(jolly.handle_selection(2));
+        assert_eq!(
+            actions,
+            ["Action::Window(Action::SetMode(Hidden))", "Action::Future"]
+        );
+        assert!(jolly.opening);
+    }
+
+    #[test]
+    fn copy_feedback_modes() {
+        let store = store::tests::parse_store("['foo']\ncopy = 'secret'").unwrap();
+        let mut settings = settings::Settings::default();
+
+        // the default replaces the ui with a message
+        let mut jolly = Jolly::with_store(store.clone(), settings.clone());
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(actions, ["Action::Clipboard(Action::Write)"]);
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::Finished(error::Error::FinalMessage(_))
+        ));
+        assert!(jolly.toast.is_none());
+
+        // toasts keep the search open until they expire
+        settings.copy_feedback = settings::CopyFeedback::Toast;
+        let mut jolly = Jolly::with_store(store.clone(), settings.clone());
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(actions, ["Action::Clipboard(Action::Write)"]);
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::LoadSucceeded(_, _)
+        ));
+        let toast = jolly.toast.clone().unwrap();
+        assert_eq!(toast, "copied to clipboard: secret");
+
+        // the toast expires once its timer is delivered
+        match &timer::tests::queued(&jolly.timers)[..] {
+            [(delay, Message::ToastExpired(msg))] => {
+                assert!(*delay <= TOAST_DURATION);
+                assert_eq!(msg, &toast);
+            }
+            other => panic!("unexpected timers {other:?}"),
+        }
+
+        let _ = jolly.update(Message::ToastExpired("older toast".into()));
+        assert!(jolly.toast.is_some());
+        let _ = jolly.update(Message::ToastExpired(toast));
+        assert!(jolly.toast.is_none());
+
+        // silent copies close right away
+        settings.copy_feedback = settings::CopyFeedback::None;
+        let mut jolly = Jolly::with_store(store, settings);
+        let actions = action_names(jolly.handle_selection(0));
+        assert_eq!(
+            actions,
+            [
+                "Action::Clipboard(Action::Write)",
+                "Action::Window(Action::Close)"
+            ]
+        );
+        assert!(matches!(
+            jolly.store_state,
+            StoreLoadedState::LoadSucceeded(_, _)
+        ));
+        assert!(jolly.toast.is_none());
+    }
+
+    #[test]
+    fn copy_entries_are_copied() {
+        let store = store::tests::parse_store("['foo']\ncopy = 'secret'").unwrap();
+        let mut jolly = Jolly::with_store(store, Default::default());
+
+        let _ = jolly.update(Message::SearchTextChanged("foo".into()));
+
+        // no modifiers are held, but enter still copies
+        let enter = keyboard::Event::KeyReleased {
+            key_code: keyboard::KeyCode::Enter,
+            modifiers: keyboard::Modifiers::empty(),
+        };
+        let actions =
+            action_names(jolly.update(Message::ExternalEvent(event::Event::Keyboard(enter))));
+        assert_eq!(actions, ["Action::Clipboard(Action::Write)"]);
+
+        match &jolly.store_state {
+            StoreLoadedState::Finished(error::Error::FinalMessage(msg)) => {
+                assert_eq!(msg, "copied to clipboard: secret")
+            }
+            state => panic!("unexpected state {state:?}"),
+        }
+    }
+
+    #[test]
+    fn warnings_propagate() {
+        let config = config::Config {
+            settings: Default::default(),
+            store: Ok(Default::default()),
+            warnings: vec!["foo".into()],
+            path: None,
+        };
+
+        let (mut jolly, _) = Jolly::new(config);
+        assert_eq!(jolly.warnings, vec!["foo".to_string()]);
+
+        let _ = jolly.update(Message::DismissWarnings);
+        assert!(jolly.warnings.is_empty());
+    }
+
+    #[test]
+    fn focus_delay_startup() {
+        let mut settings = settings::Settings::default();
+
+        // by default focus is stolen immediately
+        let mut timers = timer::Timers::default();
+        let actions = action_names(startup_command(&settings, &mut timers));
+        assert_eq!(actions.len(), 3);
+        assert!(timer::tests::queued(&timers).is_empty());
+
+        // a delay sets a timer to steal focus instead
+        settings.ui.focus_delay_ms = 100;
+        let mut timers = timer::Timers::default();
+        let actions = action_names(startup_command(&settings, &mut timers));
+        assert_eq!(actions.len(), 2);
+        match &timer::tests::queued(&timers)[..] {
+            [(delay, Message::StealFocus)] => {
+                assert!(*delay <= std::time::Duration::from_millis(100))
+            }
+            other => panic!("unexpected timers {other:?}"),
+        }
+
+        // a negative delay skips the focus steal completely
+        settings.ui.focus_delay_ms = -1;
+        let mut timers = timer::Timers::default();
+        let actions = action_names(startup_command(&settings, &mut timers));
+        assert_eq!(actions.len(), 2);
+        assert!(timer::tests::queued(&timers).is_empty());
     }
 }
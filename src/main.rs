@@ -1,15 +1,31 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use iced::{Application, Settings};
-use jolly::{cli, config, Jolly};
+use jolly::{bench, cli, config, error, explain, export, Jolly};
 use std::process::ExitCode;
 use std::time::Instant;
 
 pub fn main() -> ExitCode {
-    let custom_config = match cli::parse_args(std::env::args()) {
-        Ok(c) => c.config,
+    let args = match cli::parse_args(std::env::args()) {
+        Ok(a) => a,
         Err(e) => return e,
     };
+    let custom_config = args.config;
+
+    // the theme only needs the settings, so it can be dumped even
+    // when the entries have errors
+    if args.dump_theme {
+        return match config::load_settings(custom_config.as_deref()) {
+            Ok(settings) => {
+                println!("{}", settings.ui.theme.to_config());
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
 
     let now = Instant::now();
 
@@ -21,12 +37,62 @@ pub fn main() -> ExitCode {
 
     let elapsed = now.elapsed();
 
+    if let Some(cli::ExportFormat::Json) = args.export {
+        return match &config.store {
+            Ok(store) => {
+                let fields = args.fields.as_deref().unwrap_or(&export::ALL_FIELDS);
+                println!("{}", export::to_json(store, fields));
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some((query, name)) = &args.explain {
+        let report = config
+            .store
+            .as_ref()
+            .map_err(|e| e.to_string())
+            .and_then(|store| explain::explain(store, &config.settings, query, name));
+        return match report {
+            Ok(report) => {
+                println!("{report}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if args.bench_icons {
+        return match &config.store {
+            Ok(store) => {
+                let timings = bench::icons(store, &config.settings);
+                println!("{}", bench::report(&timings));
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
     // if we could not initialize the logger, we set the store to
     // error, so the ui shows the issue
     if let Err(e) = config.settings.log.init_logger() {
         config.store = Err(e);
     }
 
+    if let Some(path) = &config.path {
+        ::log::info!("Using config file {}", path.display());
+    }
+
     if let Ok(s) = &config.store {
         ::log::debug!(
             "Loaded {} entries in {:.6} sec",
@@ -38,14 +104,23 @@ pub fn main() -> ExitCode {
     let mut settings = Settings::default();
     settings.window.size = (
         config.settings.ui.width,
-        config.settings.ui.search.starting_height(),
+        config.settings.ui.starting_height(),
     );
+    settings.window.position = config.settings.ui.window_position(settings.window.size);
     settings.window.decorations = false;
+    settings.window.level = config.settings.ui.window_level();
     settings.window.visible = false;
     settings.default_text_size = config.settings.ui.common.text_size().into();
+
+    // decided before the config is handed to the window
+    let exit_code = config.exit_code();
     settings.flags = config;
 
-    Jolly::run(settings)
-        .map(|_| ExitCode::SUCCESS)
-        .unwrap_or(ExitCode::FAILURE)
+    match Jolly::run(settings) {
+        Ok(_) => exit_code,
+        Err(e) => {
+            eprintln!("{}", error::Error::IcedError(e));
+            ExitCode::FAILURE
+        }
+    }
 }
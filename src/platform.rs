@@ -5,11 +5,17 @@ use std::ffi::OsStr;
 use std::fmt;
 use std::io;
 use std::path::Path;
+use std::process::{Child, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+// how often we check on a spawned command while waiting for it to start
+const SPAWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Debug)]
 pub enum Error {
     OpenerError(opener::OpenError),
     IoError(io::Error),
+    StartError(ExitStatus),
 }
 
 impl fmt::Display for Error {
@@ -23,6 +29,7 @@ impl fmt::Display for Error {
                 }
             }
             Error::IoError(err) => err.fmt(f),
+            Error::StartError(status) => write!(f, "command failed to start: {status}"),
         }
     }
 }
@@ -40,28 +47,104 @@ const DEFAULT_ACCENT_COLOR: ui::Color = ui::Color(csscolorparser::Color {
 #[cfg(unix)]
 pub(crate) mod os {
     use crate::ui;
-    use std::ffi::OsStr;
+    use std::ffi::{OsStr, OsString};
+    use std::path::Path;
     use std::process::Command;
 
     pub const SHELL: [&str; 2] = ["sh", "-c"];
     pub const ACCENT_COLOR: &'static ui::Color = &super::DEFAULT_ACCENT_COLOR;
 
-    // run a subshell and interpret results
-    pub fn system(cmdstr: impl AsRef<OsStr>) -> std::io::Result<std::process::Child> {
-        Command::new(SHELL[0]).args(&SHELL[1..]).arg(cmdstr).spawn()
+    // show a directory in Finder
+    #[cfg(target_os = "macos")]
+    pub fn file_manager_command(path: &Path) -> Vec<OsString> {
+        vec!["open".into(), "-a".into(), "Finder".into(), path.into()]
+    }
+
+    // show a file selected in its folder in Finder
+    #[cfg(target_os = "macos")]
+    pub fn reveal_command(path: &Path) -> Vec<OsString> {
+        vec!["open".into(), "-R".into(), path.into()]
+    }
+
+    // ask the freedesktop file manager service to show a directory
+    #[cfg(not(target_os = "macos"))]
+    pub fn file_manager_command(path: &Path) -> Vec<OsString> {
+        let uri = url::Url::from_directory_path(path)
+            .map(String::from)
+            .unwrap_or_else(|_| path.display().to_string());
+        [
+            "dbus-send",
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowFolders",
+            &format!("array:string:{uri}"),
+            "string:",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect()
+    }
+
+    // ask the freedesktop file manager service to show a file
+    // selected in its folder
+    #[cfg(not(target_os = "macos"))]
+    pub fn reveal_command(path: &Path) -> Vec<OsString> {
+        let uri = url::Url::from_file_path(path)
+            .map(String::from)
+            .unwrap_or_else(|_| path.display().to_string());
+        [
+            "dbus-send",
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            &format!("array:string:{uri}"),
+            "string:",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect()
+    }
+
+    // build a subshell that runs the command
+    pub fn command<I, K, V>(cmdstr: impl AsRef<OsStr>, env: I) -> Command
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        let mut command = Command::new(SHELL[0]);
+        command.args(&SHELL[1..]).arg(cmdstr).envs(env);
+        command
     }
 }
 
 #[cfg(windows)]
 pub(crate) mod os {
     use crate::ui;
-    use std::ffi::OsStr;
+    use std::ffi::{OsStr, OsString};
     use std::os::windows::process::CommandExt;
+    use std::path::Path;
     use std::process::Command;
     use windows::UI::ViewManagement::{UIColorType, UISettings};
 
     pub const SHELL: [&str; 2] = ["cmd.exe", "/c"];
 
+    // show a directory in explorer
+    pub fn file_manager_command(path: &Path) -> Vec<OsString> {
+        vec!["explorer.exe".into(), path.into()]
+    }
+
+    // show a file selected in its folder in explorer
+    pub fn reveal_command(path: &Path) -> Vec<OsString> {
+        let mut select = OsString::from("/select,");
+        select.push(path);
+        vec!["explorer.exe".into(), select]
+    }
+
     // try and get the windows accent color. This wont work for
     // windows < 10
     fn try_get_color() -> Option<ui::Color> {
@@ -83,20 +166,128 @@ pub(crate) mod os {
     };
     }
 
-    // run a subshell and interpret results
-    pub fn system(cmdstr: impl AsRef<OsStr>) -> std::io::Result<std::process::Child> {
-        Command::new(SHELL[0])
+    // build a subshell that runs the command
+    pub fn command<I, K, V>(cmdstr: impl AsRef<OsStr>, env: I) -> Command
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        let mut command = Command::new(SHELL[0]);
+        command
             //spawn the command window without a console (CREATE_NO_WINDOW)
             // see https://learn.microsoft.com/en-us/windows/win32/procthread/process-creation-flags
             .creation_flags(0x08000000)
             .args(&SHELL[1..])
             .arg(cmdstr)
-            .spawn()
+            .envs(env);
+        command
+    }
+}
+
+// run a system command, with extra environment variables merged
+// into the inherited environment. If a timeout is provided, the
+// command is given that long to fail before we assume it started
+// successfully
+pub fn system<I, K, V>(
+    cmdstr: impl AsRef<OsStr>,
+    env: I,
+    timeout: Option<Duration>,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let child = os::command(cmdstr, env).spawn().map_err(Error::IoError)?;
+    match timeout {
+        Some(timeout) => wait_for_start(child, timeout),
+        None => Ok(()),
+    }
+}
+
+// how a command that was waited on finished
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandStatus {
+    pub success: bool,
+    // exit code of the command, if it was not killed by a signal
+    pub code: Option<i32>,
+    // anything the command printed to stderr
+    pub stderr: String,
+}
+
+impl fmt::Display for CommandStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "exit status {code}")?,
+            None => f.write_str("terminated by a signal")?,
+        }
+        let stderr = self.stderr.trim();
+        if !stderr.is_empty() {
+            write!(f, ": {stderr}")?;
+        }
+        Ok(())
+    }
+}
+
+// run a system command like system, but wait for it to exit and
+// collect its stderr
+pub fn system_await<I, K, V>(cmdstr: impl AsRef<OsStr>, env: I) -> Result<CommandStatus, Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let output = os::command(cmdstr, env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(Error::IoError)?;
+
+    Ok(CommandStatus {
+        success: output.status.success(),
+        code: output.status.code(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+// wait up to timeout for a spawned command to exit. A command that
+// exits unsuccessfully in that window is reported as failing to
+// start, a command that is still running is assumed to be fine.
+fn wait_for_start(mut child: Child, timeout: Duration) -> Result<(), Error> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(Error::IoError)? {
+            return if status.success() {
+                Ok(())
+            } else {
+                Err(Error::StartError(status))
+            };
+        }
+
+        if start.elapsed() >= timeout {
+            return Ok(());
+        }
+        std::thread::sleep(SPAWN_POLL_INTERVAL);
     }
 }
 
-pub fn system(cmdstr: impl AsRef<OsStr>) -> Result<(), Error> {
-    os::system(cmdstr).map(|_| ()).map_err(Error::IoError)
+// name of this machine, used to pick host specific config sections
+pub fn hostname() -> Option<String> {
+    #[cfg(windows)]
+    let name = std::env::var("COMPUTERNAME").ok();
+
+    #[cfg(unix)]
+    let name = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            String::from_utf8(output.stdout).ok()
+        });
+
+    name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
 }
 
 pub fn accent_color() -> ui::Color {
@@ -106,3 +297,653 @@ pub fn accent_color() -> ui::Color {
 pub fn open_file<P: AsRef<Path>>(path: P) -> Result<(), Error> {
     opener::open(path.as_ref().as_os_str()).map_err(Error::OpenerError)
 }
+
+// open a directory in the file manager, even if another program,
+// such as an editor, is the default handler for directories. If the
+// file manager cannot be started, the directory is opened normally
+pub fn open_dir<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    if !path.is_dir() {
+        return Err(Error::IoError(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not a directory", path.display()),
+        )));
+    }
+
+    let cmd = os::file_manager_command(path);
+    ::log::debug!("Opening directory with {cmd:?}");
+    match std::process::Command::new(&cmd[0]).args(&cmd[1..]).status() {
+        // explorer exits with an error even when it opens the directory
+        Ok(status) if status.success() || cfg!(windows) => Ok(()),
+        result => {
+            ::log::warn!("Could not open file manager ({result:?}), opening directory normally");
+            open_file(path)
+        }
+    }
+}
+
+// show a file or directory selected in the folder that contains
+// it. If the file manager cannot be started, the folder is opened
+// normally
+pub fn reveal<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(Error::IoError(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", path.display()),
+        )));
+    }
+
+    let cmd = os::reveal_command(path);
+    ::log::debug!("Revealing file with {cmd:?}");
+    match std::process::Command::new(&cmd[0]).args(&cmd[1..]).status() {
+        // explorer exits with an error even when it shows the file
+        Ok(status) if status.success() || cfg!(windows) => Ok(()),
+        result => {
+            ::log::warn!("Could not open file manager ({result:?}), opening folder normally");
+            open_file(path.parent().unwrap_or(path))
+        }
+    }
+}
+
+// open a url using the desktop file that is registered as the
+// handler for its scheme, so that the program that is launched
+// matches the icon that is shown for the entry. If no handler can be
+// found, the url is opened normally
+pub fn open_url_with_handler(url: &url::Url) -> Result<(), Error> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Some(cmd) = desktop::handler_command(url) {
+        ::log::debug!("Opening {url} with scheme handler {cmd:?}");
+        return std::process::Command::new(&cmd[0])
+            .args(&cmd[1..])
+            .spawn()
+            .map(|_| ())
+            .map_err(Error::IoError);
+    }
+
+    open_file(url.as_str())
+}
+
+// the area of a monitor, in screen coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        let (x, y) = (i64::from(x), i64::from(y));
+        let (left, top) = (i64::from(self.x), i64::from(self.y));
+        x >= left
+            && y >= top
+            && x < left + i64::from(self.width)
+            && y < top + i64::from(self.height)
+    }
+}
+
+// find the top left corner of a window of the given size, so that
+// it is centered on the monitor that contains point. Returns None if
+// no monitor contains the point
+pub fn center_on_monitor(
+    monitors: &[Rect],
+    point: (i32, i32),
+    (width, height): (u32, u32),
+) -> Option<(i32, i32)> {
+    let monitor = monitors.iter().find(|m| m.contains(point))?;
+    let offset = |outer: u32, inner: u32| (i64::from(outer) - i64::from(inner)) / 2;
+    let x = i64::from(monitor.x) + offset(monitor.width, width);
+    let y = i64::from(monitor.y) + offset(monitor.height, height);
+    Some((x.try_into().ok()?, y.try_into().ok()?))
+}
+
+// position that centers a window of the given size on the monitor
+// under the mouse cursor, if the monitor geometry is available
+pub fn center_on_cursor(size: (u32, u32)) -> Option<(i32, i32)> {
+    let (monitors, cursor) = screen::geometry()?;
+    ::log::debug!("Cursor at {cursor:?} on monitors {monitors:?}");
+    center_on_monitor(&monitors, cursor, size)
+}
+
+// height of the monitor under the mouse cursor, or of the first
+// monitor if the cursor is not on any of them
+pub fn screen_height() -> Option<u32> {
+    let (monitors, cursor) = screen::geometry()?;
+    monitors
+        .iter()
+        .find(|m| m.contains(cursor))
+        .or_else(|| monitors.first())
+        .map(|m| m.height)
+}
+
+// querying the monitors and mouse cursor
+#[cfg(windows)]
+mod screen {
+    use super::Rect;
+    use windows::Win32::Foundation::{BOOL, LPARAM, POINT, RECT};
+    use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    unsafe extern "system" fn add_monitor(
+        _: HMONITOR,
+        _: HDC,
+        rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<Rect>);
+        let rect = &*rect;
+        monitors.push(Rect {
+            x: rect.left,
+            y: rect.top,
+            width: (rect.right - rect.left) as u32,
+            height: (rect.bottom - rect.top) as u32,
+        });
+        true.into()
+    }
+
+    pub fn geometry() -> Option<(Vec<Rect>, (i32, i32))> {
+        let mut monitors: Vec<Rect> = Vec::new();
+        let mut cursor = POINT::default();
+        unsafe {
+            EnumDisplayMonitors(
+                HDC::default(),
+                None,
+                Some(add_monitor),
+                LPARAM(&mut monitors as *mut _ as isize),
+            )
+            .ok()
+            .ok()?;
+            GetCursorPos(&mut cursor).ok().ok()?;
+        }
+        Some((monitors, (cursor.x, cursor.y)))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod screen {
+    use super::Rect;
+    use core_graphics::display::CGDisplay;
+    use core_graphics::event::CGEvent;
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    pub fn geometry() -> Option<(Vec<Rect>, (i32, i32))> {
+        let monitors = CGDisplay::active_displays()
+            .ok()?
+            .into_iter()
+            .map(|id| {
+                let bounds = CGDisplay::new(id).bounds();
+                Rect {
+                    x: bounds.origin.x as i32,
+                    y: bounds.origin.y as i32,
+                    width: bounds.size.width as u32,
+                    height: bounds.size.height as u32,
+                }
+            })
+            .collect();
+
+        let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState).ok()?;
+        let cursor = CGEvent::new(source).ok()?.location();
+        Some((monitors, (cursor.x as i32, cursor.y as i32)))
+    }
+}
+
+// monitors and the cursor are queried through Xlib, which is loaded
+// at runtime. Wayland has no way for a client to find the cursor, so
+// there this only works for as long as the cursor is over an
+// XWayland window, and without an X server it returns None
+#[cfg(all(unix, not(target_os = "macos")))]
+mod screen {
+    use super::Rect;
+    use std::os::raw::{c_int, c_uint, c_ulong};
+    use x11_dl::{xinerama, xlib};
+
+    pub fn geometry() -> Option<(Vec<Rect>, (i32, i32))> {
+        let xlib = xlib::Xlib::open().ok()?;
+        unsafe {
+            let display = (xlib.XOpenDisplay)(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+            let geometry = query(&xlib, display);
+            (xlib.XCloseDisplay)(display);
+            geometry
+        }
+    }
+
+    unsafe fn query(
+        xlib: &xlib::Xlib,
+        display: *mut xlib::Display,
+    ) -> Option<(Vec<Rect>, (i32, i32))> {
+        let root = (xlib.XDefaultRootWindow)(display);
+        let (mut root_return, mut child): (c_ulong, c_ulong) = (0, 0);
+        let (mut x, mut y, mut win_x, mut win_y): (c_int, c_int, c_int, c_int) = (0, 0, 0, 0);
+        let mut mask: c_uint = 0;
+        let on_screen = (xlib.XQueryPointer)(
+            display,
+            root,
+            &mut root_return,
+            &mut child,
+            &mut x,
+            &mut y,
+            &mut win_x,
+            &mut win_y,
+            &mut mask,
+        );
+        if on_screen == 0 {
+            return None;
+        }
+
+        let monitors = xinerama_monitors(xlib, display).unwrap_or_else(|| {
+            // without xinerama, the whole screen is one monitor
+            let screen = (xlib.XDefaultScreen)(display);
+            vec![Rect {
+                x: 0,
+                y: 0,
+                width: (xlib.XDisplayWidth)(display, screen) as u32,
+                height: (xlib.XDisplayHeight)(display, screen) as u32,
+            }]
+        });
+        Some((monitors, (x, y)))
+    }
+
+    unsafe fn xinerama_monitors(
+        xlib: &xlib::Xlib,
+        display: *mut xlib::Display,
+    ) -> Option<Vec<Rect>> {
+        let xinerama = xinerama::Xlib::open().ok()?;
+        if (xinerama.XineramaIsActive)(display) == 0 {
+            return None;
+        }
+        let mut count = 0;
+        let screens = (xinerama.XineramaQueryScreens)(display, &mut count);
+        if screens.is_null() {
+            return None;
+        }
+        let monitors = std::slice::from_raw_parts(screens, count as usize)
+            .iter()
+            .map(|s| Rect {
+                x: s.x_org.into(),
+                y: s.y_org.into(),
+                width: s.width as u32,
+                height: s.height as u32,
+            })
+            .collect();
+        (xlib.XFree)(screens.cast());
+        Some(monitors)
+    }
+}
+
+// lookup of freedesktop scheme handlers
+#[cfg(all(unix, not(target_os = "macos")))]
+mod desktop {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    // find the command line to run to open url with the default
+    // handler for its scheme
+    pub fn handler_command(url: &url::Url) -> Option<Vec<String>> {
+        let output = Command::new("xdg-settings")
+            .args(["get", "default-url-scheme-handler", url.scheme()])
+            .output()
+            .ok()?;
+        let handler = String::from_utf8(output.stdout).ok()?;
+        let handler = handler.trim();
+        if handler.is_empty() {
+            return None;
+        }
+
+        let desktop_file = data_dirs()
+            .into_iter()
+            .map(|d| d.join("applications").join(handler))
+            .find_map(|p| std::fs::read_to_string(p).ok())?;
+
+        exec_command(desktop_exec(&desktop_file)?, url.as_str())
+    }
+
+    // directories to search for desktop files, in order of preference
+    fn data_dirs() -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = match std::env::var_os("XDG_DATA_HOME") {
+            Some(d) if !d.is_empty() => vec![d.into()],
+            _ => dirs::home_dir()
+                .map(|h| h.join(".local/share"))
+                .into_iter()
+                .collect(),
+        };
+
+        match std::env::var("XDG_DATA_DIRS") {
+            Ok(d) if !d.is_empty() => dirs.extend(d.split(':').map(PathBuf::from)),
+            _ => dirs.extend(["/usr/local/share", "/usr/share"].map(PathBuf::from)),
+        }
+        dirs
+    }
+
+    // get the value of the Exec key from the main group of a desktop file
+    pub fn desktop_exec(contents: &str) -> Option<&str> {
+        let mut in_entry = false;
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+            } else if in_entry {
+                if let Some((key, value)) = line.split_once('=') {
+                    if key.trim() == "Exec" {
+                        return Some(value.trim());
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // split a desktop file Exec value into arguments, filling in the
+    // url for the file and url field codes. If there are no field
+    // codes for the url, it is passed as the last argument
+    pub fn exec_command(exec: &str, url: &str) -> Option<Vec<String>> {
+        let mut args = Vec::new();
+        let mut used_url = false;
+
+        for raw in split_exec(exec) {
+            let mut arg = String::new();
+            let mut chars = raw.chars();
+            let mut only_code = true;
+            let mut literal = false;
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    arg.push(c);
+                    only_code = false;
+                    literal = true;
+                    continue;
+                }
+                match chars.next() {
+                    Some('u' | 'U' | 'f' | 'F') => {
+                        arg.push_str(url);
+                        used_url = true;
+                        only_code = false;
+                    }
+                    Some('%') => {
+                        arg.push('%');
+                        only_code = false;
+                        literal = true;
+                    }
+                    // other field codes are deprecated or refer to
+                    // things jolly does not know, so we drop them
+                    _ => (),
+                }
+            }
+
+            // the program itself cannot come from a field code
+            if args.is_empty() && !only_code && !literal {
+                return None;
+            }
+            if !only_code {
+                args.push(arg);
+            }
+        }
+
+        if args.is_empty() {
+            return None;
+        }
+
+        if !used_url {
+            args.push(url.to_string());
+        }
+        Some(args)
+    }
+
+    // split on whitespace, respecting double quoted arguments
+    fn split_exec(exec: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut cur = None::<String>;
+        let mut quoted = false;
+        let mut chars = exec.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    quoted = !quoted;
+                    cur.get_or_insert_with(String::new);
+                }
+                '\\' if quoted => {
+                    if let Some(e) = chars.next() {
+                        cur.get_or_insert_with(String::new).push(e);
+                    }
+                }
+                c if c.is_whitespace() && !quoted => {
+                    if let Some(arg) = cur.take() {
+                        args.push(arg);
+                    }
+                }
+                c => cur.get_or_insert_with(String::new).push(c),
+            }
+        }
+
+        args.extend(cur);
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    const SLEEP_CMD: &str = "sleep 5";
+    #[cfg(windows)]
+    const SLEEP_CMD: &str = "ping -n 6 127.0.0.1 > nul";
+
+    #[cfg(unix)]
+    const CHECK_ENV_CMD: &str = r#"test "$JOLLY_TEST_ENV" = "bar""#;
+    #[cfg(windows)]
+    const CHECK_ENV_CMD: &str = r#"if "%JOLLY_TEST_ENV%"=="bar" (exit 0) else (exit 1)"#;
+
+    const NO_ENV: [(&str, &str); 0] = [];
+
+    #[test]
+    fn file_manager_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let cmd = os::file_manager_command(dir.path());
+
+        #[cfg(windows)]
+        assert_eq!(cmd, [OsStr::new("explorer.exe"), dir.path().as_os_str()]);
+        #[cfg(target_os = "macos")]
+        assert_eq!(
+            cmd,
+            [
+                OsStr::new("open"),
+                OsStr::new("-a"),
+                OsStr::new("Finder"),
+                dir.path().as_os_str()
+            ]
+        );
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            let uri = url::Url::from_directory_path(dir.path()).unwrap();
+            assert_eq!(cmd[0], "dbus-send");
+            assert!(cmd.contains(&"org.freedesktop.FileManager1.ShowFolders".into()));
+            assert!(cmd.contains(&format!("array:string:{uri}").into()));
+        }
+
+        // files are not opened as directories
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "").unwrap();
+        assert!(matches!(open_dir(&file), Err(Error::IoError(_))));
+        assert!(matches!(
+            open_dir(dir.path().join("missing")),
+            Err(Error::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn reveal_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "").unwrap();
+        let cmd = os::reveal_command(&file);
+
+        #[cfg(windows)]
+        {
+            use std::ffi::OsString;
+            let mut select = OsString::from("/select,");
+            select.push(&file);
+            assert_eq!(cmd, [OsString::from("explorer.exe"), select]);
+        }
+        #[cfg(target_os = "macos")]
+        assert_eq!(
+            cmd,
+            [OsStr::new("open"), OsStr::new("-R"), file.as_os_str()]
+        );
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            let uri = url::Url::from_file_path(&file).unwrap();
+            assert_eq!(cmd[0], "dbus-send");
+            assert!(cmd.contains(&"org.freedesktop.FileManager1.ShowItems".into()));
+            assert!(cmd.contains(&format!("array:string:{uri}").into()));
+        }
+
+        assert!(matches!(
+            reveal(dir.path().join("missing")),
+            Err(Error::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn monitor_under_point() {
+        let monitors = [
+            Rect {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            Rect {
+                x: 1920,
+                y: -200,
+                width: 1280,
+                height: 1024,
+            },
+            Rect {
+                x: -800,
+                y: 0,
+                width: 800,
+                height: 600,
+            },
+        ];
+        let size = (400, 100);
+
+        assert_eq!(
+            center_on_monitor(&monitors, (100, 100), size),
+            Some((760, 490))
+        );
+        assert_eq!(
+            center_on_monitor(&monitors, (2000, -100), size),
+            Some((2360, 262))
+        );
+        assert_eq!(
+            center_on_monitor(&monitors, (-1, 599), size),
+            Some((-600, 250))
+        );
+
+        // edges belong to the monitor on their right/bottom
+        assert_eq!(
+            center_on_monitor(&monitors, (1920, 0), size),
+            Some((2360, 262))
+        );
+
+        // points outside of every monitor have no position
+        assert_eq!(center_on_monitor(&monitors, (-1, 600), size), None);
+        assert_eq!(center_on_monitor(&monitors, (0, 1080), size), None);
+        assert_eq!(center_on_monitor(&[], (0, 0), size), None);
+
+        // windows larger than the monitor hang off the top left
+        assert_eq!(
+            center_on_monitor(&monitors, (-1, 0), (1000, 700)),
+            Some((-900, -50))
+        );
+    }
+
+    #[test]
+    fn spawn_timeout() {
+        let timeout = Some(Duration::from_millis(500));
+
+        // commands that exit immediately report their status
+        system("exit 0", NO_ENV, timeout).unwrap();
+        assert!(matches!(
+            system("exit 1", NO_ENV, timeout),
+            Err(Error::StartError(_))
+        ));
+
+        // commands that are still running at the timeout are assumed to have started
+        let start = Instant::now();
+        system(SLEEP_CMD, NO_ENV, timeout).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(500));
+
+        // without a timeout we dont wait at all
+        system("exit 1", NO_ENV, None).unwrap();
+    }
+
+    #[test]
+    fn awaited_status() {
+        let status = system_await("exit 0", NO_ENV).unwrap();
+        assert!(status.success);
+        assert_eq!(status.code, Some(0));
+
+        let status = system_await("echo oops 1>&2 && exit 3", NO_ENV).unwrap();
+        assert!(!status.success);
+        assert_eq!(status.code, Some(3));
+        assert_eq!(status.to_string(), "exit status 3: oops");
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn desktop_exec_lines() {
+        use desktop::{desktop_exec, exec_command};
+
+        let url = "https://example.com";
+        let tests: [(&str, &[&str]); 6] = [
+            ("firefox %u", &["firefox", url]),
+            ("firefox", &["firefox", url]),
+            (
+                r#""/opt/my app/bin" --new-window %U"#,
+                &["/opt/my app/bin", "--new-window", url],
+            ),
+            (
+                "app %i %c %k --url=%u",
+                &["app", "--url=https://example.com"],
+            ),
+            ("app 100%% %f", &["app", "100%", url]),
+            (
+                r#"sh -c "echo \"hi\" \\$HOME" %u"#,
+                &["sh", "-c", r#"echo "hi" \$HOME"#, url],
+            ),
+        ];
+
+        for (exec, expected) in tests {
+            assert_eq!(exec_command(exec, url).unwrap(), expected, "{exec}");
+        }
+        assert_eq!(exec_command("%u", url), None);
+
+        let desktop_file = "[Desktop Entry]\nName=Test\nExec=test %u\n\n[Desktop Action new]\nExec=test --new %u\n";
+        assert_eq!(desktop_exec(desktop_file), Some("test %u"));
+
+        let desktop_file = "[Desktop Action new]\nExec=test --new %u\n[Desktop Entry]\nName=Test\n";
+        assert_eq!(desktop_exec(desktop_file), None);
+    }
+
+    #[test]
+    fn env_is_applied() {
+        let status = os::command(CHECK_ENV_CMD, [("JOLLY_TEST_ENV", "bar")])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let status = os::command(CHECK_ENV_CMD, [("JOLLY_TEST_ENV", "baz")])
+            .status()
+            .unwrap();
+        assert!(!status.success());
+
+        // without the variable set, the check fails
+        let status = os::command(CHECK_ENV_CMD, NO_ENV).status().unwrap();
+        assert!(!status.success());
+    }
+}
@@ -0,0 +1,158 @@
+// contains logic for previewing the file of the selected entry.
+//
+// previews are loaded asynchronously, and only a bounded amount of
+// each file is read so that large files do not stall jolly
+
+use iced::widget::image;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+// how much of a text file is read for its preview
+const MAX_TEXT_BYTES: u64 = 4096;
+// how many lines of a text file are shown
+const MAX_TEXT_LINES: usize = 10;
+// images larger than this are not previewed
+const MAX_IMAGE_BYTES: u64 = 8 * 1024 * 1024;
+
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "bmp", "ico", "webp"];
+
+#[derive(Debug, Clone)]
+pub enum Preview {
+    Text(String),
+    Image(image::Handle),
+    // the file cannot be previewed, with the reason why
+    Unavailable(String),
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// read the preview of the file at path
+pub fn load(path: &Path) -> Preview {
+    match try_load(path) {
+        Ok(preview) => preview,
+        Err(e) => Preview::Unavailable(e.to_string()),
+    }
+}
+
+fn try_load(path: &Path) -> std::io::Result<Preview> {
+    if is_image(path) {
+        if fs::metadata(path)?.len() > MAX_IMAGE_BYTES {
+            return Ok(Preview::Unavailable("file too large".into()));
+        }
+        return Ok(Preview::Image(image::Handle::from_memory(fs::read(path)?)));
+    }
+
+    let mut bytes = Vec::new();
+    fs::File::open(path)?
+        .take(MAX_TEXT_BYTES + 1)
+        .read_to_end(&mut bytes)?;
+    Ok(text_preview(&bytes))
+}
+
+// build the preview for the start of a text file. bytes may contain
+// one byte more than is shown, to detect that the file was truncated
+fn text_preview(bytes: &[u8]) -> Preview {
+    let truncated = bytes.len() as u64 > MAX_TEXT_BYTES;
+    let bytes = &bytes[..bytes.len().min(MAX_TEXT_BYTES as usize)];
+
+    if bytes.contains(&0) {
+        return Preview::Unavailable("binary file".into());
+    }
+
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // a character was cut off at the end of the buffer
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()])
+            .expect("prefix before valid_up_to is utf8"),
+        Err(_) => return Preview::Unavailable("binary file".into()),
+    };
+
+    Preview::Text(truncate(text, MAX_TEXT_LINES, truncated))
+}
+
+// keep the first max_lines of text, marking if anything was removed
+fn truncate(text: &str, max_lines: usize, truncated: bool) -> String {
+    let mut lines = text.lines();
+    let mut preview = lines
+        .by_ref()
+        .take(max_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if truncated || lines.next().is_some() {
+        preview.push_str("\n…");
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(preview: Preview) -> String {
+        match preview {
+            Preview::Text(t) => t,
+            p => panic!("unexpected preview {p:?}"),
+        }
+    }
+
+    #[test]
+    fn truncate_lines() {
+        assert_eq!(truncate("a\nb\nc", 3, false), "a\nb\nc");
+        assert_eq!(truncate("a\nb\nc\n", 3, false), "a\nb\nc");
+        assert_eq!(truncate("a\nb\nc\nd", 3, false), "a\nb\nc\n…");
+        assert_eq!(truncate("a\r\nb", 1, false), "a\n…");
+        assert_eq!(truncate("a", 3, true), "a\n…");
+        assert_eq!(truncate("", 3, false), "");
+    }
+
+    #[test]
+    fn text_content() {
+        let lines: String = (0..20).map(|i| format!("line {i}\n")).collect();
+        let preview = text(text_preview(lines.as_bytes()));
+        assert_eq!(preview.lines().count(), MAX_TEXT_LINES + 1);
+        assert!(preview.starts_with("line 0\nline 1\n"));
+        assert!(preview.ends_with("line 9\n…"));
+
+        // one long line is cut at the byte limit, even in the middle
+        // of a character
+        let long = format!("a{}", "é".repeat(MAX_TEXT_BYTES as usize));
+        let preview = text(text_preview(
+            &long.as_bytes()[..MAX_TEXT_BYTES as usize + 1],
+        ));
+        assert_eq!(preview.chars().count(), MAX_TEXT_BYTES as usize / 2 + 2);
+        assert!(preview.ends_with("é\n…"));
+
+        assert!(matches!(text_preview(b"foo\0bar"), Preview::Unavailable(_)));
+        assert!(matches!(
+            text_preview(b"foo\xffbar"),
+            Preview::Unavailable(_)
+        ));
+    }
+
+    #[test]
+    fn load_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "hello\nworld").unwrap();
+        assert_eq!(text(load(&path)), "hello\nworld");
+
+        // images are only checked for size, decoding happens in the ui
+        let path = dir.path().join("picture.PNG");
+        fs::write(&path, "not really a png").unwrap();
+        assert!(matches!(load(&path), Preview::Image(_)));
+
+        let path = dir.path().join("data.bin");
+        fs::write(&path, [0u8, 1, 2, 3]).unwrap();
+        assert!(matches!(load(&path), Preview::Unavailable(_)));
+
+        let path = dir.path().join("missing.txt");
+        assert!(matches!(load(&path), Preview::Unavailable(_)));
+    }
+}
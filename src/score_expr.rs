@@ -0,0 +1,378 @@
+// contains a small expression language that lets users replace the
+// built in scoring of entries.
+//
+// an expression is made of numbers, named signals, the operators
+// + - * /, parentheses, and the functions min(...) and max(...). For
+// example:
+//
+// full_name * 20 + partial_tag * 2 + keyword * 100
+//
+// the value of the expression is the score of the entry. Entries
+// with a score of zero or less are not shown
+
+use serde::de::{Deserialize, Deserializer, Error as _};
+use std::fmt;
+
+// names of the signals that can be used in an expression. The first
+// MATCH_SIGNALS of them count how many tokens of the query matched
+// the entry in that way
+pub const SIGNALS: [&str; 13] = [
+    "full_name",
+    "partial_name",
+    "startswith_name",
+    "full_tag",
+    "partial_tag",
+    "startswith_tag",
+    "full_category",
+    "partial_category",
+    "startswith_category",
+    "keyword",
+    "priority",
+    "tokens",
+    "frecency",
+];
+
+pub const MATCH_SIGNALS: usize = 9;
+
+// indices into SIGNALS of the signals that follow the match signals
+const KEYWORD: usize = MATCH_SIGNALS;
+const PRIORITY: usize = MATCH_SIGNALS + 1;
+const TOKENS: usize = MATCH_SIGNALS + 2;
+const FRECENCY: usize = MATCH_SIGNALS + 3;
+
+// the values of the signals for one entry and query
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Signals {
+    // number of tokens with each kind of match, in the order of SIGNALS
+    pub matches: [u32; MATCH_SIGNALS],
+    // the first token is the entry's keyword
+    pub keyword: bool,
+    pub priority: u32,
+    // number of tokens in the query
+    pub tokens: usize,
+    // how often and how recently the entry was opened, between 0 and
+    // history::MAX_WEIGHT
+    pub frecency: u32,
+}
+
+impl Signals {
+    fn get(&self, signal: usize) -> f64 {
+        match signal {
+            i if i < MATCH_SIGNALS => self.matches[i] as f64,
+            KEYWORD => self.keyword as u32 as f64,
+            PRIORITY => self.priority as f64,
+            TOKENS => self.tokens as f64,
+            FRECENCY => self.frecency as f64,
+            _ => unreachable!("signals are checked when the expression is parsed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(f64),
+    // index into SIGNALS
+    Signal(usize),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, signals: &Signals) -> f64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Signal(i) => signals.get(*i),
+            Expr::Neg(e) => -e.eval(signals),
+            Expr::Binary(op, l, r) => {
+                let (l, r) = (l.eval(signals), r.eval(signals));
+                match op {
+                    Op::Add => l + r,
+                    Op::Sub => l - r,
+                    Op::Mul => l * r,
+                    // dividing by zero gives zero, so that a score is
+                    // always a number
+                    Op::Div if r == 0.0 => 0.0,
+                    Op::Div => l / r,
+                }
+            }
+            Expr::Call(func, args) => {
+                let values = args.iter().map(|a| a.eval(signals));
+                match func {
+                    Func::Min => values.fold(f64::INFINITY, f64::min),
+                    Func::Max => values.fold(f64::NEG_INFINITY, f64::max),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' | '-' | '*' | '/' => Token::Op(c),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut end = start + 1;
+                while let Some((i, _)) = chars.next_if(|(_, c)| c.is_ascii_digit() || *c == '.') {
+                    end = i + 1;
+                }
+                let num = &src[start..end];
+                Token::Num(num.parse().map_err(|_| format!("invalid number '{num}'"))?)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some((i, _)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    end = i + 1;
+                }
+                Token::Ident(src[start..end].to_string())
+            }
+            c => return Err(format!("unexpected character '{c}'")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+// recursive descent parser over the tokens of an expression
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), String> {
+        match self.next() {
+            Some(t) if t == expected => Ok(()),
+            _ => Err(format!("expected {what}")),
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        while let Some(Token::Op(c @ ('+' | '-'))) = self.peek() {
+            let op = if *c == '+' { Op::Add } else { Op::Sub };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(c @ ('*' | '/'))) = self.peek() {
+            let op = if *c == '*' { Op::Mul } else { Op::Div };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    // unary := '-' unary | atom
+    fn unary(&mut self) -> Result<Expr, String> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    // atom := number | signal | func '(' expr (',' expr)* ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Open) => {
+                let e = self.expr()?;
+                self.expect(Token::Close, "')'")?;
+                Ok(e)
+            }
+            Some(Token::Ident(name)) => {
+                let func = match name.as_str() {
+                    "min" => Func::Min,
+                    "max" => Func::Max,
+                    _ => {
+                        return SIGNALS
+                            .iter()
+                            .position(|s| *s == name)
+                            .map(Expr::Signal)
+                            .ok_or_else(|| {
+                                format!(
+                                    "unknown signal '{name}', expected one of: {}",
+                                    SIGNALS.join(", ")
+                                )
+                            })
+                    }
+                };
+
+                self.expect(Token::Open, &format!("'(' after {name}"))?;
+                let mut args = vec![self.expr()?];
+                while let Some(Token::Comma) = self.peek() {
+                    self.pos += 1;
+                    args.push(self.expr()?);
+                }
+                self.expect(Token::Close, "')'")?;
+                Ok(Expr::Call(func, args))
+            }
+            Some(t) => Err(format!("unexpected {t:?}")),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+// a parsed score expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreExpr {
+    source: String,
+    root: Expr,
+}
+
+impl ScoreExpr {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let root = parser.expr()?;
+        if let Some(t) = parser.peek() {
+            return Err(format!("unexpected {t:?}"));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    // the score of an entry. Negative values are treated as no match
+    pub fn score(&self, signals: &Signals) -> u32 {
+        let value = self.root.eval(signals);
+        if value.is_nan() || value <= 0.0 {
+            0
+        } else {
+            // float to int casts saturate
+            value.round() as u32
+        }
+    }
+}
+
+impl fmt::Display for ScoreExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScoreExpr {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(d)?;
+        ScoreExpr::parse(&source)
+            .map_err(|e| D::Error::custom(format!("invalid score_expr '{source}': {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, signals: &Signals) -> u32 {
+        ScoreExpr::parse(source).unwrap().score(signals)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let signals = Signals::default();
+        assert_eq!(eval("1 + 2 * 3", &signals), 7);
+        assert_eq!(eval("(1 + 2) * 3", &signals), 9);
+        assert_eq!(eval("10 - 2 - 3", &signals), 5);
+        assert_eq!(eval("7 / 2", &signals), 4);
+        assert_eq!(eval("1 / 0", &signals), 0);
+        assert_eq!(eval("-3 + 5", &signals), 2);
+        assert_eq!(eval("2 - 5", &signals), 0);
+        assert_eq!(eval("max(1, 4, 2) + min(3, 1.5)", &signals), 6);
+    }
+
+    #[test]
+    fn signals() {
+        let signals = Signals {
+            matches: [1, 2, 0, 0, 0, 0, 0, 0, 3],
+            keyword: true,
+            priority: 5,
+            tokens: 2,
+            frecency: 400,
+        };
+        assert_eq!(eval("full_name", &signals), 1);
+        assert_eq!(eval("partial_name * 10", &signals), 20);
+        assert_eq!(eval("startswith_category + keyword", &signals), 4);
+        assert_eq!(eval("priority - tokens", &signals), 3);
+        assert_eq!(eval("frecency / 100", &signals), 4);
+
+        // every signal has a value
+        for (i, name) in SIGNALS.iter().enumerate() {
+            assert_eq!(ScoreExpr::parse(name).unwrap().root, Expr::Signal(i));
+            assert!(signals.get(i) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for source in [
+            "",
+            "1 +",
+            "(1",
+            "1)",
+            "full_nam",
+            "min()",
+            "max 1",
+            "1 $ 2",
+            "1..2",
+            "full_name full_tag",
+        ] {
+            assert!(ScoreExpr::parse(source).is_err(), "{source}");
+        }
+
+        let err = ScoreExpr::parse("recency").unwrap_err();
+        assert!(err.contains("unknown signal 'recency'"), "{err}");
+    }
+}
@@ -0,0 +1,361 @@
+// contains settings that control how queries are matched against
+// entries in the store
+
+use crate::score_expr::ScoreExpr;
+use serde::{self, Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+
+// most synonyms that a single query word is expanded into, so that
+// a large synonym list does not slow down every search
+pub const MAX_SYNONYMS: usize = 8;
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SearchSettings {
+    // remove leading and trailing whitespace from the query
+    pub trim: bool,
+    // remove a matching pair of quotes surrounding the query
+    pub strip_quotes: bool,
+    // replace runs of whitespace inside the query with a single space
+    pub collapse_whitespace: bool,
+    // when entries have the same score, rank shorter names first
+    pub prefer_short_names: bool,
+    // match each component of names that are paths like a tag
+    pub split_paths: bool,
+    // also match the last component of names that are paths like a name
+    pub match_basename: bool,
+    // trailing text in entry names that starts with this delimiter
+    // is shown, but not searched
+    pub name_metadata: Option<String>,
+    // match the whole query as one string, instead of matching each
+    // word separately
+    pub phrase: bool,
+    // replaces the built in scoring of entries
+    pub score_expr: Option<ScoreExpr>,
+    // also match names against their latin transliteration
+    pub transliterate: bool,
+    // other words that a query word also matches, keyed by the
+    // lowercase word
+    #[serde(deserialize_with = "lowercase_synonyms")]
+    pub synonyms: HashMap<String, Vec<String>>,
+    // how query tokens are matched against tags
+    pub tag_match: TagMatch,
+    // match the host and path of url entries like tags
+    pub search_url: bool,
+    // how a keyword match is combined with the text score
+    pub keyword_mode: KeywordMode,
+    // how entries with the same score are ordered
+    pub sort: SortOrder,
+    // match tokens against the characters of names in order, when
+    // they do not appear in the name as a whole. Copied from
+    // config.ui.fuzzy when the settings are loaded
+    #[serde(skip)]
+    pub fuzzy: bool,
+    // added to fuzzy matches whose characters mostly start words. At
+    // most entry::MAX_BOUNDARY_BONUS
+    pub boundary_bonus: u32,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            trim: false,
+            strip_quotes: false,
+            collapse_whitespace: false,
+            prefer_short_names: false,
+            split_paths: false,
+            match_basename: false,
+            name_metadata: None,
+            phrase: false,
+            score_expr: None,
+            transliterate: false,
+            synonyms: HashMap::new(),
+            tag_match: Default::default(),
+            search_url: false,
+            keyword_mode: Default::default(),
+            sort: Default::default(),
+            fuzzy: true,
+            boundary_bonus: 1,
+        }
+    }
+}
+
+// how a query token can match a tag
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    // the token can appear anywhere in the tag
+    #[default]
+    Contains,
+    // the tag must start with the token
+    Prefix,
+}
+
+// how the bonus for matching an entry's keyword combines with how
+// well the rest of the query matches the entry
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordMode {
+    // the score is the larger of the keyword bonus and the text score
+    #[default]
+    Max,
+    // the keyword bonus is added to the text score
+    Add,
+}
+
+// how entries that match the query equally well are ordered
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    // entries later in the file come first
+    #[default]
+    Score,
+    // file entries whose file was modified most recently come
+    // first, then everything else
+    Mtime,
+}
+
+// lowercase the words of the synonyms table once when the settings
+// are loaded, so that query words can be looked up directly. The
+// synonyms of words that only differ in case are combined, in the
+// order of the words
+fn lowercase_synonyms<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Vec<String>>, D::Error> {
+    let mut synonyms: HashMap<String, Vec<String>> = HashMap::new();
+    for (word, words) in BTreeMap::<String, Vec<String>>::deserialize(deserializer)? {
+        synonyms
+            .entry(word.to_lowercase())
+            .or_default()
+            .extend(words);
+    }
+    Ok(synonyms)
+}
+
+impl SearchSettings {
+    // the synonyms that a query word also matches. Words are looked
+    // up ignoring case, and synonyms are not expanded any further
+    pub fn synonyms<'a>(&'a self, word: &str) -> impl Iterator<Item = &'a str> + 'a {
+        self.synonyms
+            .get(&word.to_lowercase())
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .take(MAX_SYNONYMS)
+    }
+
+    // clean up the text from the search box before it is used to
+    // score entries. By default the query is unchanged
+    pub fn normalize(&self, searchtext: &str) -> String {
+        let mut query = searchtext;
+
+        if self.trim {
+            query = query.trim();
+        }
+
+        if self.strip_quotes {
+            for quote in ['"', '\''] {
+                if let Some(inner) = query
+                    .strip_prefix(quote)
+                    .and_then(|q| q.strip_suffix(quote))
+                {
+                    query = inner;
+                    break;
+                }
+            }
+        }
+
+        if self.collapse_whitespace {
+            let leading = query.starts_with(char::is_whitespace);
+            let trailing = query.ends_with(char::is_whitespace);
+            let mut collapsed = query.split_whitespace().collect::<Vec<_>>().join(" ");
+            if leading {
+                collapsed.insert(0, ' ');
+            }
+            if trailing && !collapsed.ends_with(' ') {
+                collapsed.push(' ');
+            }
+            collapsed
+        } else {
+            query.to_string()
+        }
+    }
+
+    // the part of an entry name that is matched against the
+    // query. If name_metadata is an opening bracket, only a trailing
+    // bracketed note is removed, like "backup (old)". Otherwise
+    // everything after the last delimiter is removed
+    pub fn searchable_name<'a>(&self, name: &'a str) -> &'a str {
+        let delim = match self.name_metadata.as_deref() {
+            Some(d) if !d.is_empty() => d,
+            _ => return name,
+        };
+
+        let closer = match delim {
+            "(" => Some(')'),
+            "[" => Some(']'),
+            "{" => Some('}'),
+            "<" => Some('>'),
+            _ => None,
+        };
+        if let Some(closer) = closer {
+            if !name.trim_end().ends_with(closer) {
+                return name;
+            }
+        }
+
+        match name.rfind(delim) {
+            // names that are only metadata are searched as-is
+            Some(i) if !name[..i].trim().is_empty() => name[..i].trim_end(),
+            _ => name,
+        }
+    }
+}
+
+// split a query into tokens at whitespace. Text in double quotes is
+// a single token, without its quotes. A quote that is never closed
+// runs to the end of the query
+pub fn tokens(query: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = query.trim_start();
+    while !rest.is_empty() {
+        let (token, after) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        if !token.is_empty() {
+            tokens.push(token);
+        }
+        rest = after.trim_start();
+    }
+    tokens
+}
+
+// the parameter of a keyword query, which is everything after the
+// first word. A parameter that is entirely in double quotes is used
+// without its quotes
+pub fn keyword_param(searchtext: &str) -> Option<&str> {
+    let (_, param) = searchtext.split_once(char::is_whitespace)?;
+    let unquoted = param
+        .trim()
+        .strip_prefix('"')
+        .map(|p| p.strip_suffix('"').unwrap_or(p));
+    match unquoted {
+        Some(inner) if !inner.contains('"') => Some(inner),
+        _ => Some(param),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_tokens() {
+        assert_eq!(tokens("foo  bar "), ["foo", "bar"]);
+        assert_eq!(tokens("\"new york\" pizza"), ["new york", "pizza"]);
+        assert_eq!(tokens("pizza \"new  york\""), ["pizza", "new  york"]);
+        // unbalanced quotes run to the end of the query
+        assert_eq!(tokens("pizza \"new york"), ["pizza", "new york"]);
+        assert_eq!(tokens("\"\" \""), Vec::<&str>::new());
+        assert!(tokens("   ").is_empty());
+
+        assert_eq!(keyword_param("w \"new york\""), Some("new york"));
+        assert_eq!(keyword_param("w \"new york"), Some("new york"));
+        assert_eq!(keyword_param("w new york"), Some("new york"));
+        // only a parameter that is entirely quoted is unquoted
+        assert_eq!(keyword_param("w \"a\" \"b\""), Some("\"a\" \"b\""));
+        assert_eq!(keyword_param("w"), None);
+    }
+
+    #[test]
+    fn synonyms_ignore_case() {
+        let settings: SearchSettings = toml::from_str(
+            "synonyms = { Docs = ['manual'], docs = ['guide'], TV = ['television'] }",
+        )
+        .unwrap();
+        assert_eq!(settings.synonyms.len(), 2);
+
+        let synonyms = |word| settings.synonyms(word).collect::<Vec<_>>();
+        assert_eq!(synonyms("docs"), ["manual", "guide"]);
+        assert_eq!(synonyms("DOCS"), ["manual", "guide"]);
+        assert_eq!(synonyms("tv"), ["television"]);
+        assert!(synonyms("television").is_empty());
+    }
+
+    #[test]
+    fn default_is_noop() {
+        let settings = SearchSettings::default();
+        for q in ["", "  foo  ", "\"foo\"", "foo   bar"] {
+            assert_eq!(settings.normalize(q), q);
+        }
+    }
+
+    #[test]
+    fn trim() {
+        let settings = SearchSettings {
+            trim: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.normalize("  foo bar\t"), "foo bar");
+        assert_eq!(settings.normalize("   "), "");
+    }
+
+    #[test]
+    fn strip_quotes() {
+        let settings = SearchSettings {
+            strip_quotes: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.normalize("\"foo bar\""), "foo bar");
+        assert_eq!(settings.normalize("'foo'"), "foo");
+        // unbalanced or mismatched quotes are left alone
+        assert_eq!(settings.normalize("\"foo"), "\"foo");
+        assert_eq!(settings.normalize("\"foo'"), "\"foo'");
+        assert_eq!(settings.normalize("\""), "\"");
+    }
+
+    #[test]
+    fn collapse_whitespace() {
+        let settings = SearchSettings {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.normalize("foo   bar\t\tbaz"), "foo bar baz");
+        assert_eq!(settings.normalize("  foo  "), " foo ");
+    }
+
+    #[test]
+    fn searchable_name() {
+        let settings = SearchSettings::default();
+        assert_eq!(settings.searchable_name("backup (old)"), "backup (old)");
+
+        let settings = SearchSettings {
+            name_metadata: Some("(".into()),
+            ..Default::default()
+        };
+        assert_eq!(settings.searchable_name("backup (old)"), "backup");
+        assert_eq!(settings.searchable_name("a (b) (c) "), "a (b)");
+        // only trailing notes are metadata
+        assert_eq!(settings.searchable_name("foo (bar) baz"), "foo (bar) baz");
+        assert_eq!(settings.searchable_name("(old)"), "(old)");
+
+        let settings = SearchSettings {
+            name_metadata: Some(" -- ".into()),
+            ..Default::default()
+        };
+        assert_eq!(settings.searchable_name("backup -- old"), "backup");
+        assert_eq!(settings.searchable_name("backup"), "backup");
+    }
+
+    #[test]
+    fn all_steps() {
+        let settings = SearchSettings {
+            trim: true,
+            strip_quotes: true,
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.normalize("  \"foo    bar\" "), "foo bar");
+    }
+}
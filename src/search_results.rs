@@ -1,4 +1,6 @@
-use iced::{advanced, keyboard, widget};
+use iced::widget::scrollable;
+use iced::{advanced, keyboard, widget, Command};
+use std::time::{Duration, Instant};
 
 use crate::custom;
 use crate::entry;
@@ -7,12 +9,34 @@ use crate::theme;
 use crate::ui;
 
 const PADDING: u16 = 2;
+// height of the space taken up by a separator between results
+const SEPARATOR_HEIGHT: u16 = 3;
+// how long new results take to slide into place
+const ANIMATION_DURATION: Duration = Duration::from_millis(150);
+// width of the scrollbar next to results that do not all fit
+const SCROLLBAR_WIDTH: f32 = 4.0;
+
+lazy_static::lazy_static! {
+    static ref SCROLLABLE_ID: scrollable::Id = scrollable::Id::unique();
+}
 
 #[derive(Default)]
 pub struct SearchResults {
     entries: Vec<entry::EntryId>,
     selected: usize,
+    // the search box has the selection instead of a result
+    in_box: bool,
+    // first result that is scrolled into view
+    top: usize,
     settings: ui::UISettings,
+    animations: Animations,
+}
+
+// where keyboard focus moved after handling a key
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Focus {
+    SearchBox,
+    Results,
 }
 
 impl std::hash::Hash for SearchResults {
@@ -22,6 +46,8 @@ impl std::hash::Hash for SearchResults {
     {
         self.entries.hash(state);
         self.selected.hash(state);
+        self.in_box.hash(state);
+        self.top.hash(state);
     }
 }
 
@@ -43,46 +69,175 @@ impl SearchResults {
         SearchResults {
             entries: results.take(settings.max_results).collect(),
             selected: 0,
+            in_box: false,
+            top: 0,
             settings: settings.clone(),
+            animations: Animations::default(),
         }
     }
 
+    // slide in the results that were not shown in the previous results
+    pub fn animate_from(&mut self, previous: &SearchResults, now: Instant) {
+        self.animations = Animations::start(&previous.entries, &self.entries, now);
+    }
+
+    // advance the animations to the given time
+    pub fn tick(&mut self, now: Instant) {
+        self.animations.tick(now);
+    }
+
+    // whether any results are still sliding into place
+    pub fn animating(&self) -> bool {
+        self.animations.running()
+    }
+
     pub fn set_selection(&mut self, id: entry::EntryId) {
         if id < self.entries.len() {
             self.selected = id;
+            self.in_box = false;
         }
     }
 
     pub fn selected(&self) -> Option<entry::EntryId> {
+        if self.in_box {
+            return None;
+        }
         self.entries.get(self.selected).map(|e| *e)
     }
 
-    pub fn handle_kb(&mut self, event: keyboard::Event) {
-        let code = match event {
+    // move the selection for a press of the arrow keys, which the
+    // search box does not capture. Returns the new focus if the
+    // selection moved between the search box and the results
+    pub fn handle_kb(&mut self, event: keyboard::Event) -> Option<Focus> {
+        match event {
             keyboard::Event::KeyPressed {
-                key_code: code,
+                key_code: code @ (keyboard::KeyCode::Up | keyboard::KeyCode::Down),
                 modifiers: _,
-            } => code,
-            _ => return,
+            } => self.handle_key(code),
+            _ => None,
+        }
+    }
+
+    // move the selection as if a key was pressed
+    pub fn handle_key(&mut self, code: keyboard::KeyCode) -> Option<Focus> {
+        let current = if self.in_box {
+            None
+        } else {
+            Some(self.selected)
         };
+        let next = jump_position(current, self.entries.len(), code, self.page()).or_else(|| {
+            next_position(
+                current,
+                self.entries.len(),
+                code,
+                self.settings.box_results_nav,
+                self.settings.wrap_selection,
+            )
+        });
 
-        if code == keyboard::KeyCode::Up {
-            if self.selected > 0 {
-                self.selected -= 1;
-            }
+        self.in_box = next.is_none();
+        if let Some(next) = next {
+            self.selected = next;
+            self.top = scrolled_top(self.top, next, self.visible(), self.entries.len());
+        }
+
+        match (current, next) {
+            (Some(_), None) => Some(Focus::SearchBox),
+            (None, Some(_)) => Some(Focus::Results),
+            _ => None,
+        }
+    }
+
+    // number of results that are shown without scrolling
+    fn visible(&self) -> usize {
+        self.settings.visible_results.max(1)
+    }
+
+    // number of results moved by page up and page down
+    fn page(&self) -> usize {
+        self.settings.page_size.unwrap_or(self.visible()).max(1)
+    }
+
+    // the rows that are scrolled into view, or None if every result
+    // fits without scrolling
+    fn scrolled_rows(&self) -> Option<std::ops::Range<usize>> {
+        if self.entries.len() > self.visible() {
+            Some(self.top..self.top + self.visible())
+        } else {
+            None
         }
-        if code == keyboard::KeyCode::Down {
-            let max_num = self.entries.len();
-            if self.selected + 1 < max_num {
-                self.selected += 1;
+    }
+
+    // scroll the results so that the rows from self.top are shown
+    pub fn scroll(&self, store: &store::Store) -> Command<crate::Message> {
+        match self.scrolled_rows() {
+            Some(rows) => {
+                let (offset, _) = self.viewport(store, rows);
+                scrollable::scroll_to(
+                    SCROLLABLE_ID.clone(),
+                    scrollable::AbsoluteOffset { x: 0.0, y: offset },
+                )
             }
+            None => Command::none(),
         }
     }
 
+    // follow scrolling that did not come from the keyboard, like the
+    // mouse wheel, so that the next key press scrolls on from the rows
+    // that are in view. The first row that is at least half shown
+    // becomes the top row
+    pub fn scrolled(&mut self, store: &store::Store, offset: f32) {
+        let (heights, separator) = self.row_heights(store);
+        let mut start = 0.0;
+        let top = heights
+            .iter()
+            .take_while(|height| {
+                let middle = start + *height / 2.0;
+                start += *height + separator;
+                middle < offset
+            })
+            .count();
+        self.top = top.min(self.entries.len().saturating_sub(self.visible()));
+    }
+
+    // estimated height of each result, and of the separators between
+    // them. Row heights are estimated from the settings, since the
+    // results have to be scrolled before they are laid out
+    fn row_heights(&self, store: &store::Store) -> (Vec<f32>, f32) {
+        let separator = if self.settings.entry.separators {
+            SEPARATOR_HEIGHT as f32
+        } else {
+            0.0
+        };
+        let heights = self
+            .entries
+            .iter()
+            .map(|e| store.get(*e).row_height(&self.settings))
+            .collect();
+        (heights, separator)
+    }
+
+    // offset and height of the part of the results that shows rows
+    fn viewport(&self, store: &store::Store, rows: std::ops::Range<usize>) -> (f32, f32) {
+        let (heights, separator) = self.row_heights(store);
+        let height = |rows: std::ops::Range<usize>| -> f32 {
+            let count = rows.len() as f32;
+            heights[rows].iter().sum::<f32>() + separator * (count - 1.0).max(0.0)
+        };
+
+        let offset = if rows.start == 0 {
+            0.0
+        } else {
+            height(0..rows.start) + separator
+        };
+        (offset, height(rows) + 2.0 * PADDING as f32)
+    }
+
     pub fn view<'a, F, Renderer>(
         &'a self,
         searchtext: &str,
         store: &'a store::Store,
+        action: entry::Action,
         f: F,
     ) -> iced::Element<'a, crate::Message, Renderer>
     where
@@ -99,22 +254,681 @@ impl SearchResults {
         }
 
         let mut column = widget::Column::new().padding(PADDING);
-        for (i, e) in self.entries.iter().enumerate() {
+        for row in rows(self.entries.len(), self.settings.entry.separators) {
+            let i = match row {
+                Row::Separator => {
+                    column = column.push(widget::Rule::horizontal(SEPARATOR_HEIGHT));
+                    continue;
+                }
+                Row::Entry(i) => i,
+            };
+            let e = &self.entries[i];
             let entry = store.get(*e);
             // unwrap will never panic since UI_MAX_RESULTS is const
-            let entry_widget =
-                entry.build_entry(f, searchtext, &self.settings, i == self.selected, *e);
+            let entry_widget = entry.build_entry(
+                f,
+                searchtext,
+                &self.settings,
+                self.highlighted() == Some(i),
+                action,
+                *e,
+            );
 
             let mouse_area = custom::MouseArea::new(entry_widget)
                 .on_mouse_enter(crate::Message::EntryHovered(i));
 
-            column = column.push(mouse_area);
+            // rows stay wrapped once they are in place, so that the
+            // widget tree of a row does not change when it stops
+            if self.settings.animations {
+                let background = self.settings.theme.background_color.clone().into();
+                let progress = self.animations.progress(i);
+                column = column.push(custom::SlideIn::new(mouse_area, progress, background));
+            } else {
+                column = column.push(mouse_area);
+            }
         }
-        let element: iced::Element<'_, _, _> = column.into();
+        // results that do not all fit are scrolled, so that the
+        // window does not grow taller than the visible results
+        let element: iced::Element<'_, _, _> = match self.scrolled_rows() {
+            Some(rows) => {
+                let (_, height) = self.viewport(store, rows);
+                let scrollbar = scrollable::Properties::new()
+                    .width(SCROLLBAR_WIDTH)
+                    .scroller_width(SCROLLBAR_WIDTH);
+                widget::Scrollable::new(column)
+                    .id(SCROLLABLE_ID.clone())
+                    .height(height)
+                    .direction(scrollable::Direction::Vertical(scrollbar))
+                    .on_scroll(|viewport| {
+                        crate::Message::ResultsScrolled(viewport.absolute_offset().y)
+                    })
+                    .into()
+            }
+            None => column.into(),
+        };
         element
     }
 
     pub fn entries(&self) -> &[entry::EntryId] {
         &self.entries
     }
+
+    // the row that is drawn as selected. No row is highlighted while
+    // the search box has the selection
+    fn highlighted(&self) -> Option<usize> {
+        (!self.in_box && self.selected < self.entries.len()).then_some(self.selected)
+    }
+}
+
+// a row in the list of results. Separators are only drawn, and are
+// never selected
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Row {
+    Entry(usize),
+    Separator,
+}
+
+// the rows that show len results, with a separator between each
+// pair of results if enabled
+fn rows(len: usize, separators: bool) -> impl Iterator<Item = Row> {
+    (0..len).flat_map(move |i| {
+        let separator = (separators && i > 0).then_some(Row::Separator);
+        separator.into_iter().chain(std::iter::once(Row::Entry(i)))
+    })
+}
+
+// decides whether hovering over a result selects it. Results that
+// are laid out again under a cursor that is not moving get hovered
+// without the user touching the mouse, so once the results change or
+// the keyboard is used, hovering is ignored until the cursor moves
+#[derive(Debug, Default)]
+pub struct HoverGate {
+    position: Option<iced::Point>,
+    armed: bool,
+    // result that was hovered while hovering was ignored
+    pending: Option<usize>,
+}
+
+impl HoverGate {
+    // ignore hovering until the cursor moves
+    pub fn disarm(&mut self) {
+        self.armed = false;
+        self.pending = None;
+    }
+
+    // the result to select for a hover, if any
+    pub fn hover(&mut self, index: usize) -> Option<usize> {
+        if self.armed {
+            Some(index)
+        } else {
+            self.pending = Some(index);
+            None
+        }
+    }
+
+    // the cursor was reported at position, which may not be a
+    // change. Hover events arrive before the cursor movement that
+    // caused them, so the result hovered just before the cursor
+    // moved is returned to be selected
+    pub fn cursor_moved(&mut self, position: iced::Point) -> Option<usize> {
+        let moved = self.position.is_some_and(|p| p != position);
+        self.position = Some(position);
+
+        if moved && !self.armed {
+            self.armed = true;
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+}
+
+// lets repeated presses of enter choose the action that is taken for
+// an entry. The first press picks the first action, every press
+// after it picks the next one, and the action is taken once the
+// presses stop. Each press returns a number that identifies it, so
+// that only the timer of the last press takes the action
+#[derive(Debug, Default)]
+pub struct ActionCycle {
+    // entry and the index of its chosen action
+    pending: Option<(entry::EntryId, usize)>,
+    // number of presses so far
+    presses: u64,
+}
+
+impl ActionCycle {
+    // enter was pressed for an entry with this many actions
+    pub fn press(&mut self, id: entry::EntryId, actions: usize) -> u64 {
+        let index = match self.pending {
+            Some((pending, index)) if pending == id => (index + 1) % actions.max(1),
+            _ => 0,
+        };
+        self.pending = Some((id, index));
+        self.presses += 1;
+        self.presses
+    }
+
+    // the entry and action index that are chosen, if any
+    pub fn pending(&self) -> Option<(entry::EntryId, usize)> {
+        self.pending
+    }
+
+    // the timer of a press ran out. If it was the last press, the
+    // chosen action is returned to be taken
+    pub fn expire(&mut self, press: u64) -> Option<(entry::EntryId, usize)> {
+        if press == self.presses {
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+
+    // forget the chosen action, for example when the selection changes
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    // the selection may have moved. An action chosen for any other
+    // entry than the selected one is forgotten
+    pub fn follow(&mut self, selected: Option<entry::EntryId>) {
+        if matches!(self.pending, Some((id, _)) if Some(id) != selected) {
+            self.cancel();
+        }
+    }
+}
+
+// how far each result row has slid into place. Only rows whose entry
+// was not among the previous results are animated, so that results
+// that stay shown while typing do not flicker
+#[derive(Debug, Default)]
+struct Animations {
+    // start time and eased progress of each row that is still
+    // animating, indexed by row
+    rows: Vec<Option<(Instant, f32)>>,
+}
+
+impl Animations {
+    fn start(previous: &[entry::EntryId], current: &[entry::EntryId], now: Instant) -> Self {
+        let rows = current
+            .iter()
+            .map(|id| (!previous.contains(id)).then_some((now, 0.0)))
+            .collect();
+        Animations { rows }
+    }
+
+    fn tick(&mut self, now: Instant) {
+        for row in self.rows.iter_mut() {
+            if let Some((start, progress)) = row {
+                let t = now.saturating_duration_since(*start).as_secs_f32()
+                    / ANIMATION_DURATION.as_secs_f32();
+                if t >= 1.0 {
+                    *row = None;
+                } else {
+                    *progress = ease_out(t);
+                }
+            }
+        }
+    }
+
+    fn running(&self) -> bool {
+        self.rows.iter().any(Option::is_some)
+    }
+
+    // rows that are not animating are in place
+    fn progress(&self, row: usize) -> f32 {
+        match self.rows.get(row) {
+            Some(Some((_, progress))) => *progress,
+            _ => 1.0,
+        }
+    }
+}
+
+// starts fast and slows down as it settles, for t from 0 to 1
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+// first row to scroll into view so that the selected row is shown,
+// moving as little as possible from the current first row
+fn scrolled_top(top: usize, selected: usize, visible: usize, len: usize) -> usize {
+    let visible = visible.max(1);
+    let top = if selected < top {
+        selected
+    } else if selected >= top + visible {
+        selected + 1 - visible
+    } else {
+        top
+    };
+    top.min(len.saturating_sub(visible))
+}
+
+// the key that moves the selection for a key press that the search
+// box captures. Ctrl-n and ctrl-p move through the results like the
+// down and up arrows, so the hands can stay on the home row. Home
+// and end on their own move the cursor in the query, so they only
+// jump to the first and last results while ctrl is held
+pub fn navigation_key(
+    code: keyboard::KeyCode,
+    modifiers: keyboard::Modifiers,
+) -> Option<keyboard::KeyCode> {
+    match code {
+        keyboard::KeyCode::N if modifiers.control() => Some(keyboard::KeyCode::Down),
+        keyboard::KeyCode::P if modifiers.control() => Some(keyboard::KeyCode::Up),
+        keyboard::KeyCode::Home | keyboard::KeyCode::End if modifiers.control() => Some(code),
+        keyboard::KeyCode::PageUp | keyboard::KeyCode::PageDown => Some(code),
+        _ => None,
+    }
+}
+
+// where the selection jumps for home, end, page up and page down, or
+// None for other keys. Jumps only move within the results, so they do
+// nothing while the search box is selected or there are no results
+fn jump_position(
+    current: Option<usize>,
+    len: usize,
+    code: keyboard::KeyCode,
+    page: usize,
+) -> Option<usize> {
+    let current = current.filter(|_| len > 0)?;
+    match code {
+        keyboard::KeyCode::Home => Some(0),
+        keyboard::KeyCode::End => Some(len - 1),
+        keyboard::KeyCode::PageUp => Some(current.saturating_sub(page)),
+        keyboard::KeyCode::PageDown => Some((current + page).min(len - 1)),
+        _ => None,
+    }
+}
+
+// where the selection moves for a key press, where None is the search
+// box. Without box_nav the search box is never selected. With wrap,
+// moving past the last result selects the first one and moving
+// before the first selects the last, unless box_nav moves to the
+// search box instead
+fn next_position(
+    current: Option<usize>,
+    len: usize,
+    code: keyboard::KeyCode,
+    box_nav: bool,
+    wrap: bool,
+) -> Option<usize> {
+    match (code, current) {
+        (keyboard::KeyCode::Up, Some(0)) if box_nav => None,
+        (keyboard::KeyCode::Up, Some(0)) if wrap && len > 1 => Some(len - 1),
+        (keyboard::KeyCode::Up, Some(i)) => Some(i.saturating_sub(1)),
+        (keyboard::KeyCode::Down, None) if len > 0 => Some(0),
+        (keyboard::KeyCode::Down, Some(i)) if i + 1 < len => Some(i + 1),
+        (keyboard::KeyCode::Down, Some(_)) if wrap && len > 1 => Some(0),
+        (_, current) => current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyboard::KeyCode::{Down, Up};
+
+    #[test]
+    fn box_navigation_boundaries() {
+        // without box navigation, the first and last results stop
+        assert_eq!(next_position(Some(0), 3, Up, false, false), Some(0));
+        assert_eq!(next_position(Some(2), 3, Down, false, false), Some(2));
+        assert_eq!(next_position(Some(1), 3, Up, false, false), Some(0));
+
+        // up from the first result moves to the search box, and down
+        // moves back into the results
+        assert_eq!(next_position(Some(0), 3, Up, true, false), None);
+        assert_eq!(next_position(Some(1), 3, Up, true, false), Some(0));
+        assert_eq!(next_position(None, 3, Down, true, false), Some(0));
+        assert_eq!(next_position(None, 3, Up, true, false), None);
+        assert_eq!(next_position(Some(2), 3, Down, true, false), Some(2));
+
+        // with no results, the search box keeps the selection
+        assert_eq!(next_position(None, 0, Down, true, false), None);
+    }
+
+    #[test]
+    fn selection_stays_scrolled_into_view() {
+        let mut settings = ui::UISettings::default();
+        settings.max_results = 20;
+        settings.visible_results = 3;
+        let mut results = SearchResults::new(0..50, &settings);
+        assert_eq!(results.entries().len(), 20);
+        assert_eq!(results.scrolled_rows(), Some(0..3));
+
+        let press = |results: &mut SearchResults, key_code| {
+            results.handle_kb(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers: keyboard::Modifiers::empty(),
+            });
+            let rows = results.scrolled_rows().unwrap();
+            assert!(rows.contains(&results.selected), "{rows:?}");
+            assert!(rows.end <= results.entries().len(), "{rows:?}");
+            rows
+        };
+
+        // moving down only scrolls once the selection leaves the view
+        assert_eq!(press(&mut results, Down), 0..3);
+        assert_eq!(press(&mut results, Down), 0..3);
+        assert_eq!(press(&mut results, Down), 1..4);
+        for _ in 0..30 {
+            press(&mut results, Down);
+        }
+        assert_eq!(results.selected, 19);
+        assert_eq!(press(&mut results, Down), 17..20);
+
+        // and moving up scrolls back as needed
+        assert_eq!(press(&mut results, Up), 17..20);
+        assert_eq!(press(&mut results, Up), 17..20);
+        assert_eq!(press(&mut results, Up), 16..19);
+
+        // jumps across the results, like wrapping from one end to
+        // the other, scroll the selection into view
+        assert_eq!(scrolled_top(16, 0, 3, 20), 0);
+        assert_eq!(scrolled_top(0, 19, 3, 20), 17);
+        assert_eq!(scrolled_top(5, 6, 3, 20), 5);
+        assert_eq!(scrolled_top(18, 19, 3, 20), 17);
+        assert_eq!(scrolled_top(0, 2, 5, 3), 0);
+
+        // results that fit are not scrolled
+        settings.visible_results = 20;
+        assert_eq!(SearchResults::new(0..50, &settings).scrolled_rows(), None);
+    }
+
+    #[test]
+    fn wheel_scrolling_moves_top_row() {
+        let toml: String = (0..10)
+            .map(|i| format!("['foo {i}']\nlocation = '{i}'\n"))
+            .collect();
+        let store = crate::store::tests::parse_store(&toml).unwrap();
+        let mut settings = ui::UISettings::default();
+        settings.max_results = 10;
+        settings.visible_results = 3;
+        settings.entry.separators = true;
+        let height = store.get(0).row_height(&settings) + SEPARATOR_HEIGHT as f32;
+        let mut results = SearchResults::new(0..10, &settings);
+
+        // the first row that is at least half in view is the top row
+        results.scrolled(&store, 4.0 * height);
+        assert_eq!(results.scrolled_rows(), Some(4..7));
+        results.scrolled(&store, 2.4 * height);
+        assert_eq!(results.scrolled_rows(), Some(2..5));
+        results.scrolled(&store, 2.6 * height);
+        assert_eq!(results.scrolled_rows(), Some(3..6));
+        results.scrolled(&store, 100.0 * height);
+        assert_eq!(results.scrolled_rows(), Some(7..10));
+
+        // keys scroll on from the rows that the wheel scrolled to
+        results.scrolled(&store, 5.0 * height);
+        results.handle_key(keyboard::KeyCode::Down);
+        assert_eq!(results.selected, 1);
+        assert_eq!(results.scrolled_rows(), Some(1..4));
+        assert_eq!(results.viewport(&store, 1..4).0, height);
+    }
+
+    #[test]
+    fn wrap_selection() {
+        // the ends wrap around to each other
+        assert_eq!(next_position(Some(0), 3, Up, false, true), Some(2));
+        assert_eq!(next_position(Some(2), 3, Down, false, true), Some(0));
+        assert_eq!(next_position(Some(1), 3, Up, false, true), Some(0));
+        assert_eq!(next_position(Some(1), 3, Down, false, true), Some(2));
+
+        // a single result stays selected
+        assert_eq!(next_position(Some(0), 1, Up, false, true), Some(0));
+        assert_eq!(next_position(Some(0), 1, Down, false, true), Some(0));
+
+        // with no results, there is nothing to wrap to
+        assert_eq!(next_position(Some(0), 0, Up, false, true), Some(0));
+        assert_eq!(next_position(Some(0), 0, Down, false, true), Some(0));
+        assert_eq!(next_position(None, 0, Down, true, true), None);
+
+        // moving to the search box wins over wrapping at the top
+        assert_eq!(next_position(Some(0), 3, Up, true, true), None);
+        assert_eq!(next_position(Some(2), 3, Down, true, true), Some(0));
+
+        // wrapping scrolls the selection into view
+        let settings = ui::UISettings {
+            max_results: 10,
+            visible_results: 3,
+            wrap_selection: true,
+            ..Default::default()
+        };
+        let mut results = SearchResults::new(0..10, &settings);
+        let up = keyboard::Event::KeyPressed {
+            key_code: Up,
+            modifiers: keyboard::Modifiers::empty(),
+        };
+        results.handle_kb(up);
+        assert_eq!(results.selected(), Some(9));
+        assert_eq!(results.scrolled_rows(), Some(7..10));
+    }
+
+    #[test]
+    fn ctrl_n_and_ctrl_p() {
+        use keyboard::KeyCode::{N, P};
+        let ctrl = keyboard::Modifiers::CTRL;
+
+        assert_eq!(navigation_key(N, ctrl), Some(Down));
+        assert_eq!(navigation_key(P, ctrl), Some(Up));
+
+        // without ctrl, the letters are just typed
+        assert_eq!(navigation_key(N, keyboard::Modifiers::empty()), None);
+        assert_eq!(navigation_key(N, keyboard::Modifiers::SHIFT), None);
+        assert_eq!(navigation_key(P, keyboard::Modifiers::ALT), None);
+        assert_eq!(navigation_key(Down, keyboard::Modifiers::empty()), None);
+
+        // pressing them directly is not the same as pressing the arrows
+        let mut results = SearchResults::new(0..5, &ui::UISettings::default());
+        results.handle_kb(keyboard::Event::KeyPressed {
+            key_code: N,
+            modifiers: ctrl,
+        });
+        assert_eq!(results.selected(), Some(0));
+    }
+
+    #[test]
+    fn jump_keys() {
+        use keyboard::KeyCode::{End, Home, PageDown, PageUp};
+
+        let settings = ui::UISettings {
+            max_results: 10,
+            visible_results: 3,
+            ..Default::default()
+        };
+        let mut results = SearchResults::new(0..10, &settings);
+        let mut press = |code| {
+            results.handle_key(code);
+            (results.selected(), results.scrolled_rows())
+        };
+
+        assert_eq!(press(End), (Some(9), Some(7..10)));
+        assert_eq!(press(Home), (Some(0), Some(0..3)));
+
+        // a page is the number of visible results, and stops at the ends
+        assert_eq!(press(PageDown), (Some(3), Some(1..4)));
+        assert_eq!(press(PageDown), (Some(6), Some(4..7)));
+        assert_eq!(press(PageDown), (Some(9), Some(7..10)));
+        assert_eq!(press(PageDown), (Some(9), Some(7..10)));
+        assert_eq!(press(PageUp), (Some(6), Some(6..9)));
+        assert_eq!(press(Down), (Some(7), Some(6..9)));
+        assert_eq!(press(PageUp), (Some(4), Some(4..7)));
+        assert_eq!(press(PageUp), (Some(1), Some(1..4)));
+        assert_eq!(press(PageUp), (Some(0), Some(0..3)));
+
+        // the page size can be set
+        let settings = ui::UISettings {
+            page_size: Some(4),
+            ..settings
+        };
+        let mut results = SearchResults::new(0..10, &settings);
+        results.handle_key(PageDown);
+        assert_eq!(results.selected(), Some(4));
+
+        // home and end need ctrl, so that they can still move the
+        // cursor in the search box
+        let ctrl = keyboard::Modifiers::CTRL;
+        let none = keyboard::Modifiers::empty();
+        assert_eq!(navigation_key(Home, ctrl), Some(Home));
+        assert_eq!(navigation_key(End, ctrl), Some(End));
+        assert_eq!(navigation_key(Home, none), None);
+        assert_eq!(navigation_key(End, keyboard::Modifiers::SHIFT), None);
+        assert_eq!(navigation_key(PageUp, none), Some(PageUp));
+        assert_eq!(navigation_key(PageDown, none), Some(PageDown));
+
+        // and they are not handled a second time when the search box
+        // lets them through
+        for key_code in [Home, End, PageUp, PageDown] {
+            results.handle_kb(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers: ctrl,
+            });
+            assert_eq!(results.selected(), Some(4));
+        }
+
+        // with no results, or from the search box, nothing moves
+        for key in [Home, End, PageUp, PageDown] {
+            assert_eq!(jump_position(Some(0), 0, key, 3), None);
+            assert_eq!(jump_position(None, 10, key, 3), None);
+            assert_eq!(next_position(Some(0), 0, key, false, false), Some(0));
+            assert_eq!(next_position(None, 10, key, true, false), None);
+        }
+        assert_eq!(jump_position(Some(2), 10, Down, 3), None);
+    }
+
+    #[test]
+    fn no_highlight_in_search_box() {
+        let settings = ui::UISettings {
+            box_results_nav: true,
+            ..Default::default()
+        };
+        let mut results = SearchResults::new(0..3, &settings);
+        assert_eq!(results.highlighted(), Some(0));
+
+        // moving up into the search box leaves no row highlighted
+        assert_eq!(results.handle_key(Up), Some(Focus::SearchBox));
+        assert_eq!(results.highlighted(), None);
+        assert_eq!(results.selected(), None);
+
+        // and moving back down highlights the first row again
+        assert_eq!(results.handle_key(Down), Some(Focus::Results));
+        assert_eq!(results.highlighted(), Some(0));
+
+        assert_eq!(SearchResults::new(0..0, &settings).highlighted(), None);
+    }
+
+    #[test]
+    fn separators_between_results() {
+        use Row::*;
+
+        let rows = |len, separators| rows(len, separators).collect::<Vec<_>>();
+        assert_eq!(rows(3, false), [Entry(0), Entry(1), Entry(2)]);
+        assert_eq!(
+            rows(3, true),
+            [Entry(0), Separator, Entry(1), Separator, Entry(2)]
+        );
+        assert_eq!(rows(1, true), [Entry(0)]);
+        assert!(rows(0, true).is_empty());
+    }
+
+    #[test]
+    fn action_cycle() {
+        let mut cycle = ActionCycle::default();
+        assert_eq!(cycle.pending(), None);
+
+        // each press moves to the next action, wrapping around
+        let first = cycle.press(4, 3);
+        assert_eq!(cycle.pending(), Some((4, 0)));
+        let second = cycle.press(4, 3);
+        assert_eq!(cycle.pending(), Some((4, 1)));
+
+        // only the last press takes the action, and only once
+        assert_eq!(cycle.expire(first), None);
+        assert_eq!(cycle.pending(), Some((4, 1)));
+        assert_eq!(cycle.expire(second), Some((4, 1)));
+        assert_eq!(cycle.expire(second), None);
+
+        // the cycle starts over after the action was taken
+        cycle.press(4, 3);
+        cycle.press(4, 3);
+        let last = cycle.press(4, 3);
+        assert_eq!(cycle.expire(last), Some((4, 2)));
+        let last = cycle.press(4, 3);
+        assert_eq!(cycle.expire(last), Some((4, 0)));
+
+        cycle.press(4, 2);
+        cycle.press(4, 2);
+        let last = cycle.press(4, 2);
+        assert_eq!(cycle.pending(), Some((4, 0)));
+
+        // a different entry starts with its first action
+        let other = cycle.press(5, 2);
+        assert_eq!(cycle.pending(), Some((5, 0)));
+        assert_eq!(cycle.expire(last), None);
+
+        cycle.cancel();
+        assert_eq!(cycle.expire(other), None);
+    }
+
+    #[test]
+    fn row_animations() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // only entries that were not shown before slide in
+        let mut animations = Animations::start(&[1, 2], &[3, 1, 4], start);
+        assert!(animations.running());
+        assert_eq!(animations.progress(0), 0.0);
+        assert_eq!(animations.progress(1), 1.0);
+        assert_eq!(animations.progress(2), 0.0);
+        assert_eq!(animations.progress(3), 1.0);
+
+        // progress eases out, moving most in the first half
+        animations.tick(at(75));
+        let halfway = animations.progress(0);
+        assert!(halfway > 0.5 && halfway < 1.0, "{halfway}");
+        assert_eq!(animations.progress(0), animations.progress(2));
+
+        animations.tick(at(140));
+        assert!(animations.progress(0) > halfway);
+
+        // finished rows are in place and stop the animation
+        animations.tick(at(150));
+        assert_eq!(animations.progress(0), 1.0);
+        assert!(!animations.running());
+
+        assert!(!Animations::start(&[1, 2], &[2, 1], start).running());
+        assert!(!Animations::default().running());
+        assert_eq!(ease_out(0.0), 0.0);
+        assert_eq!(ease_out(1.0), 1.0);
+        assert_eq!(ease_out(2.0), 1.0);
+    }
+
+    #[test]
+    fn hover_needs_cursor_motion() {
+        let mut gate = HoverGate::default();
+        let p = |x| iced::Point::new(x, 10.0);
+
+        // hovering is ignored until the cursor moves
+        assert_eq!(gate.hover(1), None);
+        assert_eq!(gate.cursor_moved(p(5.0)), None);
+        assert_eq!(gate.cursor_moved(p(5.0)), None);
+        assert_eq!(gate.hover(2), None);
+
+        // the first real movement selects the hovered result
+        assert_eq!(gate.cursor_moved(p(6.0)), Some(2));
+        assert_eq!(gate.hover(3), Some(3));
+        assert_eq!(gate.cursor_moved(p(7.0)), None);
+
+        // results that move under a still cursor are not selected
+        gate.disarm();
+        assert_eq!(gate.hover(0), None);
+        assert_eq!(gate.cursor_moved(p(7.0)), None);
+        assert_eq!(gate.hover(1), None);
+        assert_eq!(gate.cursor_moved(p(8.0)), Some(1));
+
+        // a disarmed gate with nothing hovered selects nothing
+        gate.disarm();
+        assert_eq!(gate.cursor_moved(p(9.0)), None);
+        assert_eq!(gate.hover(4), Some(4));
+    }
 }
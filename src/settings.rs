@@ -1,9 +1,199 @@
-use crate::{log, ui};
+use crate::{entry, log, search, ui};
 use serde;
+use std::time::Duration;
 
-#[derive(serde::Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 #[serde(default)]
 pub struct Settings {
     pub ui: ui::UISettings,
     pub log: log::LogSettings,
+    pub search: search::SearchSettings,
+    pub spawn_timeout_ms: Option<u64>,
+    pub config_trigger: String,
+    pub allowed_schemes: Option<Vec<String>>,
+    pub denied_schemes: Vec<String>,
+    pub copy_prefix: String,
+    pub copy_suffix: String,
+    pub use_scheme_handler: bool,
+    pub fallback_url: Option<String>,
+    pub copy_feedback: CopyFeedback,
+    pub open_copy_modifier: OpenCopyModifier,
+    // if false, the command key is ignored and entries are only opened
+    pub allow_copy: bool,
+    // url of a shared config file whose entries are added to the store
+    pub remote_store: Option<String>,
+    // what selecting a keyword entry does when the query has no parameter
+    pub empty_keyword: EmptyKeyword,
+}
+
+// what happens when a keyword entry is selected, but the query does
+// not contain a parameter for it
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyKeyword {
+    // open the entry with its default_arg, or an empty parameter
+    #[default]
+    Run,
+    // replace the query with the keyword, so the parameter can be typed
+    Prefill,
+    // do nothing
+    Ignore,
+}
+
+// modifier that, held together with the command key, both opens the
+// selected entry and copies it to the clipboard
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenCopyModifier {
+    // entries are never opened and copied at once
+    #[default]
+    None,
+    Shift,
+    Alt,
+}
+
+// how jolly lets the user know that something was copied
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyFeedback {
+    // keep the search window open, with a short notice
+    Toast,
+    // replace the search window with a message
+    #[default]
+    Message,
+    // copy and close without any message
+    None,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ui: Default::default(),
+            log: Default::default(),
+            search: Default::default(),
+            spawn_timeout_ms: None,
+            config_trigger: ":config".into(),
+            allowed_schemes: None,
+            // scripts and inline content, file urls that can hide a
+            // local program behind a link, and windows handlers that
+            // have been used to run code from a link
+            denied_schemes: [
+                "javascript",
+                "vbscript",
+                "data",
+                "file",
+                "ms-msdt",
+                "search-ms",
+            ]
+            .map(String::from)
+            .to_vec(),
+            copy_prefix: String::new(),
+            copy_suffix: String::new(),
+            use_scheme_handler: false,
+            fallback_url: None,
+            copy_feedback: Default::default(),
+            open_copy_modifier: Default::default(),
+            allow_copy: true,
+            remote_store: None,
+            empty_keyword: Default::default(),
+        }
+    }
+}
+
+impl Settings {
+    // query that shows an entry for editing the config file. An
+    // empty trigger disables the entry
+    pub fn config_trigger(&self) -> Option<&str> {
+        if self.config_trigger.is_empty() {
+            None
+        } else {
+            Some(&self.config_trigger)
+        }
+    }
+
+    // check if urls with this scheme may be opened. The denylist
+    // always wins, and if there is no allowlist any other scheme is ok
+    pub fn scheme_allowed(&self, scheme: &str) -> bool {
+        let matches = |s: &String| s.eq_ignore_ascii_case(scheme);
+
+        if self.denied_schemes.iter().any(matches) {
+            return false;
+        }
+
+        match &self.allowed_schemes {
+            Some(allowed) => allowed.iter().any(matches),
+            None => true,
+        }
+    }
+
+    // every problem with the settings, so that they can all be
+    // reported at once instead of one at a time. Problems that
+    // clamp() fixes say which value is used instead
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = self.ui.validate();
+
+        if let Some(url) = &self.fallback_url {
+            if !url.contains("%s") {
+                problems.push(format!(
+                    "config.fallback_url '{url}' does not contain %s, so the query is never used"
+                ));
+            }
+        }
+
+        let mut synonyms: Vec<_> = self.search.synonyms.iter().collect();
+        synonyms.sort();
+        for (word, synonyms) in synonyms {
+            if synonyms.len() > search::MAX_SYNONYMS {
+                problems.push(format!(
+                    "config.search.synonyms.{word} has more than {} synonyms, only the first {} are used",
+                    search::MAX_SYNONYMS,
+                    search::MAX_SYNONYMS
+                ));
+            }
+        }
+
+        if self.search.boundary_bonus > entry::MAX_BOUNDARY_BONUS {
+            problems.push(format!(
+                "config.search.boundary_bonus must be at most {0}, using {0}",
+                entry::MAX_BOUNDARY_BONUS
+            ));
+        }
+
+        if let Some(url) = &self.remote_store {
+            if url::Url::parse(url).map_or(true, |u| u.scheme() != "https") {
+                problems.push(format!("config.remote_store '{url}' is not an https url"));
+            }
+        }
+
+        // the query is trimmed before it is compared to the trigger
+        if self.config_trigger.trim() != self.config_trigger {
+            problems.push(format!(
+                "config.config_trigger '{}' starts or ends with whitespace, so it never matches",
+                self.config_trigger
+            ));
+        }
+
+        problems
+    }
+
+    // adjust settings that are out of range. validate() reports
+    // each setting that is changed
+    pub fn clamp(&mut self) {
+        if self.ui.max_results == 0 {
+            self.ui.max_results = 1;
+        }
+        if self.ui.visible_results == 0 {
+            self.ui.visible_results = 1;
+        }
+        self.search.boundary_bonus = self.search.boundary_bonus.min(entry::MAX_BOUNDARY_BONUS);
+    }
+
+    // text that is placed on the clipboard when copying a selection
+    pub fn copy_text(&self, selection: &str) -> String {
+        format!("{}{}{}", self.copy_prefix, selection, self.copy_suffix)
+    }
+
+    pub fn spawn_timeout(&self) -> Option<Duration> {
+        self.spawn_timeout_ms.map(Duration::from_millis)
+    }
 }
@@ -8,25 +8,102 @@
 // system = 'cmd to run'# can contain mozilla style query string (single %s)
 // keyword = 'k' # keyword used for mozilla style query strings
 // escape = true # only valid for keyword entries, determines if query string is escaped.
+// runs = ['a', 'b'] # names of other entries that are opened in order
 
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::SystemTime;
 use toml;
 
-use crate::{entry, icon};
+use crate::{entry, history, icon, search};
+
+// so that code outside of jolly, like benchmarks, can name the
+// entries of a store and the errors of building one
+pub use crate::entry::{EntryId, Error, StoreEntry};
 
 #[derive(Debug, Default, Clone)]
 pub struct Store {
     entries: Vec<entry::StoreEntry>,
+    // synthetic entry that is only shown when the query exactly
+    // matches its trigger
+    reserved: Option<(String, entry::EntryId)>,
+    search: search::SearchSettings,
 }
 
 impl Store {
+    // build the store from the entries of a config file. base_dir is
+    // the directory of the config file, if it has one
     pub fn build<'a, E: Iterator<Item = (String, toml::Value)>>(
         serialized_entries: E,
+        base_dir: Option<&Path>,
     ) -> Result<Store, entry::Error> {
-        Ok(Store {
+        let store = Store {
             entries: serialized_entries
-                .map(|(k, v)| entry::StoreEntry::from_value(k, v))
+                .map(|(k, v)| entry::StoreEntry::from_value(k, v, base_dir))
                 .collect::<Result<Vec<_>, _>>()?,
-        })
+            reserved: None,
+            search: Default::default(),
+        };
+
+        // check that every entry that runs other entries can be
+        // resolved, so that mistakes are reported when loading
+        for id in 0..store.entries.len() {
+            store.resolve_runs(id)?;
+        }
+
+        Ok(store)
+    }
+
+    // build a store from the text of a config file that only
+    // contains entries
+    pub fn parse(text: &str) -> Result<Store, entry::Error> {
+        match toml::from_str(text) {
+            Ok(toml::Value::Table(table)) => Store::build(table.into_iter(), None),
+            Ok(_) => Err(entry::Error::ParseError("Toml is not a Table".into())),
+            Err(e) => Err(entry::Error::ParseError(e.to_string())),
+        }
+    }
+
+    // settings that affect how matches are ranked. Files are looked
+    // up once when sorting by mtime is turned on, instead of on
+    // every search
+    pub fn set_search_settings(&mut self, settings: search::SearchSettings) {
+        let mtime = search::SortOrder::Mtime;
+        if settings.sort == mtime && self.search.sort != mtime {
+            for entry in &mut self.entries {
+                entry.set_mtime(entry.modified());
+            }
+        }
+        self.search = settings;
+    }
+
+    // prefer entries that were used often and recently, when their
+    // scores are equal. Score expressions can also use how much each
+    // entry was used
+    pub fn set_history(&mut self, history: &history::History, now: SystemTime) {
+        for entry in &mut self.entries {
+            entry.set_frecency(history.weight(entry.name(), now));
+        }
+    }
+
+    // add an entry that opens the config file at path when the query is trigger
+    pub fn add_config_entry(&mut self, trigger: &str, path: &Path) {
+        self.entries.push(entry::StoreEntry::config_entry(path));
+        self.reserved = Some((trigger.to_string(), self.entries.len() - 1));
+    }
+
+    fn is_reserved(&self, id: entry::EntryId) -> bool {
+        matches!(&self.reserved, Some((_, r)) if *r == id)
+    }
+
+    // all entries in file order, not including reserved entries
+    pub fn entries(&self) -> impl Iterator<Item = &entry::StoreEntry> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| !self.is_reserved(*id))
+            .map(|(_, e)| e)
     }
 
     pub fn get(&self, id: entry::EntryId) -> &entry::StoreEntry {
@@ -37,37 +114,189 @@ impl Store {
         &mut self.entries[id]
     }
 
+    // find an entry by its name, not including reserved entries
+    pub fn get_by_name(&self, name: &str) -> Option<entry::EntryId> {
+        self.entries
+            .iter()
+            .enumerate()
+            .find(|(id, e)| e.name() == name && !self.is_reserved(*id))
+            .map(|(id, _)| id)
+    }
+
+    // the entry with the given name, not including reserved entries
+    pub fn entry_by_name(&self, name: &str) -> Option<&entry::StoreEntry> {
+        self.get_by_name(name).map(|id| self.get(id))
+    }
+
+    // the entries that are opened when the entry is selected, in
+    // order. Entries that run other entries are expanded in place
+    pub fn resolve_runs(&self, id: entry::EntryId) -> Result<Vec<entry::EntryId>, entry::Error> {
+        let mut targets = Vec::new();
+        self.resolve_into(id, &mut vec![id], &mut targets)?;
+        Ok(targets)
+    }
+
+    // path holds the entries that are currently being expanded, to
+    // detect entries that end up running themselves
+    fn resolve_into(
+        &self,
+        id: entry::EntryId,
+        path: &mut Vec<entry::EntryId>,
+        targets: &mut Vec<entry::EntryId>,
+    ) -> Result<(), entry::Error> {
+        let entry = &self.entries[id];
+        for name in entry.runs() {
+            let target = self.get_by_name(name).ok_or_else(|| {
+                entry::Error::ParseError(format!(
+                    "Error with entry ['{}']: runs unknown entry '{name}'",
+                    entry.name()
+                ))
+            })?;
+
+            if path.contains(&target) {
+                let chain: Vec<_> = path
+                    .iter()
+                    .chain(std::iter::once(&target))
+                    .map(|id| format!("'{}'", self.entries[*id].name()))
+                    .collect();
+                return Err(entry::Error::ParseError(format!(
+                    "Error with entry ['{}']: runs itself: {}",
+                    self.entries[path[0]].name(),
+                    chain.join(" -> ")
+                )));
+            }
+
+            let target_entry = &self.entries[target];
+            if target_entry.kind() == "copy" {
+                return Err(entry::Error::ParseError(format!(
+                    "Error with entry ['{}']: cannot run copy entry '{name}'",
+                    entry.name()
+                )));
+            }
+
+            if target_entry.runs().is_empty() {
+                targets.push(target);
+            } else {
+                path.push(target);
+                self.resolve_into(target, path, targets)?;
+                path.pop();
+            }
+        }
+        Ok(())
+    }
+
     pub fn find_matches(&self, query: &str) -> Vec<entry::EntryId> {
-        // get indicies of all entries with scores greater than zero
-        let mut matches: Vec<_> = self
-            .entries
+        // the reserved entry only shows up for its exact trigger
+        if let Some((trigger, id)) = &self.reserved {
+            if query.trim() == trigger {
+                return vec![*id];
+            }
+        }
+
+        // get references to entries in sorted order
+        self.scored_matches(query).iter().map(|s| s.0).collect()
+    }
+
+    // the best matching entry whose keyword is the first word of
+    // the query, if any
+    pub fn active_keyword(&self, query: &str) -> Option<entry::EntryId> {
+        self.scored_matches(query)
+            .into_iter()
+            .map(|s| s.0)
+            .find(|id| self.entries[*id].keyword_active(query))
+    }
+
+    // score of every searchable entry in file order, including
+    // entries that do not match the query at all
+    pub fn scored_entries(&self, query: &str) -> Vec<(entry::EntryId, u32)> {
+        self.entries
             .iter()
-            .map(|entry| entry.score(query))
+            .map(|entry| entry.score_with(query, &self.search))
             .enumerate()
+            .filter(|s| !self.is_reserved(s.0))
+            .collect()
+    }
+
+    // entries that match the query (score greater than zero), best match first
+    pub fn scored_matches(&self, query: &str) -> Vec<(entry::EntryId, u32)> {
+        let mut matches: Vec<_> = self
+            .scored_entries(query)
+            .into_iter()
             .filter(|s| s.1 > 0)
             .rev() // flip order: now we prefer LAST entries in file
-            .collect::<Vec<_>>();
+            .collect();
 
-        // sort by score
-        matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        // sort by score, then by how well fuzzy matches match and
+        // how much each entry was used, optionally preferring
+        // recently modified files and then shorter names within a
+        // score. Since the sort is stable, ties keep their file order
+        let mtime = self.search.sort == search::SortOrder::Mtime;
+        let short = self.search.prefer_short_names;
+        matches.sort_by_cached_key(|&(id, score)| {
+            let entry = &self.entries[id];
+            // files that cannot be found sort after every file that can
+            let modified = if mtime { entry.mtime() } else { None };
+            let name_len = if short {
+                entry.format_name(query).chars().count()
+            } else {
+                0
+            };
+            let grade = if self.search.fuzzy {
+                entry.fuzzy_grade(query, &self.search)
+            } else {
+                0
+            };
+            let frecency = entry.frecency();
+            (
+                Reverse(score),
+                Reverse(grade),
+                Reverse(frecency),
+                Reverse(modified),
+                name_len,
+            )
+        });
 
-        // get references to entries in sorted order
-        matches.iter().map(|s| s.0).collect()
+        matches
+    }
+
+    // soft problems found in the entries of the store
+    pub fn warnings(&self) -> Vec<String> {
+        self.entries().flat_map(|e| e.warnings()).collect()
     }
 
     pub fn load_icons(&mut self, entries: &[entry::EntryId], icache: &mut icon::IconCache) {
         for e in entries {
             let entry = &mut self.entries[*e];
-            if !entry.icon_loaded() {
+            if entry.has_icon() && !entry.icon_loaded() {
                 if let Some(icon) = icache.get(&entry.icontype()) {
                     entry.icon(icon);
                 }
             }
         }
+
+        // entries let go of the icons the cache evicted, so that the
+        // size of the cache bounds the memory used by icons
+        let evicted: HashSet<_> = icache.take_evicted().into_iter().collect();
+        if !evicted.is_empty() {
+            for entry in &mut self.entries {
+                if evicted.contains(entry.icontype()) {
+                    entry.forget_icon();
+                }
+            }
+        }
+    }
+
+    // look up the icons of every entry, so that they are ready
+    // before the entries are first shown
+    pub fn preload_icons(&mut self, icache: &mut icon::IconCache) {
+        let all: Vec<_> = (0..self.entries.len())
+            .filter(|id| !self.is_reserved(*id))
+            .collect();
+        self.load_icons(&all, icache);
     }
 
     pub fn len(&self) -> usize {
-        self.entries.len()
+        self.entries.len() - self.reserved.is_some() as usize
     }
 }
 
@@ -76,13 +305,7 @@ pub mod tests {
     use super::*;
 
     pub fn parse_store(text: &str) -> Result<Store, entry::Error> {
-        let value: toml::Value = toml::from_str(text).unwrap();
-
-        if let toml::Value::Table(table) = value {
-            Store::build(table.into_iter())
-        } else {
-            panic!("Toml is not a Table")
-        }
+        Store::parse(text)
     }
 
     #[test]
@@ -145,6 +368,424 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn zero_score_excluded() {
+        let toml = r#"['foo']
+                      location = "test/location"
+
+                      ['bar']
+                      location = "test/location""#;
+
+        let store = parse_store(toml).unwrap();
+
+        // every entry is scored, even if it does not match
+        let scored = store.scored_entries("foo");
+        assert_eq!(scored.len(), 2);
+        assert!(scored[0].1 > 0);
+        assert_eq!(scored[1], (1, 0));
+
+        // but only entries with a non zero score are matches
+        assert_eq!(store.scored_matches("foo"), vec![scored[0]]);
+        assert_eq!(store.find_matches("foo"), vec![0]);
+
+        assert!(store.scored_entries("").iter().all(|s| s.1 == 0));
+        assert!(store.scored_matches("").is_empty());
+    }
+
+    #[test]
+    fn prefer_short_names() {
+        let toml = r#"['git']
+                      location = "test/location"
+
+                      ['github-actions']
+                      location = "test/location"
+
+                      ['gitk']
+                      location = "test/location"
+
+                      ['gitg']
+                      location = "test/location""#;
+
+        let mut store = parse_store(toml).unwrap();
+
+        let names = |store: &Store, query| {
+            store
+                .find_matches(query)
+                .into_iter()
+                .map(|id| store.get(id).format_name(query))
+                .collect::<Vec<_>>()
+        };
+
+        // by default, prefix matches tie and newer entries win
+        assert_eq!(
+            names(&store, "gi"),
+            ["gitg", "gitk", "github-actions", "git"]
+        );
+
+        store.set_search_settings(search::SearchSettings {
+            prefer_short_names: true,
+            ..Default::default()
+        });
+
+        // shorter names win within a score, then newer entries
+        assert_eq!(
+            names(&store, "gi"),
+            ["git", "gitg", "gitk", "github-actions"]
+        );
+
+        // but a better score still wins over a shorter name
+        assert_eq!(names(&store, "gitk"), ["gitk"]);
+        assert_eq!(names(&store, "git")[0], "git");
+    }
+
+    #[test]
+    fn mtime_sort() {
+        let dir = tempfile::tempdir().unwrap();
+        let touch = |name: &str, secs: u64| {
+            let path = dir.path().join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            file.set_modified(time).unwrap();
+            path.display().to_string()
+        };
+        let new = touch("new.txt", 2_000_000_000);
+        let old = touch("old.txt", 1_000_000_000);
+        let missing = dir.path().join("missing.txt").display().to_string();
+
+        let toml = format!(
+            r#"['report new']
+               location = '{new}'
+
+               ['report old']
+               location = '{old}'
+
+               ['report missing']
+               location = '{missing}'
+
+               ['report site']
+               url = 'https://example.com/report'
+
+               ['reporting']
+               location = '{new}'"#
+        );
+        let mut store = parse_store(&toml).unwrap();
+
+        let names = |store: &Store, query| {
+            store
+                .find_matches(query)
+                .into_iter()
+                .map(|id| store.get(id).name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&store, "report"),
+            [
+                "reporting",
+                "report site",
+                "report missing",
+                "report old",
+                "report new"
+            ]
+        );
+
+        store.set_search_settings(toml::from_str("sort = 'mtime'").unwrap());
+
+        // newest first, then entries without a file in file order
+        assert_eq!(
+            names(&store, "report"),
+            [
+                "reporting",
+                "report new",
+                "report old",
+                "report site",
+                "report missing"
+            ]
+        );
+
+        // but a better score still wins
+        assert_eq!(names(&store, "report old")[0], "report old");
+
+        // files are only looked up when the sort order is set, so
+        // the order holds until the store is loaded again
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(3_000_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+        assert_eq!(names(&store, "report")[1], "report new");
+        let mut store = parse_store(&toml).unwrap();
+        store.set_search_settings(toml::from_str("sort = 'mtime'").unwrap());
+        assert_eq!(names(&store, "report")[0], "report old");
+    }
+
+    #[test]
+    fn frecency_breaks_ties() {
+        let toml = r#"['mail work']
+                      location = 'a'
+
+                      ['mail']
+                      location = 'b'
+
+                      ['mailbox']
+                      location = 'c'
+                      keyword = 'mail'"#;
+        let mut store = parse_store(toml).unwrap();
+        let names = |store: &Store, query| {
+            store
+                .find_matches(query)
+                .into_iter()
+                .map(|id| store.get(id).name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&store, "ma"), ["mailbox", "mail", "mail work"]);
+
+        let now = SystemTime::now();
+        let mut history = history::History::default();
+        for _ in 0..50 {
+            history.record("mail work", now);
+        }
+        history.record("mail", now);
+        store.set_history(&history, now);
+
+        // entries with the same score are ordered by use
+        assert_eq!(names(&store, "ma"), ["mail work", "mail", "mailbox"]);
+
+        // but no amount of use beats a better score or a keyword match
+        assert_eq!(names(&store, "mail"), ["mailbox", "mail", "mail work"]);
+    }
+
+    #[test]
+    fn score_expr_ordering() {
+        let toml = r#"['work log']
+                      location = "test/location"
+
+                      ['notes']
+                      location = "test/location"
+                      tags = ['work']
+
+                      ['workshop']
+                      location = "test/location""#;
+
+        let mut store = parse_store(toml).unwrap();
+
+        let names = |store: &Store, query| {
+            store
+                .find_matches(query)
+                .into_iter()
+                .map(|id| store.get(id).format_name(query))
+                .collect::<Vec<_>>()
+        };
+        let set_expr = |store: &mut Store, expr: &str| {
+            let settings: search::SearchSettings =
+                toml::from_str(&format!("score_expr = '{expr}'")).unwrap();
+            store.set_search_settings(settings);
+        };
+
+        // built in scoring prefers full tag matches
+        assert_eq!(names(&store, "work"), ["notes", "workshop", "work log"]);
+
+        set_expr(&mut store, "partial_name * 10 + full_tag");
+        assert_eq!(names(&store, "work"), ["workshop", "work log", "notes"]);
+
+        // tokens can be used to require that every token matches
+        assert_eq!(names(&store, "work log"), ["work log", "workshop", "notes"]);
+        set_expr(&mut store, "partial_name + partial_tag - tokens + 1");
+        assert_eq!(names(&store, "work log"), ["work log"]);
+
+        // entries that were opened before can be scored higher
+        set_expr(&mut store, "partial_name + frecency");
+        assert_eq!(names(&store, "work"), ["workshop", "work log"]);
+        let now = SystemTime::now();
+        let mut history = history::History::default();
+        history.record("notes", now);
+        store.set_history(&history, now);
+        assert_eq!(names(&store, "work"), ["notes", "workshop", "work log"]);
+
+        // expressions are checked when the settings are loaded
+        let invalid: Result<search::SearchSettings, _> =
+            toml::from_str("score_expr = 'full_name +'");
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn priority_outranks_equal_score() {
+        let toml = r#"['foo']
+                      location = "test/location"
+                      priority = 1
+
+                      ['foo2']
+                      location = "test/location""#;
+
+        let store = parse_store(toml).unwrap();
+
+        // both names match the query equally well, so without the
+        // priority the newer entry would be first
+        let names: Vec<_> = store
+            .find_matches("fo")
+            .into_iter()
+            .map(|id| store.get(id).format_name("fo"))
+            .collect();
+        assert_eq!(names, ["foo", "foo2"]);
+    }
+
+    #[test]
+    fn keyword_modes() {
+        let mut store = parse_store(
+            r#"['github %s']
+               url = 'https://github.com/search?q=%s'
+               keyword = 'g'
+
+               ['google %s']
+               url = 'https://google.com/search?q=%s'
+               keyword = 'g'
+
+               ['github']
+               url = 'https://github.com'"#,
+        )
+        .unwrap();
+        let names = |store: &Store| -> Vec<_> {
+            store
+                .find_matches("g github")
+                .into_iter()
+                .map(|id| store.get(id).name().to_string())
+                .collect()
+        };
+
+        // by default, every keyword match scores the same, so the text
+        // of the query cannot tell the keyword entries apart
+        assert_eq!(names(&store), ["google %s", "github %s", "github"]);
+
+        // added together, the entry whose name also matches wins
+        store.set_search_settings(search::SearchSettings {
+            keyword_mode: search::KeywordMode::Add,
+            ..Default::default()
+        });
+        assert_eq!(names(&store), ["github %s", "google %s", "github"]);
+    }
+
+    #[test]
+    fn active_keyword() {
+        let store = parse_store(
+            r#"['search']
+               url = 'https://example.com/?q=%s'
+               keyword = 'ex'
+
+               ['exit']
+               system = 'exit'
+
+               ['translate']
+               url = 'https://example.com/t?q=%s'
+               keyword = 'Tr'"#,
+        )
+        .unwrap();
+
+        assert_eq!(store.active_keyword("ex foo"), Some(0));
+        assert_eq!(store.active_keyword("ex"), Some(0));
+        // the keyword has to be the whole first word
+        assert_eq!(store.active_keyword("exi"), None);
+        assert_eq!(store.active_keyword("foo ex"), None);
+        assert_eq!(store.active_keyword(""), None);
+        // same case rules as the rest of the query
+        assert_eq!(store.active_keyword("tr hallo"), Some(2));
+        assert_eq!(store.active_keyword("TR Hallo"), None);
+    }
+
+    #[test]
+    fn runs_resolution() {
+        let store = parse_store(
+            r#"['editor']
+               location = 'a'
+
+               ['server']
+               system = 'b'
+
+               ['start']
+               runs = ['editor', 'server']
+
+               ['everything']
+               runs = ['start', 'editor']"#,
+        )
+        .unwrap();
+
+        let names = |id| {
+            store
+                .resolve_runs(id)
+                .unwrap()
+                .into_iter()
+                .map(|t| store.get(t).name())
+                .collect::<Vec<_>>()
+        };
+        let id = |name| store.get_by_name(name).unwrap();
+
+        assert_eq!(names(id("start")), ["editor", "server"]);
+        assert_eq!(names(id("everything")), ["editor", "server", "editor"]);
+        assert!(names(id("editor")).is_empty());
+        assert_eq!(store.get(id("start")).kind(), "runs");
+        assert_eq!(store.get_by_name("missing"), None);
+
+        let err = parse_store("['a']\nruns = ['b']").unwrap_err();
+        assert!(err.to_string().contains("unknown entry 'b'"), "{err}");
+
+        let err = parse_store("['a']\nruns = ['c']\n['c']\ncopy = 'x'").unwrap_err();
+        assert!(err.to_string().contains("copy entry 'c'"), "{err}");
+
+        // runs cannot be combined with another target
+        assert!(parse_store("['a']\nlocation = 'x'\nruns = ['a']").is_err());
+    }
+
+    #[test]
+    fn entry_by_name() {
+        let store = Store::parse(
+            r#"['search %s']
+               url = 'https://example.com/?q=%s'
+               keyword = 'sr'
+               escape = true
+
+               ['notes']
+               location = 'notes.txt'"#,
+        )
+        .unwrap();
+
+        let entry: &StoreEntry = store.entry_by_name("search %s").unwrap();
+        assert_eq!(entry.format_name("sr rust lang"), "search rust lang");
+        assert_eq!(
+            entry.format_selection("sr rust lang"),
+            "https://example.com/?q=rust%20lang"
+        );
+
+        let entry = store.entry_by_name("notes").unwrap();
+        assert_eq!(entry.format_selection("notes"), "notes.txt");
+        assert!(store.entry_by_name("missing").is_none());
+
+        assert!(Store::parse("['broken'").is_err());
+        assert!(Store::parse("bare_key = 42").is_err());
+    }
+
+    #[test]
+    fn runs_loop_detection() {
+        let err = parse_store("['a']\nruns = ['a']").unwrap_err();
+        assert!(err.to_string().contains("'a' -> 'a'"), "{err}");
+
+        let err = parse_store(
+            r#"['a']
+               runs = ['b']
+
+               ['b']
+               runs = ['c']
+
+               ['c']
+               runs = ['a']"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("'a' -> 'b' -> 'c' -> 'a'"),
+            "{err}"
+        );
+    }
+
     #[test]
     fn bare_keys_not_allowed() {
         let toml = r#"bare_key = 42"#;
@@ -156,6 +797,72 @@ pub mod tests {
         )
     }
 
+    #[test]
+    fn entries_without_icons() {
+        let mut store = parse_store(
+            r#"['plain']
+               location = 'a'
+               icon = false
+
+               ['named none']
+               location = 'b'
+               icon = 'none'
+
+               ['shown']
+               location = 'c'"#,
+        )
+        .unwrap();
+        assert!(parse_store("['foo']\nlocation = 'a'\nicon = true").is_err());
+
+        let (cmd, commands) = std::sync::mpsc::channel();
+        let mut icache = icon::IconCache::new();
+        icache.set_cmd(cmd);
+
+        let ids: Vec<_> = (0..store.entries.len()).collect();
+        store.load_icons(&ids, &mut icache);
+
+        // only the entry with an icon asks the icon worker for one
+        let requested: Vec<_> = commands.try_iter().collect();
+        assert_eq!(requested.len(), 1, "{requested:?}");
+        assert!(matches!(
+            &requested[0],
+            icon::IconCommand::LoadIcon(it) if it == store.entries[2].icontype()
+        ));
+
+        assert!(!store.entries[0].has_icon());
+        assert!(!store.entries[1].has_icon());
+        assert!(store.entries[2].has_icon());
+        assert!(!store.entries[0].icon_loaded());
+    }
+
+    #[test]
+    fn evicted_icons_are_released() {
+        let mut store = parse_store(
+            r#"['a']
+               location = 'a'
+               icon = 'a.png'
+
+               ['b']
+               location = 'b'
+               icon = 'b.png'"#,
+        )
+        .unwrap();
+        let mut icache = icon::IconCache::new();
+        icache.set_capacity(1);
+
+        let icon = || icon::Icon::from_pixels(1, 1, &[0, 0, 0, 255]);
+        icache.add_icon(store.entries[0].icontype().clone(), icon());
+        store.load_icons(&[0], &mut icache);
+        assert!(store.entries[0].icon_loaded());
+
+        // loading the icon of b evicts the icon of a, and nothing
+        // holds on to it afterwards
+        icache.add_icon(store.entries[1].icontype().clone(), icon());
+        store.load_icons(&[1], &mut icache);
+        assert!(store.entries[1].icon_loaded());
+        assert!(!store.entries[0].icon_loaded());
+    }
+
     #[test]
     fn parse_error() {
         let tests = [
@@ -4,11 +4,14 @@ use iced::application;
 use iced::overlay::menu;
 use iced::widget::button;
 use iced::widget::container;
+use iced::widget::rule;
+use iced::widget::scrollable;
 use iced::widget::text;
 use iced::widget::text_input;
 use serde;
 use serde::de::{self, DeserializeSeed, Deserializer, Error, IntoDeserializer, MapAccess, Visitor};
-use serde::Deserialize;
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use toml;
 
@@ -19,17 +22,42 @@ pub enum DefaultTheme {
     Dark,
 }
 
+// environment variable that forces the base theme, for systems where
+// dark mode detection is wrong
+pub const THEME_ENV: &str = "JOLLY_THEME";
+
+// color of errors and warnings, in every theme
+const DANGER_COLOR: &str = "#D64541";
+
+impl DefaultTheme {
+    // use the theme named by the environment variable if it is set
+    // to a valid theme, otherwise fall back to detect
+    fn from_env_or(value: Option<&str>, detect: impl FnOnce() -> Self) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("dark") => DefaultTheme::Dark,
+            Some("light") => DefaultTheme::Light,
+            _ => detect(),
+        }
+    }
+
+    fn detect() -> Self {
+        if dark_light::detect() == dark_light::Mode::Dark {
+            DefaultTheme::Dark
+        } else {
+            DefaultTheme::Light
+        }
+    }
+}
+
 impl Default for DefaultTheme {
     fn default() -> Self {
         use lazy_static::lazy_static;
         // store default theme in lazy static to avoid generating it more than once
         lazy_static! {
-            static ref DEFAULT_THEME: DefaultTheme =
-                if dark_light::detect() == dark_light::Mode::Dark {
-                    DefaultTheme::Dark
-                } else {
-                    DefaultTheme::Light
-                };
+            static ref DEFAULT_THEME: DefaultTheme = DefaultTheme::from_env_or(
+                std::env::var(THEME_ENV).ok().as_deref(),
+                DefaultTheme::detect
+            );
         }
         *DEFAULT_THEME
     }
@@ -46,19 +74,40 @@ pub struct Theme {
     pub text_color: ui::Color,
     pub accent_color: ui::Color,
     pub selected_text_color: ui::Color,
+    // background of the selected entry. Uses the accent color if unset
+    pub highlight_color: Option<ui::Color>,
 }
 
 impl Theme {
+    pub fn highlight_color(&self) -> ui::Color {
+        self.highlight_color
+            .clone()
+            .unwrap_or_else(|| self.accent_color.clone())
+    }
+
+    // the theme as a [config.ui.theme] table for jolly.toml. Every
+    // color is written out, so the table does not depend on the
+    // base theme that is detected
+    pub fn to_config(&self) -> String {
+        let colors = toml::to_string(self).expect("theme colors are always valid toml");
+        format!("[config.ui.theme]\n{colors}")
+    }
+
     fn palette(&self) -> iced::theme::palette::Palette {
         iced::theme::palette::Palette {
             background: self.background_color.clone().into(),
             text: self.text_color.clone().into(),
             primary: self.accent_color.clone().into(),
             success: self.accent_color.clone().into(),
-            danger: self.accent_color.clone().into(),
+            danger: ui::Color::from_str(DANGER_COLOR).into(),
         }
     }
 
+    // color used to draw attention to errors and warnings
+    pub fn danger_color(&self) -> iced::Color {
+        self.extended_palette().danger.base.color
+    }
+
     fn extended_palette(&self) -> iced::theme::palette::Extended {
         iced::theme::palette::Extended::generate(self.palette())
     }
@@ -104,6 +153,8 @@ impl<'de> Visitor<'de> for DefaultTheme {
             AccentColor,
             #[serde(rename = "selected_text_color")]
             SelectedTextColor,
+            #[serde(rename = "highlight_color")]
+            HighlightColor,
             #[serde(other)]
             Other,
         }
@@ -113,6 +164,7 @@ impl<'de> Visitor<'de> for DefaultTheme {
         let mut text_visited = false;
         let mut selected_text_visited = false;
         let mut accent_visited = false;
+        let mut highlight_visited = false;
         while let Some(key) = map.next_key()? {
             match key {
                 Field::BackgroundColor => {
@@ -143,6 +195,13 @@ impl<'de> Visitor<'de> for DefaultTheme {
                     selected_text_visited = true;
                     theme.selected_text_color = map.next_value()?;
                 }
+                Field::HighlightColor => {
+                    if highlight_visited {
+                        return Err(de::Error::duplicate_field("highlight_color"));
+                    }
+                    highlight_visited = true;
+                    theme.highlight_color = Some(map.next_value()?);
+                }
                 Field::Other => {}
             }
         }
@@ -174,6 +233,26 @@ impl<'de> de::Deserialize<'de> for Theme {
     }
 }
 
+// the highlight color is only written if it was set, so that it
+// keeps following the accent color
+impl Serialize for Theme {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut theme = serializer.serialize_struct("Theme", 5)?;
+        theme.serialize_field("background_color", &self.background_color)?;
+        theme.serialize_field("text_color", &self.text_color)?;
+        theme.serialize_field("accent_color", &self.accent_color)?;
+        theme.serialize_field("selected_text_color", &self.selected_text_color)?;
+        match &self.highlight_color {
+            Some(color) => theme.serialize_field("highlight_color", color)?,
+            None => theme.skip_field("highlight_color")?,
+        }
+        theme.end()
+    }
+}
+
 // convert default theme enum into appropriate jolly default theme
 impl From<DefaultTheme> for Theme {
     fn from(f: DefaultTheme) -> Self {
@@ -183,6 +262,7 @@ impl From<DefaultTheme> for Theme {
                 text_color: ui::Color::from_str("black"),
                 accent_color: platform::accent_color(),
                 selected_text_color: ui::Color::from_str("white"),
+                highlight_color: None,
             },
 
             DefaultTheme::Dark => Theme {
@@ -190,6 +270,7 @@ impl From<DefaultTheme> for Theme {
                 text_color: ui::Color::from_str("B3B3B3"),
                 accent_color: platform::accent_color(),
                 selected_text_color: ui::Color::from_str("black"),
+                highlight_color: None,
             },
         }
     }
@@ -251,6 +332,8 @@ pub enum ButtonStyle {
     #[default]
     Transparent,
     Selected,
+    // selected, with a highlight color other than the theme's
+    Highlight(iced::Color),
 }
 
 impl button::StyleSheet for Theme {
@@ -267,16 +350,17 @@ impl button::StyleSheet for Theme {
             },
 
             ButtonStyle::Selected => {
-                let accent_color: iced::Color = self.accent_color.clone().into();
-                button::Appearance {
-                    shadow_offset: iced::Vector::default(),
-                    text_color: self.selected_text_color.clone().into(),
-                    background: Some(accent_color.into()),
-                    border_radius: 5.0.into(),
-                    border_width: 1.0,
-                    border_color: iced::Color::TRANSPARENT,
-                }
+                self.active(&ButtonStyle::Highlight(self.highlight_color().into()))
             }
+
+            ButtonStyle::Highlight(highlight_color) => button::Appearance {
+                shadow_offset: iced::Vector::default(),
+                text_color: self.selected_text_color.clone().into(),
+                background: Some((*highlight_color).into()),
+                border_radius: 5.0.into(),
+                border_width: 1.0,
+                border_color: iced::Color::TRANSPARENT,
+            },
         }
     }
 }
@@ -287,6 +371,8 @@ pub enum ContainerStyle {
     Transparent,
     Selected,
     Error,
+    // outlined label inside an entry
+    Badge,
 }
 
 impl container::StyleSheet for Theme {
@@ -296,10 +382,10 @@ impl container::StyleSheet for Theme {
             ContainerStyle::Transparent => iced::Theme::default().appearance(&Default::default()),
 
             ContainerStyle::Selected => {
-                let accent_color: iced::Color = self.accent_color.clone().into();
+                let highlight_color: iced::Color = self.highlight_color().into();
                 container::Appearance {
                     text_color: Some(self.selected_text_color.clone().into()),
-                    background: Some(accent_color.into()),
+                    background: Some(highlight_color.into()),
                     border_radius: 5.0.into(),
                     border_width: 1.0,
                     border_color: iced::Color::TRANSPARENT,
@@ -313,9 +399,70 @@ impl container::StyleSheet for Theme {
                     background: Some(bg_color.into()),
                     border_radius: 5.0.into(),
                     border_width: 2.0,
-                    border_color: ui::Color::from_str("#D64541").into(),
+                    border_color: self.danger_color(),
                 }
             }
+
+            ContainerStyle::Badge => container::Appearance {
+                text_color: None,
+                background: None,
+                border_radius: 4.0.into(),
+                border_width: 1.0,
+                border_color: self.extended_palette().background.strong.color,
+            },
+        }
+    }
+}
+
+// separators between results
+impl rule::StyleSheet for Theme {
+    type Style = ();
+
+    fn appearance(&self, _style: &Self::Style) -> rule::Appearance {
+        let palette = self.extended_palette();
+
+        rule::Appearance {
+            color: palette.background.strong.color,
+            width: 1,
+            radius: 0.0.into(),
+            fill_mode: rule::FillMode::Full,
+        }
+    }
+}
+
+// scrollbar of results that do not all fit
+impl scrollable::StyleSheet for Theme {
+    type Style = ();
+
+    fn active(&self, _style: &Self::Style) -> scrollable::Scrollbar {
+        let palette = self.extended_palette();
+
+        scrollable::Scrollbar {
+            background: Some(palette.background.weak.color.into()),
+            border_radius: 2.0.into(),
+            border_width: 0.0,
+            border_color: iced::Color::TRANSPARENT,
+            scroller: scrollable::Scroller {
+                color: palette.background.strong.color,
+                border_radius: 2.0.into(),
+                border_width: 0.0,
+                border_color: iced::Color::TRANSPARENT,
+            },
+        }
+    }
+
+    fn hovered(&self, style: &Self::Style, is_mouse_over_scrollbar: bool) -> scrollable::Scrollbar {
+        let active = self.active(style);
+        if !is_mouse_over_scrollbar {
+            return active;
+        }
+
+        scrollable::Scrollbar {
+            scroller: scrollable::Scroller {
+                color: self.extended_palette().primary.base.color,
+                ..active.scroller
+            },
+            ..active
         }
     }
 }
@@ -409,6 +556,7 @@ mod tests {
             text_color: ui::Color::from_str("orange"),
             accent_color: ui::Color::from_str("yellow"),
             selected_text_color: ui::Color::from_str("green"),
+            highlight_color: Some(ui::Color::from_str("blue")),
         };
 
         let toml = r#"
@@ -416,11 +564,37 @@ mod tests {
 		      text_color = "orange"
 		      accent_color = "yellow"
 		      selected_text_color = "green"
+		      highlight_color = "blue"
                    "#;
 
         assert_eq!(custom, toml::from_str(toml).unwrap());
     }
 
+    #[test]
+    fn dumped_theme_reparses() {
+        let parse = |text: &str| -> Theme {
+            let value: toml::Value = toml::from_str(text).unwrap();
+            value["config"]["ui"]["theme"].clone().try_into().unwrap()
+        };
+
+        for theme in [
+            Theme::default(),
+            DefaultTheme::Dark.into(),
+            DefaultTheme::Light.into(),
+            toml::from_str(r##"background_color = "#12345678""##).unwrap(),
+            toml::from_str(r#"highlight_color = "orange""#).unwrap(),
+        ] {
+            let dump = theme.to_config();
+            assert!(dump.starts_with("[config.ui.theme]\n"), "{dump}");
+            assert_eq!(parse(&dump), theme, "{dump}");
+        }
+
+        let dark: Theme = DefaultTheme::Dark.into();
+        let dump = dark.to_config();
+        assert!(dump.contains(r##"background_color = "#202225""##), "{dump}");
+        assert!(!dump.contains("highlight_color"), "{dump}");
+    }
+
     #[test]
     fn set_dark_theme() {
         let toml = r#"
@@ -441,6 +615,29 @@ mod tests {
         assert_eq!(theme, toml::from_str(toml).unwrap());
     }
 
+    #[test]
+    fn env_forces_base_theme() {
+        let detect = || -> DefaultTheme { panic!("detection should be skipped") };
+        assert_eq!(
+            DefaultTheme::from_env_or(Some("dark"), detect),
+            DefaultTheme::Dark
+        );
+        assert_eq!(
+            DefaultTheme::from_env_or(Some(" Light "), detect),
+            DefaultTheme::Light
+        );
+
+        // unset or unknown values use detection
+        assert_eq!(
+            DefaultTheme::from_env_or(None, || DefaultTheme::Dark),
+            DefaultTheme::Dark
+        );
+        assert_eq!(
+            DefaultTheme::from_env_or(Some("blue"), || DefaultTheme::Light),
+            DefaultTheme::Light
+        );
+    }
+
     #[test]
     fn override_custom_default() {
         let toml = r#"
@@ -455,6 +652,37 @@ mod tests {
         assert_eq!(theme, toml::from_str(toml).unwrap());
     }
 
+    #[test]
+    fn highlight_color_defaults_to_accent() {
+        use iced::widget::button::StyleSheet;
+
+        let selected = |theme: &Theme| StyleSheet::active(theme, &ButtonStyle::Selected).background;
+        let background = |color| Some(iced::Background::Color(ui::Color::from_str(color).into()));
+
+        let theme: Theme = toml::from_str(r#"accent_color = "purple""#).unwrap();
+        assert_eq!(theme.highlight_color, None);
+        assert_eq!(theme.highlight_color(), ui::Color::from_str("purple"));
+        assert_eq!(selected(&theme), background("purple"));
+
+        let toml = r#"
+		      accent_color = "blue"
+		      highlight_color = "green"
+                   "#;
+        let theme: Theme = toml::from_str(toml).unwrap();
+        assert_eq!(theme.highlight_color(), ui::Color::from_str("green"));
+        assert_eq!(theme.accent_color, ui::Color::from_str("blue"));
+        assert_eq!(selected(&theme), background("green"));
+
+        // the focus ring still uses the accent color
+        use iced::widget::text_input::StyleSheet as TextStyleSheet;
+        let accent: iced::Color = ui::Color::from_str("blue").into();
+        assert_eq!(theme.focused(&()).border_color, accent);
+
+        assert!(
+            toml::from_str::<Theme>("highlight_color = 'green'\nhighlight_color = 'red'").is_err()
+        );
+    }
+
     #[test]
     fn accent_color_used_for_theme() {
         // test that the major accent color we use actually shows up in the theme.
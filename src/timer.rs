@@ -0,0 +1,131 @@
+// delivers messages to the application after a delay. A single thread
+// waits for every pending timer, so that waiting never holds up one
+// of the executor's threads
+
+use crate::Message;
+use iced::futures::channel::mpsc;
+use iced::futures::{executor, SinkExt};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+// a message, and when to deliver it
+type Timer = (Instant, Message);
+
+// the application's side of the timer thread. Timers that are set
+// before the thread has started are kept until it has
+#[derive(Debug, Default)]
+pub struct Timers {
+    cmd: Option<std::sync::mpsc::Sender<Timer>>,
+    queued: Vec<Timer>,
+}
+
+impl Timers {
+    // deliver message once delay has passed
+    pub fn after(&mut self, delay: Duration, message: Message) {
+        let timer = (Instant::now() + delay, message);
+        match &self.cmd {
+            Some(cmd) => {
+                if cmd.send(timer).is_err() {
+                    ::log::warn!("Could not set timer, the timer thread has stopped");
+                }
+            }
+            None => self.queued.push(timer),
+        }
+    }
+
+    pub fn set_cmd(&mut self, cmd: std::sync::mpsc::Sender<Timer>) {
+        for timer in self.queued.drain(..) {
+            let _ = cmd.send(timer);
+        }
+        self.cmd = Some(cmd);
+    }
+}
+
+pub fn timer_worker() -> mpsc::Receiver<Message> {
+    let (mut output, sub_stream) = mpsc::channel(100);
+
+    std::thread::spawn(move || {
+        let (input, timers) = std::sync::mpsc::channel();
+
+        // send the application a channel to set timers with
+        output
+            .try_send(Message::StartedTimer(input))
+            .expect("Could not send timer back to application");
+
+        run_timer(timers, output);
+    });
+    sub_stream
+}
+
+// deliver timers until either channel is closed
+fn run_timer(timers: std::sync::mpsc::Receiver<Timer>, mut output: mpsc::Sender<Message>) {
+    let mut pending: Vec<Timer> = Vec::new();
+    loop {
+        let now = Instant::now();
+        let (due, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(at, _)| *at <= now);
+        pending = waiting;
+        for (_, message) in due {
+            if executor::block_on(output.send(message)).is_err() {
+                return;
+            }
+        }
+
+        // sleep until the next timer is due, or a new one is set
+        let next = pending.iter().map(|(at, _)| *at).min();
+        let timer = match next {
+            Some(at) => timers.recv_timeout(at.saturating_duration_since(now)),
+            None => timers.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match timer {
+            Ok(timer) => pending.push(timer),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // the messages of the timers that are waiting for the timer
+    // thread to start
+    pub(crate) fn queued(timers: &Timers) -> Vec<(Duration, &Message)> {
+        let now = Instant::now();
+        timers
+            .queued
+            .iter()
+            .map(|(at, message)| (at.saturating_duration_since(now), message))
+            .collect()
+    }
+
+    #[test]
+    fn timers_are_delivered_in_order() {
+        let (input, timers) = std::sync::mpsc::channel();
+        let (output, mut messages) = mpsc::channel(10);
+
+        let mut app = Timers::default();
+        app.after(Duration::from_millis(40), Message::ToastExpired("b".into()));
+        assert_eq!(queued(&app).len(), 1);
+        app.set_cmd(input);
+        assert!(queued(&app).is_empty());
+        app.after(Duration::from_millis(10), Message::ToastExpired("a".into()));
+
+        let start = Instant::now();
+        let worker = std::thread::spawn(move || run_timer(timers, output));
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            match executor::block_on(iced::futures::StreamExt::next(&mut messages)) {
+                Some(Message::ToastExpired(msg)) => received.push(msg),
+                other => panic!("unexpected message {other:?}"),
+            }
+        }
+        assert_eq!(received, ["a", "b"]);
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        // the thread stops once the application stops setting timers
+        drop(app);
+        worker.join().unwrap();
+    }
+}
@@ -1,12 +1,15 @@
 // eventually the jolly main window logic will move here out of main
 // but for now it will just hold settings.
 
-use crate::{entry, icon, theme};
+use crate::{entry, icon, platform, theme};
 use csscolorparser;
 use iced;
 use serde;
 use serde::de::value::{StrDeserializer, StringDeserializer};
 use serde::Deserialize;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 #[serde(default)]
@@ -21,7 +24,128 @@ pub struct UISettings {
     pub search: SearchSettings,
     pub entry: entry::EntrySettings,
     pub max_results: usize,
+    // results shown before the rest have to be scrolled to
+    pub visible_results: usize,
     pub icon: icon::IconSettings,
+    pub focus_delay_ms: i64,
+    pub show_config_path: bool,
+    pub empty_enter: EmptyEnter,
+    pub position: WindowPosition,
+    pub preview: bool,
+    // move between the search box and the results with the arrow keys
+    pub box_results_nav: bool,
+    // moving past either end of the results wraps to the other end
+    pub wrap_selection: bool,
+    // results moved by page up and page down, instead of the number
+    // of visible results
+    pub page_size: Option<usize>,
+    // only select results by hovering once the mouse has moved
+    pub hover_requires_motion: bool,
+    // start with the text on the clipboard as the query
+    pub seed_from_selection: bool,
+    // keep the window above other windows
+    pub always_on_top: bool,
+    // include the number of results in the window title
+    pub title_shows_count: bool,
+    // the tallest the window may grow
+    pub max_height: Option<MaxHeight>,
+    // margin around everything in the window
+    pub padding: u16,
+    // pressing enter again chooses the next action of the entry
+    pub enter_cycles_actions: bool,
+    // slide new results into place
+    pub animations: bool,
+    // show long queries with their middle cut out, instead of
+    // scrolling the search box
+    pub ellipsize_query: bool,
+    // match query words against the characters of names in order,
+    // when they do not appear in the name as a whole
+    pub fuzzy: bool,
+}
+
+// cap on the height of the window, either in pixels or as a
+// percentage of the height of the screen
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MaxHeight {
+    Pixels(u32),
+    Percent(f32),
+}
+
+impl MaxHeight {
+    // parse a height like "400", "400px" or "60%"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let error = || format!("Cannot parse height `{text}`, expected pixels or a percentage");
+        if let Some(percent) = text.strip_suffix('%') {
+            let percent: f32 = percent.trim().parse().map_err(|_| error())?;
+            if percent.is_nan() || percent <= 0.0 || percent > 100.0 {
+                return Err(format!("height `{text}` must be between 0% and 100%"));
+            }
+            Ok(Self::Percent(percent))
+        } else {
+            let pixels = text.strip_suffix("px").unwrap_or(text).trim();
+            pixels.parse().map(Self::Pixels).map_err(|_| error())
+        }
+    }
+
+    // the height in pixels on a screen of the given height. A
+    // percentage cannot be resolved without knowing the screen
+    pub fn resolve(&self, screen_height: Option<u32>) -> Option<u32> {
+        match *self {
+            Self::Pixels(pixels) => Some(pixels),
+            Self::Percent(percent) => {
+                screen_height.map(|h| (h as f32 * percent / 100.0).round() as u32)
+            }
+        }
+    }
+}
+
+// heights can be given as a bare number of pixels, or as a string
+impl<'de> serde::Deserialize<'de> for MaxHeight {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Pixels(u32),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Pixels(pixels) => Ok(Self::Pixels(pixels)),
+            Raw::Text(text) => Self::parse(&text).map_err(D::Error::custom),
+        }
+    }
+}
+
+// where the jolly window is placed when it opens
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowPosition {
+    // let the window manager decide
+    #[default]
+    Default,
+    // centered on the primary monitor
+    Center,
+    // centered on the monitor under the mouse cursor
+    Cursor,
+}
+
+// what pressing enter does when there is no result selected
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyEnter {
+    // close the jolly window
+    #[default]
+    Close,
+    // do nothing
+    Ignore,
+    // search for the query using the fallback url
+    Fallback,
 }
 
 #[derive(serde::Deserialize, Debug, Clone, PartialEq, Default)]
@@ -51,6 +175,45 @@ impl UISettings {
         self.entry.propagate(&self.common);
         self.search.propagate(&self.common);
     }
+
+    // problems with the ui settings, once they are propagated
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.width == 0 {
+            problems.push("config.ui.width must be greater than 0".to_string());
+        }
+        if self.max_results == 0 {
+            problems.push("config.ui.max_results must be at least 1, using 1".to_string());
+        }
+        if self.visible_results == 0 {
+            problems.push("config.ui.visible_results must be at least 1, using 1".to_string());
+        }
+        if self.page_size == Some(0) {
+            problems.push("config.ui.page_size must be at least 1, using 1".to_string());
+        }
+        if self.search.common.text_size() == 0 {
+            problems.push("config.ui.search.text_size must be greater than 0".to_string());
+        }
+        problems.extend(self.entry.validate());
+
+        // colors that are drawn on top of each other have to differ
+        let theme = &self.theme;
+        if theme.text_color == theme.background_color {
+            problems.push(
+                "config.ui.theme.text_color is the same as background_color, text would not be visible"
+                    .to_string(),
+            );
+        }
+        if theme.selected_text_color == theme.highlight_color() {
+            problems.push(
+                "config.ui.theme.selected_text_color is the same as the highlight color, the selected entry would not be visible"
+                    .to_string(),
+            );
+        }
+
+        problems.extend(icon::validate_settings(&self.icon));
+        problems
+    }
 }
 
 impl Default for UISettings {
@@ -62,7 +225,99 @@ impl Default for UISettings {
             search: SearchSettings::default(),
             entry: Default::default(),
             max_results: 5,
+            visible_results: 5,
             icon: Default::default(),
+            focus_delay_ms: 0,
+            show_config_path: false,
+            empty_enter: Default::default(),
+            position: Default::default(),
+            preview: false,
+            box_results_nav: false,
+            wrap_selection: false,
+            page_size: None,
+            hover_requires_motion: false,
+            seed_from_selection: false,
+            always_on_top: false,
+            title_shows_count: false,
+            max_height: None,
+            padding: 0,
+            enter_cycles_actions: false,
+            animations: false,
+            ellipsize_query: false,
+            fuzzy: true,
+        }
+    }
+}
+
+impl UISettings {
+    // how long to wait before stealing focus on startup. A negative
+    // delay means that we never steal focus
+    pub fn focus_delay(&self) -> Option<Duration> {
+        u64::try_from(self.focus_delay_ms)
+            .ok()
+            .map(Duration::from_millis)
+    }
+
+    // the tallest the window may grow, given the height of the
+    // screen it is on. The window is never shorter than the search box
+    pub fn max_height(&self, screen_height: Option<u32>) -> Option<u32> {
+        let max_height = self.max_height?;
+        match max_height.resolve(screen_height) {
+            Some(height) => Some(height.max(self.starting_height())),
+            None => {
+                ::log::warn!("Could not find screen height, ignoring config.ui.max_height");
+                None
+            }
+        }
+    }
+
+    // warning for a max_height that is a percentage of a screen whose
+    // height could not be found
+    pub fn max_height_warning(&self, screen_height: Option<u32>) -> Option<String> {
+        let max_height = self.max_height?;
+        max_height.resolve(screen_height).is_none().then(|| {
+            "config.ui.max_height is a percentage, but the height of the screen could not be found, so it is ignored".to_string()
+        })
+    }
+
+    // how the query is displayed in the search box
+    pub fn shown_query(&self, query: &str) -> ShownQuery {
+        if !self.ellipsize_query {
+            return ShownQuery::whole(query);
+        }
+        let text_width = self
+            .width
+            .saturating_sub(2 * (u32::from(self.padding) + u32::from(self.search.padding)));
+        let column = self.search.common.text_size() as f32 * entry::COLUMN_WIDTH;
+        ShownQuery::new(query, (text_width as f32 / column) as usize)
+    }
+
+    // height of the window before there are any results
+    pub fn starting_height(&self) -> u32 {
+        self.search.starting_height() + 2 * u32::from(self.padding)
+    }
+
+    pub fn window_level(&self) -> iced::window::Level {
+        if self.always_on_top {
+            iced::window::Level::AlwaysOnTop
+        } else {
+            iced::window::Level::Normal
+        }
+    }
+
+    // where to open a window of the given size. If the monitor under
+    // the cursor cannot be found, the window is centered instead
+    pub fn window_position(&self, size: (u32, u32)) -> iced::window::Position {
+        match self.position {
+            WindowPosition::Default => iced::window::Position::Default,
+            WindowPosition::Center => iced::window::Position::Centered,
+            WindowPosition::Cursor => match platform::center_on_cursor(size) {
+                Some((x, y)) => iced::window::Position::Specific(x, y),
+                None => {
+                    ::log::warn!("Could not find monitor under cursor, centering window");
+                    iced::window::Position::Centered
+                }
+            },
         }
     }
 }
@@ -95,6 +350,110 @@ impl Default for SearchSettings {
     }
 }
 
+// the query as it is displayed in the search box. A long query has
+// its middle replaced by an ellipsis, so that both its start and the
+// end being typed at stay visible
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShownQuery {
+    pub text: String,
+    // bytes of the query kept before and after the ellipsis
+    head: usize,
+    tail: usize,
+}
+
+impl ShownQuery {
+    fn whole(query: &str) -> Self {
+        Self {
+            text: query.to_string(),
+            head: query.len(),
+            tail: 0,
+        }
+    }
+
+    // fit the query into max_width columns. Text is only cut between
+    // grapheme clusters, and the end of the query gets the larger share
+    pub fn new(query: &str, max_width: usize) -> Self {
+        if query.width() <= max_width || max_width < 3 {
+            return Self::whole(query);
+        }
+
+        // leave room for the ellipsis
+        let budget = max_width - 1;
+        let graphemes: Vec<&str> = query.graphemes(true).collect();
+
+        let mut tail_width = 0;
+        let mut tail = 0;
+        for grapheme in graphemes.iter().rev() {
+            if tail_width + grapheme.width() > budget - budget / 2 {
+                break;
+            }
+            tail_width += grapheme.width();
+            tail += grapheme.len();
+        }
+
+        let mut head_width = 0;
+        let mut head = 0;
+        for grapheme in graphemes.iter() {
+            if head_width + grapheme.width() > budget - tail_width {
+                break;
+            }
+            head_width += grapheme.width();
+            head += grapheme.len();
+        }
+
+        let text = format!("{}…{}", &query[..head], &query[query.len() - tail..]);
+        Self { text, head, tail }
+    }
+
+    // the whole query after the shown text was edited to `edited`.
+    // Only the part of the shown text that changed is replaced, and
+    // removing the ellipsis removes the hidden middle of the query
+    pub fn edit(&self, query: &str, edited: &str) -> String {
+        if self.text.len() == self.head + self.tail {
+            return edited.to_string();
+        }
+
+        let shown = self.text.as_str();
+        let prefix: usize = shown
+            .chars()
+            .zip(edited.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        // the suffix cannot overlap the prefix
+        let limit = shown.len().min(edited.len());
+        let suffix: usize = shown
+            .chars()
+            .rev()
+            .zip(edited.chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .scan(prefix, |len, c| {
+                *len += c;
+                (*len <= limit).then_some(c)
+            })
+            .sum();
+
+        // positions after the ellipsis are counted from the end
+        let to_query = |i: usize| {
+            if i <= self.head {
+                i
+            } else {
+                query.len() - (shown.len() - i)
+            }
+        };
+        let start = to_query(prefix);
+        let end = to_query(shown.len() - suffix);
+
+        format!(
+            "{}{}{}",
+            &query[..start],
+            &edited[prefix..edited.len() - suffix],
+            &query[end..]
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Color(pub csscolorparser::Color);
 
@@ -125,6 +484,16 @@ impl<'de> serde::Deserialize<'de> for Color {
     }
 }
 
+// colors are written back as hex, like "#5e7ce2"
+impl serde::Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serde::Serialize::serialize(&self.0, serializer)
+    }
+}
+
 impl From<Color> for iced::Color {
     fn from(value: Color) -> Self {
         Self {
@@ -154,4 +523,155 @@ mod tests {
 
         assert_eq!(child.text_size(), parent.text_size());
     }
+
+    #[test]
+    fn text_size_precedence() {
+        let sizes = |text: &str| {
+            let mut settings: UISettings = toml::from_str(text).unwrap();
+            settings.propagate();
+            (
+                settings.common.text_size(),
+                settings.search.common.text_size(),
+                settings.entry.text_size(),
+            )
+        };
+
+        assert_eq!(sizes(""), (20, 20, 20));
+        assert_eq!(sizes("text_size = 30"), (30, 30, 30));
+
+        // explicit child sizes win over the parent, in either order
+        assert_eq!(sizes("search.text_size = 40"), (20, 40, 20));
+        assert_eq!(sizes("entry.text_size = 10"), (20, 20, 10));
+        assert_eq!(sizes("text_size = 30\nsearch.text_size = 40"), (30, 40, 30));
+        assert_eq!(
+            sizes("search.text_size = 40\nentry.text_size = 10\ntext_size = 30"),
+            (30, 40, 10)
+        );
+
+        // a child that matches the default is still explicit
+        assert_eq!(sizes("text_size = 30\nentry.text_size = 20"), (30, 30, 20));
+    }
+
+    #[test]
+    fn padding_adds_to_starting_height() {
+        let settings = UISettings::default();
+        assert_eq!(settings.padding, 0);
+        assert_eq!(
+            settings.starting_height(),
+            settings.search.starting_height()
+        );
+
+        let settings: UISettings = toml::from_str("padding = 8").unwrap();
+        assert_eq!(
+            settings.starting_height(),
+            settings.search.starting_height() + 16
+        );
+    }
+
+    #[test]
+    fn always_on_top() {
+        let settings = UISettings::default();
+        assert!(!settings.always_on_top);
+        assert_eq!(settings.window_level(), iced::window::Level::Normal);
+
+        let settings: UISettings = toml::from_str("always_on_top = true").unwrap();
+        assert!(settings.always_on_top);
+        assert_eq!(settings.window_level(), iced::window::Level::AlwaysOnTop);
+    }
+
+    #[test]
+    fn max_height_forms() {
+        let parse = |text: &str| toml::from_str::<UISettings>(text).map(|s| s.max_height);
+
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(
+            parse("max_height = 400").unwrap(),
+            Some(MaxHeight::Pixels(400))
+        );
+        assert_eq!(
+            parse("max_height = \"400px\"").unwrap(),
+            Some(MaxHeight::Pixels(400))
+        );
+        assert_eq!(
+            parse("max_height = \"60%\"").unwrap(),
+            Some(MaxHeight::Percent(60.0))
+        );
+        assert_eq!(
+            parse("max_height = \" 12.5 % \"").unwrap(),
+            Some(MaxHeight::Percent(12.5))
+        );
+
+        for bad in ["\"tall\"", "\"60\"%", "\"0%\"", "\"150%\"", "\"px\"", "-5"] {
+            assert!(parse(&format!("max_height = {bad}")).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn max_height_resolves_against_screen() {
+        assert_eq!(MaxHeight::Pixels(400).resolve(None), Some(400));
+        assert_eq!(MaxHeight::Pixels(400).resolve(Some(1080)), Some(400));
+        assert_eq!(MaxHeight::Percent(60.0).resolve(Some(1080)), Some(648));
+        assert_eq!(MaxHeight::Percent(50.0).resolve(Some(1441)), Some(721));
+        assert_eq!(MaxHeight::Percent(60.0).resolve(None), None);
+
+        let settings: UISettings = toml::from_str("max_height = \"50%\"").unwrap();
+        assert_eq!(settings.max_height(Some(1000)), Some(500));
+        assert_eq!(settings.max_height(None), None);
+        assert_eq!(settings.max_height_warning(Some(1000)), None);
+        assert!(settings.max_height_warning(None).is_some());
+
+        // never smaller than the search box
+        let settings: UISettings = toml::from_str("max_height = 1").unwrap();
+        assert_eq!(settings.max_height(None), Some(settings.starting_height()));
+        assert_eq!(settings.max_height_warning(None), None);
+        assert_eq!(UISettings::default().max_height(Some(1000)), None);
+        assert_eq!(UISettings::default().max_height_warning(None), None);
+    }
+
+    #[test]
+    fn ellipsized_query() {
+        let query = "abcdefghij";
+        assert_eq!(ShownQuery::new(query, 10).text, query);
+        assert_eq!(ShownQuery::new(query, 2).text, query);
+
+        let shown = ShownQuery::new(query, 5);
+        assert_eq!(shown.text, "ab…ij");
+        // the end of the query gets the larger share
+        assert_eq!(ShownQuery::new(query, 6).text, "ab…hij");
+
+        // edits to what is shown are applied to the whole query
+        assert_eq!(shown.edit(query, "ab…ijk"), "abcdefghijk");
+        assert_eq!(shown.edit(query, "ab…i"), "abcdefghi");
+        assert_eq!(shown.edit(query, "Xab…ij"), "Xabcdefghij");
+        assert_eq!(shown.edit(query, "abij"), "abij");
+        assert_eq!(shown.edit(query, "x"), "x");
+
+        // repeated characters are not mistaken for the edit
+        let query = "aaaaaaaaaa";
+        let shown = ShownQuery::new(query, 5);
+        assert_eq!(shown.edit(query, "aa…aaa"), "aaaaaaaaaaa");
+
+        // wide characters count as two columns
+        let query = "日本語のテキスト";
+        let shown = ShownQuery::new(query, 9);
+        assert_eq!(shown.text, "日本…スト");
+        assert_eq!(shown.edit(query, "日本…スト!"), "日本語のテキスト!");
+
+        // short queries are edited as is
+        let shown = ShownQuery::new("abc", 5);
+        assert_eq!(shown.edit("abc", "abd"), "abd");
+    }
+
+    #[test]
+    fn ellipsize_query_setting() {
+        let query = "x".repeat(200);
+        let mut settings = UISettings::default();
+        assert_eq!(settings.shown_query(&query).text, query);
+
+        settings.ellipsize_query = true;
+        let shown = settings.shown_query(&query);
+        assert!(shown.text.contains('…'));
+        assert!(shown.text.width() < query.width());
+        assert_eq!(settings.shown_query("short").text, "short");
+    }
 }
@@ -0,0 +1,38 @@
+// builds stores through the public api, the way benchmarks and other
+// code outside of jolly do
+
+use jolly::store::{Error, Store};
+
+#[test]
+fn entries_are_found_by_name() {
+    let store = Store::parse(
+        r#"['search docs']
+           url = 'https://docs.rs/%s'
+           keyword = 'd'
+
+           ['notes']
+           location = '/home/user/notes.txt'"#,
+    )
+    .unwrap();
+
+    let entry = store.entry_by_name("search docs").unwrap();
+    assert_eq!(entry.format_selection("d serde"), "https://docs.rs/serde");
+    assert_eq!(entry.format_name("d serde"), "search docs");
+
+    let entry = store.entry_by_name("notes").unwrap();
+    assert_eq!(entry.format_selection(""), "/home/user/notes.txt");
+
+    assert!(store.entry_by_name("missing").is_none());
+}
+
+#[test]
+fn parse_errors_can_be_named() {
+    assert!(matches!(
+        Store::parse("['foo']\nlocation = 'a'\nsystem = 'b'"),
+        Err(Error::ParseError(_))
+    ));
+    assert!(matches!(
+        Store::parse("not toml ["),
+        Err(Error::ParseError(_))
+    ));
+}